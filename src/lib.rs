@@ -0,0 +1,13 @@
+//! Binary tree and binary search tree structures built on `Rc<RefCell<_>>` nodes.
+//!
+//! * `structure::tree` - plain binary tree of `Node`.
+//! * `structure::bst` - binary search tree of `BstNode`, with the wrappers and variants built on it
+//!   next to it in `structure`.
+//! * `tool` - graphviz dot writers (feature `visualization`), the benchmark, model and fuzz harnesses
+//!   (feature `cli`) and a small deterministic random generator.
+//!
+//! The text format in `structure::serialize` is behind the `serialization` feature. Every feature is
+//! on by default, `default-features = false` leaves the tree code alone.
+
+pub mod structure;
+pub mod tool;
@@ -1,18 +1,169 @@
-mod structure;
-mod tool;
-
-use crate::structure::bst::{BstNode, BstNodeLink};
-use crate::structure::tree::{Node, NodeLink};
-use crate::tool::{
-    generate_dotfile, generate_dotfile_bst, generate_dotfile_bst_better, print_graph,
-};
+// the demo below prints the way the course material does
+#![allow(clippy::needless_borrow, clippy::println_empty_string)]
+
+use binarysearchtree::structure::bst::{BstNode, BstNodeLink};
+use binarysearchtree::structure::journal::{Journal, Operation};
+use binarysearchtree::structure::metrics;
+use binarysearchtree::structure::observer;
+use binarysearchtree::structure::tree::{Node, NodeLink};
+use binarysearchtree::tool::bench::{self, KeyStream};
+use binarysearchtree::tool::fuzz::{self, FuzzTarget};
+use binarysearchtree::tool::model;
+use binarysearchtree::tool::rng::XorShift64;
+use binarysearchtree::tool::{generate_dotfile, generate_dotfile_bst_better, print_graph};
 
 fn main() {
+    //RUST_LOG=binarysearchtree=debug routes the tree events to stderr
+    observer::init_from_env();
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("metrics") => return run_metrics(&args[2..]),
+        Some("bench") => return run_bench(&args[2..]),
+        Some("model") => return run_model(&args[2..]),
+        Some("fuzz") => return run_fuzz(&args[2..]),
+        _ => {}
+    }
     //turn on to test the old code
     //test_binary_tree();
     test_binary_search_tree();
 }
 
+//metrics [count] [random|sorted]: insert, search and delete count keys, then print the counters
+fn run_metrics(args: &[String]) {
+    let count: usize = args.first().and_then(|x| x.parse().ok()).unwrap_or(1000);
+    let order: &str = args.get(1).map(String::as_str).unwrap_or("random");
+    let mut keys: Vec<i32> = (0..count as i32).collect();
+    match order {
+        "random" => XorShift64::new(42).shuffle(&mut keys),
+        "sorted" => {}
+        _ => {
+            eprintln!("unknown key order {}, expected random or sorted", order);
+            return;
+        }
+    }
+    if keys.is_empty() {
+        println!("nothing to measure for 0 keys");
+        return;
+    }
+
+    metrics::reset();
+    let rootlink: BstNodeLink = BstNode::new_bst_nodelink(keys[0]);
+    for key in keys.iter().skip(1) {
+        metrics::measure("insert", || BstNode::tree_insert(&rootlink, key));
+    }
+    for key in keys.iter() {
+        metrics::measure("search", || BstNode::tree_search_link(&rootlink, key));
+    }
+    //the root is deleted last, a BstNode cannot delete itself out of its own handle
+    for key in keys.iter().skip(1) {
+        metrics::measure("delete", || {
+            let node: Option<BstNodeLink> = BstNode::tree_search_link(&rootlink, key);
+            node.map(|x| BstNode::tree_delete_node(&x))
+        });
+    }
+    println!("{} keys in {} order", count, order);
+    println!("{}", metrics::report());
+}
+
+//bench [count] [stream] [csv path]: time every structure against BTreeMap, stream defaults to all
+fn run_bench(args: &[String]) {
+    let count: usize = args.first().and_then(|x| x.parse().ok()).unwrap_or(1000);
+    let streams: Vec<KeyStream> = match args.get(1).map(String::as_str) {
+        None | Some("all") => KeyStream::all().to_vec(),
+        Some(name) => match KeyStream::parse(name) {
+            Some(stream) => vec![stream],
+            None => {
+                eprintln!(
+                    "unknown key stream {}, expected all, random, sorted, reverse or zipfian",
+                    name
+                );
+                return;
+            }
+        },
+    };
+    let report: bench::BenchReport = bench::run(count, &streams, 42, bench::default_subjects);
+    println!("{} keys per stream", count);
+    print!("{}", report);
+    match args.get(2) {
+        Some(path) => match std::fs::write(path, report.to_csv()) {
+            Ok(()) => println!("csv written to {}", path),
+            Err(err) => eprintln!("cannot write {}: {}", path, err),
+        },
+        None => print!("\n{}", report.to_csv()),
+    }
+}
+
+//model [cases] [length] [seed]: differential test of BstNode against BTreeSet
+fn run_model(args: &[String]) {
+    let cases: usize = args.first().and_then(|x| x.parse().ok()).unwrap_or(100);
+    let length: usize = args.get(1).and_then(|x| x.parse().ok()).unwrap_or(500);
+    let seed: u64 = args.get(2).and_then(|x| x.parse().ok()).unwrap_or(42);
+    silence_panics();
+    let failure: model::Counterexample = match model::check(seed, cases, length) {
+        None => {
+            println!(
+                "{} sequences of {} operations agree with BTreeSet",
+                cases, length
+            );
+            return;
+        }
+        Some(x) => x,
+    };
+    println!("mismatch at {}", failure.mismatch);
+    println!("minimal reproducer of {} operations:", failure.ops.len());
+    for op in failure.ops.iter() {
+        println!("    {}", op);
+    }
+    let failure_path: &str = "model_failure.dot";
+    if failure.write_dot(failure_path) {
+        println!("tree after the reproducer written to {}", failure_path);
+    }
+}
+
+//panics of the code under test are reported as failures, the default hook would only add noise.
+//The library leaves the hook alone, it is process global and tests run in parallel
+fn silence_panics() {
+    std::panic::set_hook(Box::new(|_| {}));
+}
+
+//fuzz <operations|tree> [iterations] [seed]: replay the seed corpus, then random mutations of it
+fn run_fuzz(args: &[String]) {
+    let target: FuzzTarget = match args.first().and_then(|x| FuzzTarget::parse(x)) {
+        Some(x) => x,
+        None => {
+            eprintln!("expected a fuzz target, operations or tree");
+            return;
+        }
+    };
+    let iterations: usize = args.get(1).and_then(|x| x.parse().ok()).unwrap_or(10000);
+    let seed: u64 = args.get(2).and_then(|x| x.parse().ok()).unwrap_or(42);
+    let corpus: Vec<Vec<u8>> = match fuzz::load_corpus(".", target) {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("cannot read fuzz/corpus/{}: {}", target.name(), err);
+            return;
+        }
+    };
+    silence_panics();
+    match fuzz::fuzz_corpus(target, &corpus, iterations, seed) {
+        None => println!(
+            "{} seeds and {} mutations of the {} target ran clean",
+            corpus.len(),
+            iterations,
+            target.name()
+        ),
+        Some((input, failure)) => {
+            println!("{}", failure);
+            println!("failing input {:?}", input);
+            let crash_path: String = format!("fuzz_crash_{}.bin", target.name());
+            match std::fs::write(&crash_path, &input) {
+                Ok(()) => println!("failing input written to {}", crash_path),
+                Err(err) => eprintln!("cannot write {}: {}", crash_path, err),
+            }
+        }
+    }
+}
+
 fn test_binary_search_tree() {
     let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
     {
@@ -148,8 +299,24 @@ fn test_binary_search_tree() {
     main_tree_path = "bst_graph_after_delete.dot";
     generate_dotfile_bst_better(&rootlink, main_tree_path);
     print_graph(&rootlink);
+
+    // --- TEST journal undo/redo ---
+    let mut journal: Journal = Journal::new(Some(rootlink.clone()));
+    journal.checkpoint("before root delete");
+    let root_deleted: bool = journal.tree_delete_with_key(15);
+    println!(
+        "\nDelete root node 15 through the journal: {}",
+        root_deleted
+    );
+    print_graph(&journal.root().unwrap());
+
+    let undone: Option<Operation> = journal.undo();
+    println!("\nUndo {:?}", undone);
+    print_graph(&journal.root().unwrap());
 }
 
+//only called when the old code is turned on in main
+#[allow(dead_code)]
 fn test_binary_tree() {
     //create the nodelink of the root node
     let rootlink: NodeLink = Node::new_nodelink(5);
@@ -217,8 +384,8 @@ fn test_binary_tree() {
         another_left_subtree
     );
 
-    //Discard the right subtree from parent
-    let rootlink2 = rootlink.borrow().get_nodelink_copy();
+    //Discard the left subtree from a copy, a deep one so the original tree keeps its subtree
+    let rootlink2 = Node::deep_clone(&rootlink);
 
     let flag = rootlink2.borrow_mut().discard_node_by_value(3);
     println!("status of node deletion: {0}", flag);
@@ -0,0 +1,524 @@
+use crate::structure::bst::{BstNode, BstNodeLink, WeakBstNodeLink};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+//this package implement subtree augmentation for BstNode trees.
+//An Augment describes a per node value computed bottom-up from the node and both child values,
+//AugmentedBst keeps those values correct through tree_insert, transplant and tree_delete
+//and answers range queries with them in O(height).
+
+/// Per node value recomputed bottom-up, e.g. subtree size, sum, minimum or maximum.
+///
+/// `combine` must only depend on its arguments, range queries call it on partial subtrees too.
+pub trait Augment {
+    type Value: Clone;
+
+    fn combine(
+        left: Option<&Self::Value>,
+        node: &BstNode,
+        right: Option<&Self::Value>,
+    ) -> Self::Value;
+}
+
+/// Number of nodes in the subtree.
+pub struct SubtreeSize;
+
+impl Augment for SubtreeSize {
+    type Value = usize;
+
+    fn combine(left: Option<&usize>, _node: &BstNode, right: Option<&usize>) -> usize {
+        left.copied().unwrap_or(0) + 1 + right.copied().unwrap_or(0)
+    }
+}
+
+/// Sum of the keys in the subtree, NIL keys count as zero.
+pub struct SubtreeSum;
+
+impl Augment for SubtreeSum {
+    type Value = i64;
+
+    fn combine(left: Option<&i64>, node: &BstNode, right: Option<&i64>) -> i64 {
+        left.copied().unwrap_or(0) + node.key.map_or(0, i64::from) + right.copied().unwrap_or(0)
+    }
+}
+
+/// Smallest key in the subtree, NIL keys are ignored.
+pub struct SubtreeMin;
+
+impl Augment for SubtreeMin {
+    type Value = Option<i32>;
+
+    fn combine(
+        left: Option<&Option<i32>>,
+        node: &BstNode,
+        right: Option<&Option<i32>>,
+    ) -> Option<i32> {
+        [left.copied().flatten(), node.key, right.copied().flatten()]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+}
+
+/// Greatest key in the subtree, NIL keys are ignored.
+pub struct SubtreeMax;
+
+impl Augment for SubtreeMax {
+    type Value = Option<i32>;
+
+    fn combine(
+        left: Option<&Option<i32>>,
+        node: &BstNode,
+        right: Option<&Option<i32>>,
+    ) -> Option<i32> {
+        [left.copied().flatten(), node.key, right.copied().flatten()]
+            .into_iter()
+            .flatten()
+            .max()
+    }
+}
+
+/**
+ * Lowest node whose subtree changes when z is deleted with BstNode::tree_delete_node,
+ * every node whose subtree changes lies on the path from it to the root
+ * replacement is what BstNode::tree_delete_replacement returned for z before the delete
+ */
+pub fn lowest_changed_by_delete(
+    z: &BstNodeLink,
+    replacement: &Option<BstNodeLink>,
+) -> Option<BstNodeLink> {
+    let has_both_children: bool = z.borrow().left.is_some() && z.borrow().right.is_some();
+    if !has_both_children {
+        return BstNode::get_parent(z);
+    }
+    let successor: BstNodeLink = replacement.clone().unwrap();
+    let successor_parent: Option<BstNodeLink> = BstNode::get_parent(&successor);
+    if successor_parent.as_ref().is_some_and(|x| Rc::ptr_eq(x, z)) {
+        Some(successor)
+    } else {
+        successor_parent
+    }
+}
+
+fn node_id(node: &BstNodeLink) -> usize {
+    node.as_ptr() as usize
+}
+
+//the weak link pins the allocation, so the address of a dropped node is not handed
+//to a new node while its entry is still in the table
+struct Entry<V> {
+    node: WeakBstNodeLink,
+    value: V,
+}
+
+/// BstNode tree owning its root handle, with an `Augment` value kept for every node.
+///
+/// Nodes the wrapper has not seen, e.g. inserted through `BstNode::tree_insert` directly,
+/// get their value computed from their subtree. Their ancestors keep the old values
+/// until `refresh_upward` is called on the changed node.
+pub struct AugmentedBst<A: Augment> {
+    root: Option<BstNodeLink>,
+    values: HashMap<usize, Entry<A::Value>>,
+    augment: PhantomData<A>,
+}
+
+impl<A: Augment> Default for AugmentedBst<A> {
+    fn default() -> Self {
+        AugmentedBst {
+            root: None,
+            values: HashMap::new(),
+            augment: PhantomData,
+        }
+    }
+}
+
+impl<A: Augment> AugmentedBst<A> {
+    pub fn new() -> Self {
+        AugmentedBst::default()
+    }
+
+    /**
+     * Take over an existing tree, every value is computed once bottom-up
+     */
+    pub fn from_root(root: BstNodeLink) -> Self {
+        let mut augmented: AugmentedBst<A> = AugmentedBst::new();
+        augmented.refresh_subtree(&root);
+        augmented.root = Some(root);
+        augmented
+    }
+
+    pub fn root(&self) -> Option<BstNodeLink> {
+        self.root.clone()
+    }
+
+    /**
+     * Replace the root handle, for variants whose rotations move the root
+     */
+    pub fn set_root(&mut self, root: Option<BstNodeLink>) {
+        self.root = root;
+    }
+
+    /**
+     * Augmented value of the subtree rooted at node, computed on the spot if the node has no entry
+     */
+    pub fn value(&self, node: &BstNodeLink) -> Option<A::Value> {
+        match self.stored(node) {
+            Some(value) => Some(value.clone()),
+            None => Some(self.compute(node)),
+        }
+    }
+
+    //the entry of node, an entry left by another node at the same address is ignored
+    fn stored(&self, node: &BstNodeLink) -> Option<&A::Value> {
+        self.values
+            .get(&node_id(node))
+            .filter(|x| std::ptr::eq(x.node.as_ptr(), Rc::as_ptr(node)))
+            .map(|x| &x.value)
+    }
+
+    //value of node from the values of its children, without storing anything
+    fn compute(&self, node: &BstNodeLink) -> A::Value {
+        let borrowed = node.borrow();
+        let left: Option<A::Value> = borrowed.left.as_ref().and_then(|x| self.value(x));
+        let right: Option<A::Value> = borrowed.right.as_ref().and_then(|x| self.value(x));
+        A::combine(left.as_ref(), &borrowed, right.as_ref())
+    }
+
+    /**
+     * Augmented value of the whole tree
+     */
+    pub fn root_value(&self) -> Option<A::Value> {
+        self.root.as_ref().and_then(|x| self.value(x))
+    }
+
+    /**
+     * Recompute the value of a single node from its children, which must be up to date
+     */
+    pub fn refresh(&mut self, node: &BstNodeLink) {
+        let value: A::Value = self.compute(node);
+        let entry: Entry<A::Value> = Entry {
+            node: Rc::downgrade(node),
+            value,
+        };
+        self.values.insert(node_id(node), entry);
+    }
+
+    //drop the entries of node and its subtree, the subtree of keep excluded
+    fn forget_subtree(&mut self, node: &BstNodeLink, keep: &Option<BstNodeLink>) {
+        let mut stack: Vec<BstNodeLink> = vec![node.clone()];
+        while let Some(x) = stack.pop() {
+            if keep.as_ref().is_some_and(|k| Rc::ptr_eq(k, &x)) {
+                continue;
+            }
+            self.values.remove(&node_id(&x));
+            let borrowed = x.borrow();
+            stack.extend(borrowed.left.clone());
+            stack.extend(borrowed.right.clone());
+        }
+    }
+
+    /**
+     * Recompute node and every ancestor up to the root
+     */
+    pub fn refresh_upward(&mut self, node: Option<BstNodeLink>) {
+        let mut current: Option<BstNodeLink> = node;
+        while let Some(x) = current {
+            self.refresh(&x);
+            current = BstNode::get_parent(&x);
+        }
+    }
+
+    /**
+     * Recompute every node of the subtree, children first
+     */
+    pub fn refresh_subtree(&mut self, node: &BstNodeLink) {
+        let left: Option<BstNodeLink> = node.borrow().left.clone();
+        let right: Option<BstNodeLink> = node.borrow().right.clone();
+        if let Some(ref x) = left {
+            self.refresh_subtree(x);
+        }
+        if let Some(ref x) = right {
+            self.refresh_subtree(x);
+        }
+        self.refresh(node);
+    }
+
+    /// Inserts a key through `BstNode::tree_insert` and updates the values on its path.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(BstNodeLink)` with the new node if the insertion is successful.
+    /// * `None` if a node with the same key already exists.
+    pub fn tree_insert(&mut self, key: &i32) -> Option<BstNodeLink> {
+        let inserted: Option<BstNodeLink> = match self.root {
+            Some(ref root) => BstNode::tree_insert(root, key),
+            None => {
+                let node: BstNodeLink = BstNode::new_bst_nodelink(*key);
+                self.root = Some(node.clone());
+                Some(node)
+            }
+        };
+        self.refresh_upward(inserted.clone());
+        inserted
+    }
+
+    /// Runs `BstNode::transplant_node` and updates the values above `u`.
+    ///
+    /// When `u` is the root, `v` becomes the new root. The values inside `v` are kept as they are,
+    /// the entries of the rest of the detached subtree of `u` are dropped.
+    pub fn transplant(&mut self, u: &BstNodeLink, v: &Option<BstNodeLink>) -> bool {
+        let u_parent: Option<BstNodeLink> = BstNode::get_parent(u);
+        if !BstNode::transplant_node(u, v) {
+            return false;
+        }
+        if self.is_root(u) {
+            self.root = v.clone();
+        }
+        self.forget_subtree(u, v);
+        self.refresh_upward(u_parent);
+        true
+    }
+
+    /// Runs `BstNode::tree_delete_node` and updates the values above every moved node.
+    pub fn tree_delete(&mut self, z: &BstNodeLink) -> bool {
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(z);
+        let lowest: Option<BstNodeLink> = lowest_changed_by_delete(z, &replacement);
+        let is_root: bool = self.is_root(z);
+        if !BstNode::tree_delete_node(z) {
+            return false;
+        }
+        if is_root {
+            self.root = replacement;
+        }
+        self.values.remove(&node_id(z));
+        self.refresh_upward(lowest);
+        true
+    }
+
+    /// Deletes the node holding `key`.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the node is found and deleted.
+    /// * `false` if the node with the given key does not exist.
+    pub fn tree_delete_with_key(&mut self, key: i32) -> bool {
+        let node: Option<BstNodeLink> = self
+            .root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, &key));
+        match node {
+            Some(x) => self.tree_delete(&x),
+            None => false,
+        }
+    }
+
+    /**
+     * Aggregate of every key within low..=high, None if the range holds no key
+     */
+    pub fn range_value(&self, low: i32, high: i32) -> Option<A::Value> {
+        if low > high {
+            return None;
+        }
+        self.range_from(&self.root, low, high)
+    }
+
+    //aggregate of the keys of the subtree within low..=high
+    fn range_from(&self, node: &Option<BstNodeLink>, low: i32, high: i32) -> Option<A::Value> {
+        let x: &BstNodeLink = node.as_ref()?;
+        let borrowed = x.borrow();
+        let key: Option<i32> = borrowed.key;
+        if key < Some(low) {
+            return self.range_from(&borrowed.right, low, high);
+        }
+        if key > Some(high) {
+            return self.range_from(&borrowed.left, low, high);
+        }
+        //the node splits the range, each side is bounded on one end only
+        let left: Option<A::Value> = self.at_least(&borrowed.left, low);
+        let right: Option<A::Value> = self.at_most(&borrowed.right, high);
+        Some(A::combine(left.as_ref(), &borrowed, right.as_ref()))
+    }
+
+    //aggregate of the keys of the subtree greater than or equal to low
+    fn at_least(&self, node: &Option<BstNodeLink>, low: i32) -> Option<A::Value> {
+        let x: &BstNodeLink = node.as_ref()?;
+        let borrowed = x.borrow();
+        if borrowed.key < Some(low) {
+            return self.at_least(&borrowed.right, low);
+        }
+        let left: Option<A::Value> = self.at_least(&borrowed.left, low);
+        let right: Option<A::Value> = borrowed.right.as_ref().and_then(|r| self.value(r));
+        Some(A::combine(left.as_ref(), &borrowed, right.as_ref()))
+    }
+
+    //aggregate of the keys of the subtree smaller than or equal to high
+    fn at_most(&self, node: &Option<BstNodeLink>, high: i32) -> Option<A::Value> {
+        let x: &BstNodeLink = node.as_ref()?;
+        let borrowed = x.borrow();
+        if borrowed.key > Some(high) {
+            return self.at_most(&borrowed.left, high);
+        }
+        let left: Option<A::Value> = borrowed.left.as_ref().and_then(|l| self.value(l));
+        let right: Option<A::Value> = self.at_most(&borrowed.right, high);
+        Some(A::combine(left.as_ref(), &borrowed, right.as_ref()))
+    }
+
+    fn is_root(&self, node: &BstNodeLink) -> bool {
+        self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, node))
+    }
+}
+
+impl AugmentedBst<SubtreeSum> {
+    /**
+     * Sum of every key within low..=high
+     */
+    pub fn range_sum(&self, low: i32, high: i32) -> i64 {
+        self.range_value(low, high).unwrap_or(0)
+    }
+}
+
+impl AugmentedBst<SubtreeMin> {
+    /**
+     * Smallest key within low..=high
+     */
+    pub fn range_min(&self, low: i32, high: i32) -> Option<i32> {
+        self.range_value(low, high).flatten()
+    }
+}
+
+impl AugmentedBst<SubtreeMax> {
+    /**
+     * Greatest key within low..=high
+     */
+    pub fn range_max(&self, low: i32, high: i32) -> Option<i32> {
+        self.range_value(low, high).flatten()
+    }
+}
+
+impl AugmentedBst<SubtreeSize> {
+    /**
+     * Amount of keys within low..=high
+     */
+    pub fn range_count(&self, low: i32, high: i32) -> usize {
+        self.range_value(low, high).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::binary_tree;
+
+    fn sized(keys: &[i32]) -> AugmentedBst<SubtreeSize> {
+        let mut tree: AugmentedBst<SubtreeSize> = AugmentedBst::new();
+        for key in keys {
+            tree.tree_insert(key);
+        }
+        tree
+    }
+
+    fn scan(keys: &[i32], low: i32, high: i32) -> Vec<i32> {
+        keys.iter()
+            .copied()
+            .filter(|x| low <= *x && *x <= high)
+            .collect()
+    }
+
+    #[test]
+    fn range_queries_match_a_scan() {
+        let keys: Vec<i32> = vec![15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9];
+        let counted: AugmentedBst<SubtreeSize> = sized(&keys);
+        let mut summed: AugmentedBst<SubtreeSum> = AugmentedBst::new();
+        let mut lowest: AugmentedBst<SubtreeMin> = AugmentedBst::new();
+        let mut highest: AugmentedBst<SubtreeMax> = AugmentedBst::new();
+        for key in keys.iter() {
+            summed.tree_insert(key);
+            lowest.tree_insert(key);
+            highest.tree_insert(key);
+        }
+        for (low, high) in [(0, 30), (4, 15), (5, 5), (7, 7), (10, 12), (21, 40), (9, 3)] {
+            let inside: Vec<i32> = scan(&keys, low, high);
+            assert_eq!(counted.range_count(low, high), inside.len());
+            assert_eq!(
+                summed.range_sum(low, high),
+                inside.iter().map(|x| i64::from(*x)).sum::<i64>()
+            );
+            assert_eq!(lowest.range_min(low, high), inside.iter().copied().min());
+            assert_eq!(highest.range_max(low, high), inside.iter().copied().max());
+        }
+        assert_eq!(summed.root_value(), Some(114));
+        assert_eq!(AugmentedBst::<SubtreeSum>::new().range_sum(0, 10), 0);
+    }
+
+    #[test]
+    fn deletes_keep_the_values_correct() {
+        //a small linear congruential generator keeps the run reproducible
+        let mut state: u64 = 3;
+        let mut below = |bound: u64| -> u64 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        let mut tree: AugmentedBst<SubtreeSum> = AugmentedBst::new();
+        let mut keys: Vec<i32> = Vec::new();
+        for _ in 0..400 {
+            let key: i32 = below(120) as i32;
+            if below(3) == 0 {
+                assert_eq!(tree.tree_delete_with_key(key), keys.contains(&key));
+                keys.retain(|x| *x != key);
+            } else if tree.tree_insert(&key).is_some() {
+                keys.push(key);
+            }
+            let (low, high) = (below(130) as i32, below(130) as i32);
+            let expected: i64 = scan(&keys, low, high).iter().map(|x| i64::from(*x)).sum();
+            assert_eq!(tree.range_sum(low, high), expected);
+        }
+    }
+
+    #[test]
+    fn from_root_computes_the_existing_tree() {
+        let root: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 2, 3, 4, 5]).unwrap();
+        let mut tree: AugmentedBst<SubtreeMax> = AugmentedBst::from_root(root.clone());
+        assert_eq!(tree.root_value(), Some(Some(5)));
+        assert_eq!(tree.range_max(1, 3), Some(3));
+        let left: BstNodeLink = root.borrow().left.clone().unwrap();
+        assert_eq!(tree.value(&left), Some(Some(2)));
+        tree.set_root(None);
+        assert!(tree.root_value().is_none());
+        assert!(tree.range_max(1, 5).is_none());
+    }
+
+    #[test]
+    fn entries_follow_the_nodes_in_the_tree() {
+        let mut tree: AugmentedBst<SubtreeSize> = sized(&[15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9]);
+        let six: BstNodeLink = BstNode::tree_search_link(&tree.root().unwrap(), &6).unwrap();
+        let seven: Option<BstNodeLink> = six.borrow().right.clone();
+        //6 and its left subtree 3(2,4) leave the tree, 7(,13(9,)) takes their place
+        assert!(tree.transplant(&six, &seven));
+        assert_eq!(tree.values.len(), 7);
+        assert_eq!(tree.root_value(), Some(7));
+        assert!(tree.tree_delete_with_key(15));
+        assert_eq!(tree.values.len(), 6);
+        let root: BstNodeLink = tree.root().unwrap();
+        assert_eq!(tree.values.len(), binary_tree::count_nodes(&root));
+        assert_eq!(tree.root_value(), Some(6));
+    }
+
+    #[test]
+    fn nodes_changed_outside_the_wrapper() {
+        let mut tree: AugmentedBst<SubtreeSize> = sized(&[10, 5, 15]);
+        let root: BstNodeLink = tree.root().unwrap();
+        let inserted: BstNodeLink = BstNode::tree_insert(&root, &7).unwrap();
+        //the new node has no entry, its value comes from its subtree
+        assert_eq!(tree.value(&inserted), Some(1));
+        assert_eq!(tree.root_value(), Some(3));
+        tree.refresh_upward(Some(inserted));
+        assert_eq!(tree.root_value(), Some(4));
+
+        let detached: BstNodeLink = BstNode::new_bst_nodelink(1);
+        BstNode::tree_insert(&detached, &2);
+        assert_eq!(tree.value(&detached), Some(2));
+    }
+}
@@ -0,0 +1,383 @@
+use crate::structure::bst::BstNode;
+use crate::structure::tree::Node;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+//this package implement the algorithms shared by tree::Node and bst::BstNode.
+//Both node types are reached through Rc<RefCell<_>> links with a weak parent, BinaryTreeNode
+//exposes exactly that, everything else here is written once against the trait.
+
+pub type TreeLink<T> = Rc<RefCell<T>>;
+
+/// Accessors every binary tree node of the crate provides.
+pub trait BinaryTreeNode: Sized {
+    fn left(&self) -> Option<TreeLink<Self>>;
+
+    fn right(&self) -> Option<TreeLink<Self>>;
+
+    /// The parent upgraded to a strong link, `None` for a root or a dropped parent.
+    fn parent(&self) -> Option<TreeLink<Self>>;
+
+    /// Value compared by the structural equality and hash, `None` for a NIL key.
+    fn key(&self) -> Option<i32>;
+
+    /// Text shown for the node in graphs.
+    fn label(&self) -> String;
+}
+
+impl BinaryTreeNode for Node {
+    fn left(&self) -> Option<TreeLink<Self>> {
+        self.left.clone()
+    }
+
+    fn right(&self) -> Option<TreeLink<Self>> {
+        self.right.clone()
+    }
+
+    fn parent(&self) -> Option<TreeLink<Self>> {
+        self.parent.as_ref().and_then(|x| x.upgrade())
+    }
+
+    fn key(&self) -> Option<i32> {
+        Some(self.value)
+    }
+
+    fn label(&self) -> String {
+        self.value.to_string()
+    }
+}
+
+impl BinaryTreeNode for BstNode {
+    fn left(&self) -> Option<TreeLink<Self>> {
+        self.left.clone()
+    }
+
+    fn right(&self) -> Option<TreeLink<Self>> {
+        self.right.clone()
+    }
+
+    fn parent(&self) -> Option<TreeLink<Self>> {
+        self.parent.as_ref().and_then(|x| x.upgrade())
+    }
+
+    fn key(&self) -> Option<i32> {
+        self.key
+    }
+
+    fn label(&self) -> String {
+        match self.key {
+            Some(key) => key.to_string(),
+            None => "NIL".to_string(),
+        }
+    }
+}
+
+/// Preorder walk yielding the live nodes, see `preorder`.
+pub struct PreorderIter<T: BinaryTreeNode> {
+    stack: Vec<TreeLink<T>>,
+}
+
+impl<T: BinaryTreeNode> Iterator for PreorderIter<T> {
+    type Item = TreeLink<T>;
+
+    fn next(&mut self) -> Option<TreeLink<T>> {
+        let node: TreeLink<T> = self.stack.pop()?;
+        //right first, so the left subtree comes out first
+        let (left, right) = children(&node);
+        self.stack.extend(right);
+        self.stack.extend(left);
+        Some(node)
+    }
+}
+
+/// Inorder walk yielding the live nodes, see `inorder`.
+pub struct InorderIter<T: BinaryTreeNode> {
+    stack: Vec<TreeLink<T>>,
+    current: Option<TreeLink<T>>,
+}
+
+impl<T: BinaryTreeNode> Iterator for InorderIter<T> {
+    type Item = TreeLink<T>;
+
+    fn next(&mut self) -> Option<TreeLink<T>> {
+        while let Some(x) = self.current.take() {
+            self.current = x.borrow().left();
+            self.stack.push(x);
+        }
+        let node: TreeLink<T> = self.stack.pop()?;
+        self.current = node.borrow().right();
+        Some(node)
+    }
+}
+
+/// Postorder walk yielding the live nodes, see `postorder`.
+pub struct PostorderIter<T: BinaryTreeNode> {
+    //the flag tells whether both children of the node were pushed already
+    stack: Vec<(TreeLink<T>, bool)>,
+}
+
+impl<T: BinaryTreeNode> Iterator for PostorderIter<T> {
+    type Item = TreeLink<T>;
+
+    fn next(&mut self) -> Option<TreeLink<T>> {
+        loop {
+            let (node, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(node);
+            }
+            let (left, right) = children(&node);
+            self.stack.push((node, true));
+            if let Some(x) = right {
+                self.stack.push((x, false));
+            }
+            if let Some(x) = left {
+                self.stack.push((x, false));
+            }
+        }
+    }
+}
+
+/// Level order walk yielding the live nodes, see `level_order`.
+pub struct LevelOrderIter<T: BinaryTreeNode> {
+    queue: VecDeque<TreeLink<T>>,
+}
+
+impl<T: BinaryTreeNode> Iterator for LevelOrderIter<T> {
+    type Item = TreeLink<T>;
+
+    fn next(&mut self) -> Option<TreeLink<T>> {
+        let node: TreeLink<T> = self.queue.pop_front()?;
+        let (left, right) = children(&node);
+        self.queue.extend(left);
+        self.queue.extend(right);
+        Some(node)
+    }
+}
+
+//both children, the node is borrowed only for the copy
+fn children<T: BinaryTreeNode>(node: &TreeLink<T>) -> (Option<TreeLink<T>>, Option<TreeLink<T>>) {
+    let borrowed = node.borrow();
+    (borrowed.left(), borrowed.right())
+}
+
+pub fn preorder<T: BinaryTreeNode>(node: &TreeLink<T>) -> PreorderIter<T> {
+    PreorderIter {
+        stack: vec![node.clone()],
+    }
+}
+
+pub fn inorder<T: BinaryTreeNode>(node: &TreeLink<T>) -> InorderIter<T> {
+    InorderIter {
+        stack: Vec::new(),
+        current: Some(node.clone()),
+    }
+}
+
+pub fn postorder<T: BinaryTreeNode>(node: &TreeLink<T>) -> PostorderIter<T> {
+    PostorderIter {
+        stack: vec![(node.clone(), false)],
+    }
+}
+
+pub fn level_order<T: BinaryTreeNode>(node: &TreeLink<T>) -> LevelOrderIter<T> {
+    LevelOrderIter {
+        queue: VecDeque::from([node.clone()]),
+    }
+}
+
+/**
+ * Amount of nodes in the subtree, node included
+ */
+pub fn count_nodes<T: BinaryTreeNode>(node: &TreeLink<T>) -> usize {
+    preorder(node).count()
+}
+
+/**
+ * Edges on the longest downward path from node, a leaf has height 0
+ */
+pub fn height<T: BinaryTreeNode>(node: &TreeLink<T>) -> usize {
+    //deepest level reached by a walk that carries the depth along, no recursion on degenerate trees
+    let mut height: usize = 0;
+    let mut stack: Vec<(TreeLink<T>, usize)> = vec![(node.clone(), 0)];
+    while let Some((x, depth)) = stack.pop() {
+        height = height.max(depth);
+        let (left, right) = children(&x);
+        stack.extend(left.map(|child| (child, depth + 1)));
+        stack.extend(right.map(|child| (child, depth + 1)));
+    }
+    height
+}
+
+/**
+ * Edges between node and the root of its tree, the root has depth 0
+ */
+pub fn depth<T: BinaryTreeNode>(node: &TreeLink<T>) -> usize {
+    let mut depth: usize = 0;
+    let mut current: TreeLink<T> = node.clone();
+    loop {
+        let parent: Option<TreeLink<T>> = current.borrow().parent();
+        match parent {
+            Some(x) => {
+                depth += 1;
+                current = x;
+            }
+            None => return depth,
+        }
+    }
+}
+
+//children on the same side of two nodes compared with each other
+type ChildPair<T> = (Option<TreeLink<T>>, Option<TreeLink<T>>);
+
+/**
+ * Same shape with the same key in every position, parent pointers are not compared.
+ * Backs the PartialEq impls of both node types
+ */
+pub fn structurally_equal<T: BinaryTreeNode>(first: &T, second: &T) -> bool {
+    if first.key() != second.key() {
+        return false;
+    }
+    let mut stack: Vec<ChildPair<T>> = vec![
+        (first.left(), second.left()),
+        (first.right(), second.right()),
+    ];
+    while let Some(pair) = stack.pop() {
+        match pair {
+            (None, None) => {}
+            (Some(a), Some(b)) => {
+                //a shared subtree is equal to itself
+                if Rc::ptr_eq(&a, &b) {
+                    continue;
+                }
+                let (a, b) = (a.borrow(), b.borrow());
+                if a.key() != b.key() {
+                    return false;
+                }
+                stack.push((a.left(), b.left()));
+                stack.push((a.right(), b.right()));
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+/**
+ * Feed the keys of the subtree to state in preorder, marking missing children
+ * so 1(2,) and 1(,2) hash apart. Backs the Hash impls of both node types
+ */
+pub fn hash_structure<T: BinaryTreeNode, H: Hasher>(node: &T, state: &mut H) {
+    node.key().hash(state);
+    let mut stack: Vec<Option<TreeLink<T>>> = vec![node.right(), node.left()];
+    while let Some(child) = stack.pop() {
+        match child {
+            Some(x) => {
+                true.hash(state);
+                let borrowed = x.borrow();
+                borrowed.key().hash(state);
+                stack.push(borrowed.right());
+                stack.push(borrowed.left());
+            }
+            None => false.hash(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNodeLink;
+    use crate::structure::tree::NodeLink;
+
+    fn labels<T: BinaryTreeNode>(nodes: impl Iterator<Item = TreeLink<T>>) -> Vec<String> {
+        nodes.map(|x| x.borrow().label()).collect()
+    }
+
+    //the same shape for both node types, 5(3(2,4),7(,10))
+    fn sample_trees() -> (NodeLink, BstNodeLink) {
+        let node: NodeLink = Node::from_parenthesized("5(3(2,4),7(,10))")
+            .unwrap()
+            .unwrap();
+        let bst: BstNodeLink = BstNode::try_from_nodelink(&node).unwrap();
+        (node, bst)
+    }
+
+    #[test]
+    fn traversals_agree_across_node_types() {
+        let (node, bst) = sample_trees();
+        assert_eq!(labels(preorder(&node)), labels(preorder(&bst)));
+        assert_eq!(labels(inorder(&node)), labels(inorder(&bst)));
+        assert_eq!(labels(postorder(&node)), labels(postorder(&bst)));
+        assert_eq!(labels(level_order(&bst)), ["5", "3", "7", "2", "4", "10"]);
+        assert_eq!(labels(postorder(&bst)), ["2", "4", "3", "10", "7", "5"]);
+    }
+
+    #[test]
+    fn count_height_and_depth() {
+        let (node, bst) = sample_trees();
+        assert_eq!(count_nodes(&node), 6);
+        assert_eq!(count_nodes(&bst), 6);
+        assert_eq!(height(&node), 2);
+        assert_eq!(height(&bst), 2);
+        let ten: BstNodeLink = BstNode::tree_search_link(&bst, &10).unwrap();
+        assert_eq!(depth(&ten), 2);
+        assert_eq!(depth(&bst), 0);
+        assert_eq!(height(&ten), 0);
+    }
+
+    fn hash_of<T: BinaryTreeNode>(node: &TreeLink<T>) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        hash_structure(&*node.borrow(), &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equality_and_hash_follow_the_structure() {
+        let (node, bst) = sample_trees();
+        let parse = |text: &str| Node::from_parenthesized(text).unwrap().unwrap();
+        let as_bst = |node: &NodeLink| BstNode::try_from_nodelink(node).unwrap();
+        let same: NodeLink = parse("5(3(2,4),7(,10))");
+        assert_eq!(node, same);
+        assert_eq!(bst, as_bst(&same));
+        assert_eq!(hash_of(&node), hash_of(&same));
+        assert_eq!(hash_of(&bst), hash_of(&as_bst(&same)));
+        //a subtree equals a detached copy of it, parents are not compared
+        assert_eq!(node.borrow().left, Some(parse("3(2,4)")));
+        //a left and a right child are told apart
+        let mirrored: NodeLink = parse("5(3(2,4),7(10,))");
+        assert_ne!(node, mirrored);
+        assert_ne!(hash_of(&node), hash_of(&mirrored));
+        let relabeled: NodeLink = parse("5(3(2,4),7(,11))");
+        assert_ne!(node, relabeled);
+        assert_ne!(bst, as_bst(&relabeled));
+        //same keys, different shape
+        let balanced: BstNodeLink = BstNode::build_from_sorted_keys(&[2, 3, 4, 5, 7, 10]).unwrap();
+        assert_ne!(bst, balanced);
+    }
+
+    #[test]
+    fn deep_clones_share_nothing() {
+        let (node, bst) = sample_trees();
+        let node_copy: NodeLink = Node::deep_clone(&node);
+        let bst_copy: BstNodeLink = BstNode::deep_clone(&bst);
+        assert_eq!(node, node_copy);
+        assert_eq!(bst, bst_copy);
+        assert!(preorder(&node_copy).all(|x| !preorder(&node).any(|y| Rc::ptr_eq(&x, &y))));
+        assert!(preorder(&bst_copy).all(|x| !preorder(&bst).any(|y| Rc::ptr_eq(&x, &y))));
+        //every parent pointer stays inside the copy
+        for x in preorder(&bst_copy).skip(1) {
+            let parent: BstNodeLink = x.borrow().parent().unwrap();
+            assert!(preorder(&bst_copy).any(|y| Rc::ptr_eq(&parent, &y)));
+        }
+        assert!(BstNode::validate(&bst_copy).is_ok());
+    }
+
+    #[test]
+    fn nil_key_label() {
+        let bst: BstNodeLink = BstNode::new_bst_nodelink(1);
+        bst.borrow_mut().key = None;
+        assert_eq!(bst.borrow().label(), "NIL");
+    }
+}
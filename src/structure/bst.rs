@@ -1,9 +1,37 @@
+use crate::structure::binary_tree;
+use crate::structure::error::{BstError, BstResult};
+use crate::structure::metrics;
+use crate::structure::observer::{self, RotateDirection, TreeEvent};
+use crate::structure::tree::{Node, NodeLink};
 use std::cell::RefCell;
+use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 
 pub type BstNodeLink = Rc<RefCell<BstNode>>;
 pub type WeakBstNodeLink = Weak<RefCell<BstNode>>;
 
+/// Iterator over the keys of a subtree in ascending order, see `BstNode::keys`.
+/// Walks `binary_tree::inorder`, so only the current left spine is held at any time.
+pub struct InorderKeys {
+    nodes: binary_tree::InorderIter<BstNode>,
+}
+
+impl Iterator for InorderKeys {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        //NIL keys are skipped
+        self.nodes.by_ref().find_map(|x| x.borrow().key)
+    }
+}
+
+//outcome of comparing the searched value with one node
+enum SearchStep {
+    Found(BstNodeLink),
+    Next(BstNodeLink),
+    Missing,
+}
+
 //this package implement BST wrapper
 #[derive(Debug, Clone)]
 pub struct BstNode {
@@ -13,6 +41,21 @@ pub struct BstNode {
     pub right: Option<BstNodeLink>,
 }
 
+//two nodes are equal when their subtrees have the same shape and keys, parents are not looked at
+impl PartialEq for BstNode {
+    fn eq(&self, other: &Self) -> bool {
+        binary_tree::structurally_equal(self, other)
+    }
+}
+
+impl Eq for BstNode {}
+
+impl Hash for BstNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        binary_tree::hash_structure(self, state);
+    }
+}
+
 impl BstNode {
     //private interface
     fn new(key: i32) -> Self {
@@ -36,11 +79,44 @@ impl BstNode {
 
     /**
      * Get a copy of node link
+     * Only this node is copied, the children are shared with the original, use deep_clone for an independent tree
      */
     pub fn get_bst_nodelink_copy(&self) -> BstNodeLink {
         Rc::new(RefCell::new(self.clone()))
     }
 
+    /**
+     * Copy the whole subtree under node, NIL keys included, the copy shares no node with the original
+     * Every parent pointer is rebuilt inside the copy and its root is detached
+     */
+    pub fn deep_clone(node: &BstNodeLink) -> BstNodeLink {
+        let copy_of = |x: &BstNodeLink| -> BstNodeLink {
+            Rc::new(RefCell::new(BstNode {
+                key: x.borrow().key,
+                parent: None,
+                left: None,
+                right: None,
+            }))
+        };
+        let root: BstNodeLink = copy_of(node);
+        //pairs of an original node and its copy, the children of the copy are still missing
+        let mut stack: Vec<(BstNodeLink, BstNodeLink)> = vec![(node.clone(), root.clone())];
+        while let Some((original, copy)) = stack.pop() {
+            let borrowed = original.borrow();
+            if let Some(ref left) = borrowed.left {
+                let child: BstNodeLink = copy_of(left);
+                BstNode::set_left_child(&copy, Some(child.clone()));
+                stack.push((left.clone(), child));
+            }
+            if let Some(ref right) = borrowed.right {
+                let child: BstNodeLink = copy_of(right);
+                BstNode::set_right_child(&copy, Some(child.clone()));
+                stack.push((right.clone(), child));
+            }
+        }
+        root
+    }
+
     fn downgrade(node: &BstNodeLink) -> WeakBstNodeLink {
         Rc::downgrade(node)
     }
@@ -54,10 +130,12 @@ impl BstNode {
         currentlink
     }
 
+    #[allow(dead_code)]
     fn clone_node(node: &BstNodeLink) -> BstNodeLink {
         Rc::clone(node)
     }
 
+    #[allow(dead_code)]
     fn clone_optional_node(optional_node: &Option<BstNodeLink>) -> Option<BstNodeLink> {
         optional_node.as_ref().map(Self::clone_node)
     }
@@ -75,6 +153,7 @@ impl BstNode {
     /**
      * As the name implied, used to upgrade parent node to strong nodelink
      */
+    #[allow(clippy::manual_map)]
     fn upgrade_weak_to_strong(node: Option<WeakBstNodeLink>) -> Option<BstNodeLink> {
         match node {
             None => None,
@@ -82,6 +161,7 @@ impl BstNode {
         }
     }
 
+    #[allow(dead_code, clippy::manual_map)]
     fn downgrade_strong_to_weak(optional_node: Option<BstNodeLink>) -> Option<WeakBstNodeLink> {
         match optional_node {
             None => None,
@@ -103,48 +183,97 @@ impl BstNode {
 
     //search the current tree which node fit the value
     pub fn tree_search(&self, value: &i32) -> Option<BstNodeLink> {
-        match self.key {
-            Some(k) if k == *value => Some(self.get_bst_nodelink_copy()),
-            Some(k) if *value < k => {
-                if let Some(ref left) = self.left {
-                    left.borrow().tree_search(value)
-                } else {
-                    None
+        self.search_copy(value, false)
+    }
+
+    pub fn tree_search_correct(&self, value: &i32) -> Option<BstNodeLink> {
+        self.search_copy(value, true)
+    }
+
+    //walk down from self one search_step at a time and report the path as a Search event
+    fn search_copy(&self, value: &i32, correct: bool) -> Option<BstNodeLink> {
+        let mut path_length: usize = 1;
+        let mut step: SearchStep = self.search_step(value, correct);
+        let found: Option<BstNodeLink> = loop {
+            match step {
+                SearchStep::Found(x) => break Some(x),
+                SearchStep::Missing => break None,
+                SearchStep::Next(x) => {
+                    path_length += 1;
+                    step = x.borrow().search_step(value, correct);
                 }
             }
-            Some(k) if *value > k => {
-                if let Some(ref right) = self.right {
-                    right.borrow().tree_search(value)
-                } else {
-                    None
-                }
+        };
+        observer::emit(|| TreeEvent::Search {
+            key: *value,
+            path_length,
+            found: found.is_some(),
+        });
+        found
+    }
+
+    //compare value with this node and count the visit and the comparisons,
+    //tree_search_correct falls back to the right child when the left one is missing
+    fn search_step(&self, value: &i32, correct: bool) -> SearchStep {
+        metrics::record_visit();
+        let key: i32 = match self.key {
+            Some(k) => k,
+            None => return SearchStep::Missing,
+        };
+        metrics::record_comparisons(1);
+        if key == *value {
+            return SearchStep::Found(self.get_bst_nodelink_copy());
+        }
+        metrics::record_comparisons(1);
+        let next: Option<&BstNodeLink> = if correct {
+            if *value < key && self.left.is_some() {
+                self.left.as_ref()
+            } else {
+                self.right.as_ref()
             }
-            Some(_) => None,
-            None => None,
+        } else if *value < key {
+            self.left.as_ref()
+        } else {
+            self.right.as_ref()
+        };
+        match next {
+            Some(x) => SearchStep::Next(x.clone()),
+            None => SearchStep::Missing,
         }
     }
 
-    pub fn tree_search_correct(&self, value: &i32) -> Option<BstNodeLink> {
-        if let Some(key) = self.key {
-            if key == *value {
-                return Some(self.get_bst_nodelink_copy());
+    /**
+     * Search the tree under node_link for the key, unlike tree_search the live node inside the tree is returned
+     * so it can be handed to transplant or tree_delete
+     */
+    pub fn tree_search_link(node_link: &BstNodeLink, key: &i32) -> Option<BstNodeLink> {
+        let mut current: Option<BstNodeLink> = Some(node_link.clone());
+        let mut path_length: usize = 0;
+        while let Some(x) = current {
+            path_length += 1;
+            metrics::record_visit();
+            metrics::record_comparisons(1);
+            let x_key: Option<i32> = x.borrow().key;
+            if x_key == Some(*key) {
+                observer::emit(|| TreeEvent::Search {
+                    key: *key,
+                    path_length,
+                    found: true,
+                });
+                return Some(x);
             }
-            if *value < key && self.left.is_some() {
-                return self
-                    .left
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .tree_search_correct(value);
-            } else if self.right.is_some() {
-                return self
-                    .right
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .tree_search_correct(value);
+            metrics::record_comparisons(1);
+            if Some(*key) < x_key {
+                current = x.borrow().left.clone();
+            } else {
+                current = x.borrow().right.clone();
             }
         }
+        observer::emit(|| TreeEvent::Search {
+            key: *key,
+            path_length,
+            found: false,
+        });
         None
     }
 
@@ -154,6 +283,7 @@ impl BstNode {
     pub fn minimum(&self) -> BstNodeLink {
         let mut current = self.get_bst_nodelink_copy();
         loop {
+            metrics::record_visit();
             let left = current.borrow().left.clone();
             if let Some(left_node) = left {
                 current = left_node;
@@ -165,6 +295,7 @@ impl BstNode {
     }
 
     pub fn minimum_correct(&self) -> BstNodeLink {
+        metrics::record_visit();
         if self.key.is_some() {
             if let Some(left_node) = &self.left {
                 return left_node.borrow().minimum_correct();
@@ -176,6 +307,7 @@ impl BstNode {
     pub fn maximum(&self) -> BstNodeLink {
         let mut current = self.get_bst_nodelink_copy();
         loop {
+            metrics::record_visit();
             let right = current.borrow().right.clone();
             if let Some(right_node) = right {
                 current = right_node;
@@ -187,6 +319,7 @@ impl BstNode {
     }
 
     pub fn maximum_correct(&self) -> BstNodeLink {
+        metrics::record_visit();
         if self.key.is_some() {
             if let Some(right_node) = &self.right {
                 return right_node.borrow().maximum_correct();
@@ -198,6 +331,7 @@ impl BstNode {
     /**
      * Return the root of a node, return self if not exist
      */
+    #[allow(clippy::needless_return)]
     pub fn get_root(node: &BstNodeLink) -> BstNodeLink {
         let parent: Option<Rc<RefCell<BstNode>>> =
             BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
@@ -207,10 +341,18 @@ impl BstNode {
         return BstNode::get_root(&parent.unwrap());
     }
 
+    /**
+     * Return the parent of a node, None for the root or when the parent has been dropped
+     */
+    pub fn get_parent(node: &BstNodeLink) -> Option<BstNodeLink> {
+        node.borrow().parent.as_ref().and_then(|x| x.upgrade())
+    }
+
     /**
      * Find node successor according to the book
      * Possible to return self, if x_node is the highest key in the tree
      */
+    #[allow(clippy::needless_return)]
     pub fn tree_successor(x_node: &BstNodeLink) -> BstNodeLink {
         let x_borrow = x_node.borrow();
         if let Some(ref right) = x_borrow.right {
@@ -220,6 +362,7 @@ impl BstNode {
         let mut optional_parent = x_borrow.parent.clone().and_then(|w| w.upgrade());
         drop(x_borrow);
         while let Some(parent_rc_pointer) = optional_parent {
+            metrics::record_visit();
             let parent = parent_rc_pointer.borrow();
             if let Some(ref left) = parent.left {
                 if Rc::ptr_eq(left, &current) {
@@ -232,6 +375,7 @@ impl BstNode {
         return x_node.clone();
     }
 
+    #[allow(clippy::bool_comparison, clippy::needless_borrow)]
     pub fn tree_successor_simpler(x_node: &BstNodeLink) -> BstNodeLink {
         // create a shadow of x_node so it can mutate
         let mut x_node: &Rc<RefCell<BstNode>> = x_node;
@@ -261,6 +405,51 @@ impl BstNode {
         y_node.clone().unwrap()
     }
 
+    /**
+     * Find node successor, None if x_node holds the highest key in the tree
+     * Unlike tree_successor the live node is returned
+     */
+    pub fn tree_successor_link(x_node: &BstNodeLink) -> Option<BstNodeLink> {
+        if let Some(ref right) = x_node.borrow().right {
+            return Some(BstNode::leftmost(right));
+        }
+        let mut current: BstNodeLink = x_node.clone();
+        while let Some(parent) = BstNode::get_parent(&current) {
+            let from_left: bool = parent
+                .borrow()
+                .left
+                .as_ref()
+                .is_some_and(|x| Rc::ptr_eq(x, &current));
+            if from_left {
+                return Some(parent);
+            }
+            current = parent;
+        }
+        None
+    }
+
+    /**
+     * Find node predecessor, None if x_node holds the lowest key in the tree
+     */
+    pub fn tree_predecessor_link(x_node: &BstNodeLink) -> Option<BstNodeLink> {
+        if let Some(ref left) = x_node.borrow().left {
+            return Some(BstNode::rightmost(left));
+        }
+        let mut current: BstNodeLink = x_node.clone();
+        while let Some(parent) = BstNode::get_parent(&current) {
+            let from_right: bool = parent
+                .borrow()
+                .right
+                .as_ref()
+                .is_some_and(|x| Rc::ptr_eq(x, &current));
+            if from_right {
+                return Some(parent);
+            }
+            current = parent;
+        }
+        None
+    }
+
     /// Inserts a new node with the given key into the BST.
     ///
     /// # Arguments
@@ -273,17 +462,23 @@ impl BstNode {
     /// * `Some(BstNodeLink)` if the insertion is successful.
     /// * `None` if a node with the same key already exists.
     pub fn tree_insert(bst_node_link: &BstNodeLink, key: &i32) -> Option<BstNodeLink> {
-        if bst_node_link.borrow().tree_search_correct(&key).is_some() {
-            return None;
-        }
         let z: BstNodeLink = BstNode::new_bst_nodelink(*key);
         z.borrow_mut().parent = None;
         z.borrow_mut().left = None;
         z.borrow_mut().right = None;
         let mut y: Option<BstNodeLink> = None;
         let mut x: Option<BstNodeLink> = Some(bst_node_link.clone());
+        let mut depth: usize = 0;
         while let Some(x_rc_pointer) = x {
             y = Some(x_rc_pointer.clone());
+            depth += 1;
+            metrics::record_visit();
+            metrics::record_comparisons(1);
+            //the duplicate check is part of the descent, a recursive search overflows the stack on degenerate trees
+            if x_rc_pointer.borrow().key == Some(*key) {
+                return None;
+            }
+            metrics::record_comparisons(1);
             if z.borrow().key < x_rc_pointer.borrow().key {
                 x = x_rc_pointer.borrow().left.clone();
             } else {
@@ -291,16 +486,20 @@ impl BstNode {
             }
         }
         if let Some(ref y_rc_pointer) = y {
+            metrics::record_comparisons(1);
             if z.borrow().key < y_rc_pointer.borrow().key {
                 y_rc_pointer.borrow_mut().left = Some(z.clone());
             } else {
                 y_rc_pointer.borrow_mut().right = Some(z.clone());
             }
             z.borrow_mut().parent = Some(Rc::downgrade(y_rc_pointer));
+            //child pointer of y and parent pointer of z
+            metrics::record_rewires(2);
         } else {
             z.borrow_mut().parent = None;
             return Some(z.clone());
         }
+        observer::emit(|| TreeEvent::Insert { key: *key, depth });
         Some(z)
     }
 
@@ -316,14 +515,38 @@ impl BstNode {
     /// * `true` if the transplant operation is successful.
     /// * `false` if the parent pointer cannot be upgraded.
     pub fn transplant(&mut self, u: &BstNodeLink, v: &Option<BstNodeLink>) -> bool {
+        BstNode::transplant_node(u, v)
+    }
+
+    /**
+     * Same as transplant, but does not need a borrow of any node.
+     * Use this one when the node at hand may be the parent of u, borrowing it mutably would panic
+     */
+    pub fn transplant_node(u: &BstNodeLink, v: &Option<BstNodeLink>) -> bool {
+        BstNode::try_transplant(u, v).is_ok()
+    }
+
+    /// Replaces one subtree as a child of its parent with another subtree.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - The node to be replaced.
+    /// * `v` - The node to replace `u` with (can be `None`).
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::DanglingParent` if the parent pointer of `u` cannot be upgraded.
+    pub fn try_transplant(u: &BstNodeLink, v: &Option<BstNodeLink>) -> BstResult<()> {
+        let mut rewires: u64 = 0;
         if let Some(ref u_parent_weak_pointer) = &u.borrow().parent {
             if let Some(u_parent_rc_pointer) = u_parent_weak_pointer.upgrade() {
                 let is_left_children: bool =
                     if let Some(ref left_children) = u_parent_rc_pointer.borrow().left {
-                        Rc::ptr_eq(&u, left_children)
+                        Rc::ptr_eq(u, left_children)
                     } else {
-                        Rc::ptr_eq(&u, &BstNode::new_bst_nodelink(i32::default()))
+                        Rc::ptr_eq(u, &BstNode::new_bst_nodelink(i32::default()))
                     };
+                rewires += 1;
                 if is_left_children {
                     u_parent_rc_pointer.borrow_mut().left = v.clone();
                     if let Some(ref left) = u_parent_rc_pointer.borrow().left {
@@ -336,8 +559,7 @@ impl BstNode {
                     }
                 }
             } else {
-                println!("Cannot perform transplanting operation: Cannot upgrade parent pointer from Weak<RefCell<BstNode>> to Rc<RefCell<BstNode>>.");
-                return false;
+                return Err(BstError::DanglingParent);
             }
         } else {
             if let Some(ref v_rc_pointer) = &v {
@@ -347,6 +569,7 @@ impl BstNode {
         if let Some(ref v_rc_pointer) = &v {
             v_rc_pointer.borrow_mut().parent =
                 BstNode::clone_optional_weak_bst_node(&u.borrow().parent);
+            rewires += 1;
             if let Some(ref left) = v_rc_pointer.borrow().left {
                 left.borrow_mut().parent = Some(BstNode::downgrade(v_rc_pointer));
             }
@@ -354,7 +577,12 @@ impl BstNode {
                 right.borrow_mut().parent = Some(BstNode::downgrade(v_rc_pointer));
             }
         }
-        return true;
+        metrics::record_rewires(rewires);
+        observer::emit(|| TreeEvent::Transplant {
+            u: u.borrow().key,
+            v: v.as_ref().and_then(|x| x.borrow().key),
+        });
+        Ok(())
     }
 
     /// Deletes the specified node from the BST.
@@ -367,27 +595,67 @@ impl BstNode {
     ///
     /// * `true` if the deletion is successful.
     pub fn tree_delete(&mut self, z: &BstNodeLink) -> bool {
+        BstNode::tree_delete_node(z)
+    }
+
+    /**
+     * Same as tree_delete, but does not need a borrow of any node, see transplant_node
+     */
+    pub fn tree_delete_node(z: &BstNodeLink) -> bool {
+        BstNode::try_tree_delete(z).is_ok()
+    }
+
+    /// Deletes the specified node from the BST.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The live node to delete, as returned by `tree_search_link`.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::DanglingParent` if a parent pointer on the way cannot be upgraded.
+    pub fn try_tree_delete(z: &BstNodeLink) -> BstResult<()> {
         if z.borrow().left.is_none() {
-            self.transplant(&z.clone(), &z.borrow().right.clone());
+            BstNode::try_transplant(&z.clone(), &z.borrow().right.clone())?;
         } else if z.borrow().right.is_none() {
-            self.transplant(&z.clone(), &z.borrow().left.clone());
+            BstNode::try_transplant(&z.clone(), &z.borrow().left.clone())?;
         } else {
+            //minimum() hands back a copy when the right child has no left child, the live node is needed here
             let successor: Rc<RefCell<BstNode>> =
-                z.borrow().right.as_ref().unwrap().borrow().minimum();
+                BstNode::leftmost(z.borrow().right.as_ref().unwrap());
             if !Rc::ptr_eq(&successor, z.borrow().right.as_ref().unwrap()) {
-                self.transplant(&successor.clone(), &successor.borrow().right.clone());
+                BstNode::try_transplant(&successor.clone(), &successor.borrow().right.clone())?;
                 successor.borrow_mut().right = z.borrow().right.clone();
                 if let Some(ref right) = successor.borrow().right {
                     right.borrow_mut().parent = Some(Rc::downgrade(&successor));
                 }
+                metrics::record_rewires(2);
             }
-            self.transplant(&z.clone(), &Some(successor.clone()));
+            BstNode::try_transplant(&z.clone(), &Some(successor.clone()))?;
             successor.borrow_mut().left = z.borrow().left.clone();
             if let Some(ref left) = successor.borrow().left {
                 left.borrow_mut().parent = Some(Rc::downgrade(&successor));
             };
+            metrics::record_rewires(2);
+        }
+        observer::emit(|| TreeEvent::Delete {
+            key: z.borrow().key,
+        });
+        Ok(())
+    }
+
+    /**
+     * Return the node that takes the place of z when z is deleted, None if z is a leaf.
+     * Callers holding the root handle use it to find the new root after deleting the root
+     */
+    pub fn tree_delete_replacement(z: &BstNodeLink) -> Option<BstNodeLink> {
+        let left: Option<BstNodeLink> = z.borrow().left.clone();
+        let right: Option<BstNodeLink> = z.borrow().right.clone();
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(_), Some(right)) => Some(BstNode::leftmost(&right)),
         }
-        return true;
     }
 
     /// Deletes a node with the specified key from the BST.
@@ -399,22 +667,566 @@ impl BstNode {
     /// # Returns
     ///
     /// * `true` if the node is found and deleted.
-    /// * `false` if the node with the given key does not exist.
+    /// * `false` if the node with the given key does not exist, or is the node the call
+    ///   goes through, see `try_tree_delete_with_key`.
     pub fn tree_delete_with_key(&mut self, key: i32) -> bool {
-        let node_to_delete: Option<BstNodeLink> = self.tree_search_correct(&key);
-        if let Some(node) = node_to_delete {
-            self.tree_delete(&node);
-            return true;
-        } else {
-            println!(
-                "A node with the i32-typed key value of {} is not found inside this tree or subtree.",
+        self.try_tree_delete_with_key(key).is_ok()
+    }
+
+    /// Deletes a node with the specified key from the BST.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::NotFound` if the node with the given key does not exist.
+    /// * `BstError::InvalidInput` if `key` is the key of the node the call goes through,
+    ///   that node is borrowed by the call and cannot be unlinked, use `tree_delete_node`.
+    /// * `BstError::DanglingParent` if a parent pointer on the way cannot be upgraded.
+    pub fn try_tree_delete_with_key(&mut self, key: i32) -> BstResult<()> {
+        if self.key == Some(key) {
+            return Err(BstError::InvalidInput(format!(
+                "{} is the key of the receiver, delete it through BstNode::tree_delete_node",
                 key
-            );
-            return false;
+            )));
+        }
+        //descendants are looked up as live links, tree_search_correct hands back copies
+        //and a copy is never recognised as the left child of its parent
+        let node_to_delete: Option<BstNodeLink> = if Some(key) < self.key {
+            self.left
+                .as_ref()
+                .and_then(|x| BstNode::tree_search_link(x, &key))
+        } else {
+            self.right
+                .as_ref()
+                .and_then(|x| BstNode::tree_search_link(x, &key))
+        };
+        let node: BstNodeLink = match node_to_delete {
+            Some(x) => x,
+            None => return Err(BstError::NotFound(key)),
+        };
+        let is_left_child: bool = self.left.as_ref().is_some_and(|x| Rc::ptr_eq(x, &node));
+        let is_right_child: bool = self.right.as_ref().is_some_and(|x| Rc::ptr_eq(x, &node));
+        if !is_left_child && !is_right_child {
+            return BstNode::try_tree_delete(&node);
+        }
+        //self is usually reached through borrow_mut(), so detach the child first,
+        //otherwise transplant would borrow self again through the parent pointer
+        let self_weak: Option<WeakBstNodeLink> = node.borrow_mut().parent.take();
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&node);
+        BstNode::try_tree_delete(&node)?;
+        if let Some(ref x) = replacement {
+            x.borrow_mut().parent = self_weak;
+        }
+        if is_left_child {
+            self.left = replacement;
+        } else {
+            self.right = replacement;
+        }
+        Ok(())
+    }
+
+    /// Inserts a new node with the given key into the BST.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::DuplicateKey` if a node with the same key already exists.
+    pub fn try_tree_insert(bst_node_link: &BstNodeLink, key: &i32) -> BstResult<BstNodeLink> {
+        BstNode::tree_insert(bst_node_link, key).ok_or(BstError::DuplicateKey(*key))
+    }
+
+    /**
+     * Upgrade a parent pointer, unlike upgrade_weak_to_strong a dropped parent is reported instead of panicking
+     */
+    pub fn try_upgrade_weak_to_strong(
+        node: Option<WeakBstNodeLink>,
+    ) -> BstResult<Option<BstNodeLink>> {
+        match node {
+            None => Ok(None),
+            Some(x) => x.upgrade().map(Some).ok_or(BstError::DanglingParent),
+        }
+    }
+
+    /// Checks the ordering of every key and that every child points back to its parent.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvariantViolation` describing the first broken rule found.
+    /// * `BstError::DanglingParent` if a parent pointer cannot be upgraded.
+    pub fn validate(node: &BstNodeLink) -> BstResult<()> {
+        BstNode::validate_subtree(node, None, None)
+    }
+
+    //keys of the subtree must lie strictly between low and high, walked with an explicit stack
+    //so a degenerate tree cannot overflow the call stack
+    fn validate_subtree(node: &BstNodeLink, low: Option<i32>, high: Option<i32>) -> BstResult<()> {
+        let mut stack: Vec<(BstNodeLink, Option<i32>, Option<i32>)> =
+            vec![(node.clone(), low, high)];
+        while let Some((current, low, high)) = stack.pop() {
+            let borrowed = current.borrow();
+            if let Some(key) = borrowed.key {
+                if low.is_some_and(|x| key <= x) || high.is_some_and(|x| key >= x) {
+                    return Err(BstError::InvariantViolation(format!(
+                        "key {} is out of the range allowed by its ancestors",
+                        key
+                    )));
+                }
+            }
+            for child in [&borrowed.left, &borrowed.right].into_iter().flatten() {
+                let parent: Option<BstNodeLink> =
+                    BstNode::try_upgrade_weak_to_strong(child.borrow().parent.clone())?;
+                if !parent.is_some_and(|x| Rc::ptr_eq(&x, &current)) {
+                    return Err(BstError::InvariantViolation(format!(
+                        "child {:?} does not point back to its parent {:?}",
+                        child.borrow().key,
+                        borrowed.key
+                    )));
+                }
+            }
+            //right goes first so the left subtree is checked first, as a preorder walk would
+            if let Some(ref right) = borrowed.right {
+                stack.push((right.clone(), borrowed.key.or(low), high));
+            }
+            if let Some(ref left) = borrowed.left {
+                stack.push((left.clone(), low, borrowed.key.or(high)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits the tree rooted at `root` into two trees around `key`.
+    ///
+    /// The nodes are re-linked in place, no key is copied. Parent pointers of
+    /// every re-linked node are rewired and both returned roots are detached.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Reference to the root node of the BST.
+    /// * `key` - The key value to split at.
+    ///
+    /// # Returns
+    ///
+    /// * `(left_tree, right_tree)` where `left_tree` holds every key smaller than `key`
+    ///   and `right_tree` holds every key greater than or equal to `key`.
+    ///   Either side is `None` when it ends up empty.
+    pub fn split(root: &BstNodeLink, key: &i32) -> (Option<BstNodeLink>, Option<BstNodeLink>) {
+        let (left_tree, right_tree) = BstNode::split_subtree(Some(root.clone()), key);
+        for tree in [&left_tree, &right_tree].into_iter().flatten() {
+            tree.borrow_mut().parent = None;
+        }
+        (left_tree, right_tree)
+    }
+
+    //walk down the search path of key once, every node on it goes to the smaller or the greater tree
+    //and hangs in the open slot left by the previous node of that tree, the right slot of the last
+    //smaller node and the left slot of the last greater node.
+    //The parent pointer of the returned roots is left for the caller to fix
+    fn split_subtree(
+        node: Option<BstNodeLink>,
+        key: &i32,
+    ) -> (Option<BstNodeLink>, Option<BstNodeLink>) {
+        let mut smaller: Option<BstNodeLink> = None;
+        let mut greater: Option<BstNodeLink> = None;
+        let mut smaller_tail: Option<BstNodeLink> = None;
+        let mut greater_tail: Option<BstNodeLink> = None;
+        let mut current: Option<BstNodeLink> = node;
+        while let Some(x) = current {
+            if x.borrow().key < Some(*key) {
+                current = x.borrow().right.clone();
+                match smaller_tail {
+                    Some(ref tail) => BstNode::set_right_child(tail, Some(x.clone())),
+                    None => smaller = Some(x.clone()),
+                }
+                smaller_tail = Some(x);
+            } else {
+                current = x.borrow().left.clone();
+                match greater_tail {
+                    Some(ref tail) => BstNode::set_left_child(tail, Some(x.clone())),
+                    None => greater = Some(x.clone()),
+                }
+                greater_tail = Some(x);
+            }
+        }
+        //whatever still hangs in the open slots belongs to the other tree, it was re-linked above
+        if let Some(ref tail) = smaller_tail {
+            BstNode::set_right_child(tail, None);
+        }
+        if let Some(ref tail) = greater_tail {
+            BstNode::set_left_child(tail, None);
+        }
+        (smaller, greater)
+    }
+
+    /// Joins two trees where every key of `left` is smaller than every key of `right`.
+    ///
+    /// The maximum node of `left` is unlinked and becomes the new root, with the rest of
+    /// `left` as its left subtree and `right` as its right subtree.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The tree holding the smaller keys (can be `None`).
+    /// * `right` - The tree holding the greater keys (can be `None`).
+    ///
+    /// # Returns
+    ///
+    /// * The root of the joined tree, `None` if both trees are empty.
+    pub fn join(left: Option<BstNodeLink>, right: Option<BstNodeLink>) -> Option<BstNodeLink> {
+        let left: BstNodeLink = match left {
+            None => return right,
+            Some(x) => x,
+        };
+        let right: BstNodeLink = match right {
+            None => return Some(left),
+            Some(x) => x,
+        };
+        debug_assert!(
+            BstNode::rightmost(&left).borrow().key < BstNode::leftmost(&right).borrow().key,
+            "join expects every key of the left tree to be smaller than the right tree"
+        );
+        let new_root: BstNodeLink = BstNode::rightmost(&left);
+        let remaining_left: Option<BstNodeLink> = if Rc::ptr_eq(&new_root, &left) {
+            new_root.borrow().left.clone()
+        } else {
+            //the maximum never has a right child, so splice its left child into its place
+            let max_parent: BstNodeLink =
+                BstNode::upgrade_weak_to_strong(new_root.borrow().parent.clone()).unwrap();
+            let max_left: Option<BstNodeLink> = new_root.borrow().left.clone();
+            BstNode::set_right_child(&max_parent, max_left);
+            Some(left)
+        };
+        BstNode::set_left_child(&new_root, remaining_left);
+        BstNode::set_right_child(&new_root, Some(right));
+        new_root.borrow_mut().parent = None;
+        Some(new_root)
+    }
+
+    /// Same as `join`, but checks the ordering precondition first.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvariantViolation` if a key of `left` is not smaller than every key of `right`.
+    pub fn try_join(
+        left: Option<BstNodeLink>,
+        right: Option<BstNodeLink>,
+    ) -> BstResult<Option<BstNodeLink>> {
+        if let (Some(ref l), Some(ref r)) = (&left, &right) {
+            let left_max: Option<i32> = BstNode::rightmost(l).borrow().key;
+            let right_min: Option<i32> = BstNode::leftmost(r).borrow().key;
+            if left_max >= right_min {
+                return Err(BstError::InvariantViolation(format!(
+                    "left tree maximum {:?} is not smaller than right tree minimum {:?}",
+                    left_max, right_min
+                )));
+            }
+        }
+        Ok(BstNode::join(left, right))
+    }
+
+    /// Builds the union of two arbitrary trees by merging their in-order key sequences.
+    ///
+    /// Runs in linear time in the size of both trees. Neither input is modified, the
+    /// result is a new height balanced tree with every parent pointer set.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - Root of the first tree.
+    /// * `second` - Root of the second tree.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(BstNodeLink)` with the root of the new tree holding every key present in either tree once.
+    /// * `None` if neither tree holds a key, e.g. both are single NIL nodes.
+    pub fn union(first: &BstNodeLink, second: &BstNodeLink) -> Option<BstNodeLink> {
+        let first_keys: Vec<i32> = BstNode::inorder_keys(first);
+        let second_keys: Vec<i32> = BstNode::inorder_keys(second);
+        let mut merged: Vec<i32> = Vec::with_capacity(first_keys.len() + second_keys.len());
+        let (mut i, mut j) = (0, 0);
+        while i < first_keys.len() && j < second_keys.len() {
+            if first_keys[i] < second_keys[j] {
+                merged.push(first_keys[i]);
+                i += 1;
+            } else if second_keys[j] < first_keys[i] {
+                merged.push(second_keys[j]);
+                j += 1;
+            } else {
+                merged.push(first_keys[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+        merged.extend_from_slice(&first_keys[i..]);
+        merged.extend_from_slice(&second_keys[j..]);
+        //NIL keys are skipped, so the merge is empty when both trees hold only NIL keys
+        BstNode::build_from_sorted_keys(&merged)
+    }
+
+    /**
+     * Collect the keys of the subtree in ascending order, NIL keys are skipped
+     */
+    pub fn inorder_keys(node: &BstNodeLink) -> Vec<i32> {
+        BstNode::keys(node).collect()
+    }
+
+    /**
+     * Lazily walk the keys of the subtree in ascending order
+     */
+    pub fn keys(node: &BstNodeLink) -> InorderKeys {
+        InorderKeys {
+            nodes: binary_tree::inorder(node),
+        }
+    }
+
+    /**
+     * Build a height balanced tree out of strictly ascending keys, returns None for an empty slice
+     */
+    pub fn build_from_sorted_keys(keys: &[i32]) -> Option<BstNodeLink> {
+        if keys.is_empty() {
+            return None;
+        }
+        let middle: usize = keys.len() / 2;
+        let node: BstNodeLink = BstNode::new_with_rc_pointer(keys[middle]);
+        BstNode::set_left_child(&node, BstNode::build_from_sorted_keys(&keys[..middle]));
+        BstNode::set_right_child(&node, BstNode::build_from_sorted_keys(&keys[middle + 1..]));
+        Some(node)
+    }
+
+    /// Copies a `tree::Node` tree into a fresh BST with the same shape.
+    ///
+    /// `TryFrom` cannot be used here, both sides are `Rc` links and the orphan rule
+    /// forbids implementing a foreign trait between two foreign types.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - Root of the tree to copy, it is left untouched.
+    ///
+    /// # Returns
+    ///
+    /// The root of the copy, every parent pointer points into the copy.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvariantViolation` if the values are not in strict BST order,
+    ///   see `Node::is_bst`.
+    pub fn try_from_nodelink(node: &NodeLink) -> BstResult<BstNodeLink> {
+        if !Node::is_bst(node) {
+            return Err(BstError::InvariantViolation(format!(
+                "tree under {} is not in BST order",
+                node.borrow().value
+            )));
+        }
+        Ok(BstNode::copy_from_nodelink(node))
+    }
+
+    fn copy_from_nodelink(node: &NodeLink) -> BstNodeLink {
+        let root: BstNodeLink = BstNode::new_with_rc_pointer(node.borrow().value);
+        //pairs of an original node and its copy, the children of the copy are still missing
+        let mut stack: Vec<(NodeLink, BstNodeLink)> = vec![(node.clone(), root.clone())];
+        while let Some((original, copy)) = stack.pop() {
+            let borrowed = original.borrow();
+            if let Some(ref left) = borrowed.left {
+                let child: BstNodeLink = BstNode::new_with_rc_pointer(left.borrow().value);
+                BstNode::set_left_child(&copy, Some(child.clone()));
+                stack.push((left.clone(), child));
+            }
+            if let Some(ref right) = borrowed.right {
+                let child: BstNodeLink = BstNode::new_with_rc_pointer(right.borrow().value);
+                BstNode::set_right_child(&copy, Some(child.clone()));
+                stack.push((right.clone(), child));
+            }
+        }
+        root
+    }
+
+    //attach child as the left child of node and point the child back to node
+    fn set_left_child(node: &BstNodeLink, child: Option<BstNodeLink>) {
+        if let Some(ref x) = child {
+            x.borrow_mut().parent = Some(BstNode::downgrade(node));
+        }
+        node.borrow_mut().left = child;
+    }
+
+    //attach child as the right child of node and point the child back to node
+    fn set_right_child(node: &BstNodeLink, child: Option<BstNodeLink>) {
+        if let Some(ref x) = child {
+            x.borrow_mut().parent = Some(BstNode::downgrade(node));
+        }
+        node.borrow_mut().right = child;
+    }
+
+    //unlike minimum(), always hands back the node living inside the tree
+    pub fn leftmost(node: &BstNodeLink) -> BstNodeLink {
+        let mut current: BstNodeLink = node.clone();
+        loop {
+            metrics::record_visit();
+            let left: Option<BstNodeLink> = current.borrow().left.clone();
+            match left {
+                Some(x) => current = x,
+                None => return current,
+            }
+        }
+    }
+
+    //unlike maximum(), always hands back the node living inside the tree
+    pub fn rightmost(node: &BstNodeLink) -> BstNodeLink {
+        let mut current: BstNodeLink = node.clone();
+        loop {
+            metrics::record_visit();
+            let right: Option<BstNodeLink> = current.borrow().right.clone();
+            match right {
+                Some(x) => current = x,
+                None => return current,
+            }
+        }
+    }
+
+    /**
+     * Rotate x down to the left, its right child takes its place and x becomes that child's left child
+     * Returns the node now standing where x stood, None if x has no right child, in which case nothing changes
+     * If x was the root of the tree the returned node is the new root, keep it as the root handle
+     */
+    pub fn rotate_left(x: &BstNodeLink) -> Option<BstNodeLink> {
+        BstNode::try_rotate_left(x).ok()
+    }
+
+    /**
+     * Mirror of rotate_left, the left child of y takes its place and y becomes its right child
+     */
+    pub fn rotate_right(y: &BstNodeLink) -> Option<BstNodeLink> {
+        BstNode::try_rotate_right(y).ok()
+    }
+
+    /// Same as `rotate_left`, but the reason for not rotating is returned.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvariantViolation` if x has no right child.
+    /// * `BstError::DanglingParent` if the parent of x was dropped.
+    pub fn try_rotate_left(x: &BstNodeLink) -> BstResult<BstNodeLink> {
+        BstNode::try_rotate(x, RotateDirection::Left)
+    }
+
+    /// Same as `rotate_right`, but the reason for not rotating is returned.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvariantViolation` if y has no left child.
+    /// * `BstError::DanglingParent` if the parent of y was dropped.
+    pub fn try_rotate_right(y: &BstNodeLink) -> BstResult<BstNodeLink> {
+        BstNode::try_rotate(y, RotateDirection::Right)
+    }
+
+    //pivot goes down in direction, the child on the other side comes up
+    fn try_rotate(pivot: &BstNodeLink, direction: RotateDirection) -> BstResult<BstNodeLink> {
+        let rising: Option<BstNodeLink> = match direction {
+            RotateDirection::Left => pivot.borrow().right.clone(),
+            RotateDirection::Right => pivot.borrow().left.clone(),
+        };
+        let rising: BstNodeLink = rising.ok_or_else(|| {
+            BstError::InvariantViolation(format!(
+                "cannot rotate {:?} {:?} without a {} child",
+                pivot.borrow().key,
+                direction,
+                match direction {
+                    RotateDirection::Left => "right",
+                    RotateDirection::Right => "left",
+                }
+            ))
+        })?;
+        let parent: Option<BstNodeLink> =
+            BstNode::try_upgrade_weak_to_strong(pivot.borrow().parent.clone())?;
+        let mut rewires: u64 = 1;
+
+        //the inner subtree of the rising node changes sides and hangs under pivot
+        let inner: Option<BstNodeLink> = match direction {
+            RotateDirection::Left => rising.borrow_mut().left.take(),
+            RotateDirection::Right => rising.borrow_mut().right.take(),
+        };
+        if let Some(ref x) = inner {
+            x.borrow_mut().parent = Some(BstNode::downgrade(pivot));
+            rewires += 1;
         }
+        match direction {
+            RotateDirection::Left => pivot.borrow_mut().right = inner,
+            RotateDirection::Right => pivot.borrow_mut().left = inner,
+        }
+
+        //the rising node takes the place of pivot under its parent
+        if let Some(ref x) = parent {
+            let is_left: bool = x
+                .borrow()
+                .left
+                .as_ref()
+                .is_some_and(|left| Rc::ptr_eq(left, pivot));
+            if is_left {
+                x.borrow_mut().left = Some(rising.clone());
+            } else {
+                x.borrow_mut().right = Some(rising.clone());
+            }
+            rewires += 1;
+        }
+        rising.borrow_mut().parent = parent.as_ref().map(BstNode::downgrade);
+
+        //and pivot hangs under it
+        pivot.borrow_mut().parent = Some(BstNode::downgrade(&rising));
+        match direction {
+            RotateDirection::Left => rising.borrow_mut().left = Some(pivot.clone()),
+            RotateDirection::Right => rising.borrow_mut().right = Some(pivot.clone()),
+        }
+
+        metrics::record_rotation();
+        metrics::record_rewires(rewires);
+        observer::emit(|| TreeEvent::Rotate {
+            pivot: pivot.borrow().key,
+            direction,
+        });
+        Ok(rising)
+    }
+
+    /**
+     * Rotate the node holding key up until it stands where root stood, every rotation keeps the BST order
+     * Returns that node, it is the new root handle of the tree, None if the key is missing
+     */
+    pub fn move_to_root(root: &BstNodeLink, key: &i32) -> Option<BstNodeLink> {
+        BstNode::try_move_to_root(root, key).ok()
+    }
+
+    /// Same as `move_to_root`, but the reason for not moving is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Root of the tree or subtree to search, the node ends up in its place.
+    /// * `key` - Key of the node to bring up.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::NotFound` if no node under root holds the key.
+    /// * `BstError::DanglingParent` if a parent on the way up was dropped.
+    pub fn try_move_to_root(root: &BstNodeLink, key: &i32) -> BstResult<BstNodeLink> {
+        let node: BstNodeLink =
+            BstNode::tree_search_link(root, key).ok_or(BstError::NotFound(*key))?;
+        let mut top: BstNodeLink = root.clone();
+        while !Rc::ptr_eq(&node, &top) {
+            let parent: BstNodeLink =
+                BstNode::try_upgrade_weak_to_strong(node.borrow().parent.clone())?
+                    .ok_or(BstError::DanglingParent)?;
+            let is_left: bool = parent
+                .borrow()
+                .left
+                .as_ref()
+                .is_some_and(|x| Rc::ptr_eq(x, &node));
+            if is_left {
+                BstNode::try_rotate_right(&parent)?;
+            } else {
+                BstNode::try_rotate_left(&parent)?;
+            }
+            //once the parent of node was the top, node is the top now
+            if Rc::ptr_eq(&parent, &top) {
+                top = node.clone();
+            }
+        }
+        Ok(node)
     }
 
     //helper function to compare both nodelink
+    #[allow(clippy::needless_return)]
     fn is_node_match_option(node1: Option<BstNodeLink>, node2: Option<BstNodeLink>) -> bool {
         if node1.is_none() && node2.is_none() {
             return true;
@@ -425,6 +1237,7 @@ impl BstNode {
         return false;
     }
 
+    #[allow(dead_code, clippy::needless_return)]
     fn is_node_match(anode: &BstNodeLink, bnode: &BstNodeLink) -> bool {
         if anode.borrow().key == bnode.borrow().key {
             return true;
@@ -435,6 +1248,7 @@ impl BstNode {
     /**
      * private function that returns the boolean value of true if the given node doesn't have a designated parent nor children nor key
      */
+    #[allow(clippy::needless_bool, clippy::needless_return)]
     fn is_nil(node: &Option<BstNodeLink>) -> bool {
         match node {
             None => true,
@@ -451,3 +1265,795 @@ impl BstNode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the tree of the demo in main.rs
+    //            15
+    //        6        18
+    //      3   7    17  20
+    //     2 4   13
+    //          9
+    fn sample_tree() -> BstNodeLink {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(15);
+        for key in [6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            BstNode::tree_insert(&root, &key);
+        }
+        root
+    }
+
+    fn key_of(node: &BstNodeLink) -> Option<i32> {
+        node.borrow().key
+    }
+
+    fn find(root: &BstNodeLink, key: i32) -> BstNodeLink {
+        BstNode::tree_search_link(root, &key).unwrap()
+    }
+
+    #[test]
+    fn rotations_keep_order_and_parents() {
+        let root: BstNodeLink = sample_tree();
+        //6(3(2,4),7(,13(9,))) turns into 7(6(3(2,4),),13(9,)) under 15
+        let seven: BstNodeLink = BstNode::rotate_left(&find(&root, 6)).unwrap();
+        assert_eq!(key_of(&seven), Some(7));
+        assert!(Rc::ptr_eq(&BstNode::get_parent(&seven).unwrap(), &root));
+        assert!(Rc::ptr_eq(root.borrow().left.as_ref().unwrap(), &seven));
+        assert_eq!(key_of(seven.borrow().left.as_ref().unwrap()), Some(6));
+        assert!(find(&root, 6).borrow().right.is_none());
+        assert!(BstNode::validate(&root).is_ok());
+
+        let six: BstNodeLink = BstNode::rotate_right(&seven).unwrap();
+        assert_eq!(key_of(&six), Some(6));
+        //back to the original shape
+        assert!(Rc::ptr_eq(root.borrow().left.as_ref().unwrap(), &six));
+        assert!(find(&root, 7).borrow().left.is_none());
+        assert_eq!(root, sample_tree());
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&root),
+            vec![2, 3, 4, 6, 7, 9, 13, 15, 17, 18, 20]
+        );
+    }
+
+    #[test]
+    fn rotating_the_root_hands_back_the_new_root() {
+        let root: BstNodeLink = sample_tree();
+        let new_root: BstNodeLink = BstNode::rotate_right(&root).unwrap();
+        assert_eq!(key_of(&new_root), Some(6));
+        assert!(BstNode::get_parent(&new_root).is_none());
+        assert!(Rc::ptr_eq(&BstNode::get_parent(&root).unwrap(), &new_root));
+        assert_eq!(key_of(root.borrow().left.as_ref().unwrap()), Some(7));
+        assert!(BstNode::validate(&new_root).is_ok());
+        assert_eq!(BstNode::inorder_keys(&new_root).len(), 11);
+    }
+
+    #[test]
+    fn rotation_without_the_child_changes_nothing() {
+        let root: BstNodeLink = sample_tree();
+        let leaf: BstNodeLink = find(&root, 2);
+        assert!(BstNode::rotate_left(&leaf).is_none());
+        assert!(matches!(
+            BstNode::try_rotate_right(&leaf),
+            Err(BstError::InvariantViolation(_))
+        ));
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(root, sample_tree());
+    }
+
+    #[test]
+    fn searches_and_walks_are_counted() {
+        let root: BstNodeLink = sample_tree();
+        //9 sits below 15, 6, 7 and 13, each of them costs an equality and an ordering comparison
+        for correct in [false, true] {
+            let (found, counted) = metrics::measure("search", || {
+                root.borrow().search_copy(&9, correct).is_some()
+            });
+            assert!(found);
+            assert_eq!((counted.nodes_visited, counted.comparisons), (5, 9));
+            let (found, counted) = metrics::measure("search", || {
+                root.borrow().search_copy(&5, correct).is_some()
+            });
+            assert!(!found);
+            assert_eq!((counted.nodes_visited, counted.comparisons), (4, 8));
+        }
+        let (_, counted) = metrics::measure("minimum", || root.borrow().minimum());
+        assert_eq!(counted.nodes_visited, 4);
+        let (_, counted) = metrics::measure("maximum", || root.borrow().maximum());
+        assert_eq!(counted.nodes_visited, 3);
+        //13 climbs through 7 and 6 up to 15, 6 only looks at its right child 7
+        let thirteen: BstNodeLink = find(&root, 13);
+        let (successor, counted) =
+            metrics::measure("successor", || BstNode::tree_successor(&thirteen));
+        assert_eq!(key_of(&successor), Some(15));
+        assert_eq!(counted.nodes_visited, 3);
+        let six: BstNodeLink = find(&root, 6);
+        let (successor, counted) = metrics::measure("successor", || BstNode::tree_successor(&six));
+        assert_eq!(key_of(&successor), Some(7));
+        assert_eq!(counted.nodes_visited, 1);
+    }
+
+    #[test]
+    fn rotations_are_counted_and_observed() {
+        let root: BstNodeLink = sample_tree();
+        let collector: Rc<observer::CollectingObserver> =
+            Rc::new(observer::CollectingObserver::new());
+        let id = observer::register(
+            collector.clone(),
+            observer::EventFilter::only(&[observer::EventKind::Rotate]),
+        );
+        let (_, counted) = metrics::measure("rotate", || BstNode::rotate_left(&find(&root, 6)));
+        observer::unregister(id);
+        assert_eq!(counted.rotations, 1);
+        //7 -> 15 and 6 -> 7, 7 had no left subtree to hand over to 6
+        assert_eq!(counted.pointer_rewires, 2);
+        assert_eq!(
+            collector.events(),
+            vec![TreeEvent::Rotate {
+                pivot: Some(6),
+                direction: RotateDirection::Left
+            }]
+        );
+    }
+
+    #[test]
+    fn every_search_reports_its_path() {
+        let root: BstNodeLink = sample_tree();
+        let collector: Rc<observer::CollectingObserver> =
+            Rc::new(observer::CollectingObserver::new());
+        let id = observer::register(
+            collector.clone(),
+            observer::EventFilter::only(&[observer::EventKind::Search]),
+        );
+        //15 -> 6 -> 7 -> 13 -> 9
+        assert!(root.borrow().tree_search_correct(&9).is_some());
+        assert!(root.borrow().tree_search(&9).is_some());
+        assert!(root.borrow().tree_search(&5).is_none());
+        assert!(BstNode::tree_search_link(&root, &9).is_some());
+        observer::unregister(id);
+        let found = |path_length: usize| TreeEvent::Search {
+            key: 9,
+            path_length,
+            found: true,
+        };
+        assert_eq!(
+            collector.events(),
+            vec![
+                found(5),
+                found(5),
+                //15 -> 6 -> 3 -> 4
+                TreeEvent::Search {
+                    key: 5,
+                    path_length: 4,
+                    found: false
+                },
+                found(5)
+            ]
+        );
+    }
+
+    #[test]
+    fn move_to_root_brings_the_key_up() {
+        let root: BstNodeLink = sample_tree();
+        let nine: BstNodeLink = BstNode::move_to_root(&root, &9).unwrap();
+        assert!(Rc::ptr_eq(&nine, &find(&nine, 9)));
+        assert!(BstNode::get_parent(&nine).is_none());
+        assert!(BstNode::validate(&nine).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&nine),
+            vec![2, 3, 4, 6, 7, 9, 13, 15, 17, 18, 20]
+        );
+        assert!(BstNode::move_to_root(&nine, &1).is_none());
+        assert!(matches!(
+            BstNode::try_move_to_root(&nine, &1),
+            Err(BstError::NotFound(1))
+        ));
+
+        //inside a subtree the node stops where the subtree root stood
+        let three: BstNodeLink = find(&nine, 3);
+        let parent_of_three: BstNodeLink = BstNode::get_parent(&three).unwrap();
+        let four: BstNodeLink = BstNode::move_to_root(&three, &4).unwrap();
+        assert!(Rc::ptr_eq(
+            &BstNode::get_parent(&four).unwrap(),
+            &parent_of_three
+        ));
+        assert!(BstNode::validate(&nine).is_ok());
+        //the root itself stays put
+        assert!(Rc::ptr_eq(
+            &BstNode::move_to_root(&nine, &9).unwrap(),
+            &nine
+        ));
+    }
+
+    #[test]
+    fn deleting_from_a_deep_clone_leaves_the_original_alone() {
+        let root: BstNodeLink = sample_tree();
+        let copy: BstNodeLink = BstNode::deep_clone(&root);
+        assert!(BstNode::tree_delete_node(&find(&copy, 6)));
+        assert!(BstNode::tree_search_link(&root, &6).is_some());
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(BstNode::inorder_keys(&root).len(), 11);
+        //a subtree copy becomes a root
+        let subtree: BstNodeLink = BstNode::deep_clone(&find(&root, 7));
+        assert!(subtree.borrow().parent.is_none());
+        assert_eq!(BstNode::inorder_keys(&subtree), vec![7, 9, 13]);
+    }
+
+    #[test]
+    fn new_bst_nodelink_is_a_detached_leaf() {
+        let node: BstNodeLink = BstNode::new_bst_nodelink(5);
+        assert_eq!(key_of(&node), Some(5));
+        assert!(node.borrow().parent.is_none());
+        assert!(node.borrow().left.is_none());
+        assert!(node.borrow().right.is_none());
+    }
+
+    #[test]
+    fn get_bst_nodelink_copy_is_a_different_node() {
+        let root: BstNodeLink = sample_tree();
+        let copy: BstNodeLink = root.borrow().get_bst_nodelink_copy();
+        assert_eq!(key_of(&copy), Some(15));
+        assert!(!Rc::ptr_eq(&copy, &root));
+        assert!(Rc::ptr_eq(
+            copy.borrow().left.as_ref().unwrap(),
+            root.borrow().left.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn add_children_point_back_to_the_parent() {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(10);
+        root.borrow_mut().add_left_child(&root, 5);
+        root.borrow_mut().add_right_child(&root, 15);
+        let left: BstNodeLink = root.borrow().left.clone().unwrap();
+        let right: BstNodeLink = root.borrow().right.clone().unwrap();
+        assert_eq!(key_of(&left), Some(5));
+        assert_eq!(key_of(&right), Some(15));
+        assert!(Rc::ptr_eq(&BstNode::get_parent(&left).unwrap(), &root));
+        assert!(Rc::ptr_eq(&BstNode::get_parent(&right).unwrap(), &root));
+        assert!(BstNode::validate(&root).is_ok());
+    }
+
+    #[test]
+    fn tree_search_finds_present_keys_only() {
+        let root: BstNodeLink = sample_tree();
+        for key in [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            assert_eq!(
+                root.borrow().tree_search(&key).map(|x| key_of(&x)),
+                Some(Some(key))
+            );
+            assert_eq!(
+                root.borrow().tree_search_correct(&key).map(|x| key_of(&x)),
+                Some(Some(key))
+            );
+        }
+        for key in [0, 1, 5, 14, 16, 21] {
+            assert!(root.borrow().tree_search(&key).is_none());
+            assert!(root.borrow().tree_search_correct(&key).is_none());
+            assert!(BstNode::tree_search_link(&root, &key).is_none());
+        }
+    }
+
+    #[test]
+    fn tree_search_link_returns_the_live_node() {
+        let root: BstNodeLink = sample_tree();
+        let node: BstNodeLink = find(&root, 6);
+        assert!(Rc::ptr_eq(&node, root.borrow().left.as_ref().unwrap()));
+        assert!(Rc::ptr_eq(&find(&root, 15), &root));
+    }
+
+    #[test]
+    fn minimum_and_maximum() {
+        let root: BstNodeLink = sample_tree();
+        assert_eq!(key_of(&root.borrow().minimum()), Some(2));
+        assert_eq!(key_of(&root.borrow().minimum_correct()), Some(2));
+        assert_eq!(key_of(&root.borrow().maximum()), Some(20));
+        assert_eq!(key_of(&root.borrow().maximum_correct()), Some(20));
+        let leaf: BstNodeLink = BstNode::new_bst_nodelink(1);
+        assert_eq!(key_of(&leaf.borrow().minimum()), Some(1));
+        assert_eq!(key_of(&leaf.borrow().maximum_correct()), Some(1));
+    }
+
+    #[test]
+    fn leftmost_and_rightmost_return_live_nodes() {
+        let root: BstNodeLink = sample_tree();
+        let leftmost: BstNodeLink = BstNode::leftmost(&root);
+        let rightmost: BstNodeLink = BstNode::rightmost(&root);
+        assert_eq!(key_of(&leftmost), Some(2));
+        assert_eq!(key_of(&rightmost), Some(20));
+        assert!(Rc::ptr_eq(&leftmost, &find(&root, 2)));
+        assert!(Rc::ptr_eq(&rightmost, &find(&root, 20)));
+        assert!(Rc::ptr_eq(
+            &BstNode::leftmost(&find(&root, 20)),
+            &find(&root, 20)
+        ));
+    }
+
+    #[test]
+    fn get_root_and_get_parent() {
+        let root: BstNodeLink = sample_tree();
+        let deep: BstNodeLink = find(&root, 9);
+        assert!(Rc::ptr_eq(&BstNode::get_root(&deep), &root));
+        assert!(Rc::ptr_eq(&BstNode::get_root(&root), &root));
+        assert_eq!(
+            BstNode::get_parent(&deep).map(|x| key_of(&x)),
+            Some(Some(13))
+        );
+        assert!(BstNode::get_parent(&root).is_none());
+    }
+
+    #[test]
+    fn tree_successor_walks_up_and_down() {
+        let root: BstNodeLink = sample_tree();
+        assert_eq!(key_of(&BstNode::tree_successor(&find(&root, 6))), Some(7));
+        assert_eq!(key_of(&BstNode::tree_successor(&find(&root, 13))), Some(15));
+        assert_eq!(key_of(&BstNode::tree_successor(&find(&root, 4))), Some(6));
+        //the maximum is its own successor
+        assert_eq!(key_of(&BstNode::tree_successor(&find(&root, 20))), Some(20));
+    }
+
+    #[test]
+    fn tree_successor_simpler_with_a_full_right_child() {
+        let root: BstNodeLink = sample_tree();
+        assert_eq!(
+            key_of(&BstNode::tree_successor_simpler(&find(&root, 15))),
+            Some(17)
+        );
+    }
+
+    #[test]
+    fn successor_and_predecessor_links_cover_every_key() {
+        let root: BstNodeLink = sample_tree();
+        let keys: Vec<i32> = BstNode::inorder_keys(&root);
+        for pair in keys.windows(2) {
+            let successor: BstNodeLink =
+                BstNode::tree_successor_link(&find(&root, pair[0])).unwrap();
+            assert!(Rc::ptr_eq(&successor, &find(&root, pair[1])));
+            let predecessor: BstNodeLink =
+                BstNode::tree_predecessor_link(&find(&root, pair[1])).unwrap();
+            assert!(Rc::ptr_eq(&predecessor, &find(&root, pair[0])));
+        }
+        assert!(BstNode::tree_successor_link(&find(&root, 20)).is_none());
+        assert!(BstNode::tree_predecessor_link(&find(&root, 2)).is_none());
+    }
+
+    #[test]
+    fn tree_insert_places_keys_in_order() {
+        let root: BstNodeLink = sample_tree();
+        let inserted: BstNodeLink = BstNode::tree_insert(&root, &14).unwrap();
+        assert_eq!(key_of(&inserted), Some(14));
+        assert_eq!(
+            BstNode::get_parent(&inserted).map(|x| key_of(&x)),
+            Some(Some(13))
+        );
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&root),
+            vec![2, 3, 4, 6, 7, 9, 13, 14, 15, 17, 18, 20]
+        );
+    }
+
+    #[test]
+    fn tree_insert_rejects_duplicates() {
+        let root: BstNodeLink = sample_tree();
+        assert!(BstNode::tree_insert(&root, &7).is_none());
+        assert!(matches!(
+            BstNode::try_tree_insert(&root, &7),
+            Err(BstError::DuplicateKey(7))
+        ));
+        assert_eq!(
+            BstNode::try_tree_insert(&root, &1).map(|x| key_of(&x)).ok(),
+            Some(Some(1))
+        );
+        assert_eq!(BstNode::inorder_keys(&root).len(), 12);
+    }
+
+    #[test]
+    fn transplant_replaces_a_left_child() {
+        let root: BstNodeLink = sample_tree();
+        let six: BstNodeLink = find(&root, 6);
+        let seven: Option<BstNodeLink> = six.borrow().right.clone();
+        //transplant goes through any node that is not rewired, here the unrelated leaf 2
+        let two: BstNodeLink = find(&root, 2);
+        assert!(two.borrow_mut().transplant(&six, &seven));
+        assert!(Rc::ptr_eq(
+            root.borrow().left.as_ref().unwrap(),
+            seven.as_ref().unwrap()
+        ));
+        assert!(Rc::ptr_eq(
+            &BstNode::get_parent(seven.as_ref().unwrap()).unwrap(),
+            &root
+        ));
+        assert_eq!(BstNode::inorder_keys(&root), vec![7, 9, 13, 15, 17, 18, 20]);
+    }
+
+    #[test]
+    fn transplant_at_the_root_detaches_the_new_root() {
+        let root: BstNodeLink = sample_tree();
+        let eighteen: Option<BstNodeLink> = root.borrow().right.clone();
+        assert!(BstNode::transplant_node(&root, &eighteen));
+        let new_root: BstNodeLink = eighteen.unwrap();
+        assert!(BstNode::get_parent(&new_root).is_none());
+        assert!(BstNode::validate(&new_root).is_ok());
+    }
+
+    #[test]
+    fn transplant_with_none_removes_the_subtree() {
+        let root: BstNodeLink = sample_tree();
+        assert!(BstNode::try_transplant(&find(&root, 18), &None).is_ok());
+        assert!(root.borrow().right.is_none());
+        assert_eq!(BstNode::inorder_keys(&root), vec![2, 3, 4, 6, 7, 9, 13, 15]);
+    }
+
+    #[test]
+    fn transplant_reports_a_dangling_parent() {
+        let child: BstNodeLink = {
+            let parent: BstNodeLink = BstNode::new_bst_nodelink(1);
+            parent.borrow_mut().add_right_child(&parent, 2);
+            let child: BstNodeLink = parent.borrow().right.clone().unwrap();
+            child
+        };
+        assert!(matches!(
+            BstNode::try_transplant(&child, &None),
+            Err(BstError::DanglingParent)
+        ));
+        assert!(!BstNode::transplant_node(&child, &None));
+    }
+
+    #[test]
+    fn tree_delete_leaf_one_child_and_two_children() {
+        let root: BstNodeLink = sample_tree();
+        //leaf
+        assert!(BstNode::tree_delete_node(&find(&root, 9)));
+        //one child
+        assert!(BstNode::tree_delete_node(&find(&root, 7)));
+        //two children, successor is the right child
+        let eighteen: BstNodeLink = find(&root, 18);
+        assert!(find(&root, 2).borrow_mut().tree_delete(&eighteen));
+        //two children, successor deeper inside the right subtree
+        BstNode::tree_insert(&root, &5);
+        assert!(BstNode::try_tree_delete(&find(&root, 3)).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&root),
+            vec![2, 4, 5, 6, 13, 15, 17, 20]
+        );
+        assert!(BstNode::validate(&root).is_ok());
+    }
+
+    #[test]
+    fn deleting_the_root_promotes_the_replacement() {
+        let root: BstNodeLink = sample_tree();
+        let replacement: BstNodeLink = BstNode::tree_delete_replacement(&root).unwrap();
+        assert_eq!(key_of(&replacement), Some(17));
+        assert!(BstNode::tree_delete_node(&root));
+        assert!(BstNode::get_parent(&replacement).is_none());
+        assert!(BstNode::validate(&replacement).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&replacement),
+            vec![2, 3, 4, 6, 7, 9, 13, 17, 18, 20]
+        );
+    }
+
+    #[test]
+    fn deleting_a_lone_root_leaves_nothing() {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(1);
+        assert!(BstNode::tree_delete_replacement(&root).is_none());
+        assert!(BstNode::tree_delete_node(&root));
+    }
+
+    #[test]
+    fn tree_delete_replacement_per_shape() {
+        let root: BstNodeLink = sample_tree();
+        assert!(BstNode::tree_delete_replacement(&find(&root, 9)).is_none());
+        assert_eq!(
+            BstNode::tree_delete_replacement(&find(&root, 13)).map(|x| key_of(&x)),
+            Some(Some(9))
+        );
+        assert_eq!(
+            BstNode::tree_delete_replacement(&find(&root, 6)).map(|x| key_of(&x)),
+            Some(Some(7))
+        );
+    }
+
+    #[test]
+    fn tree_delete_with_key_on_descendants() {
+        let root: BstNodeLink = sample_tree();
+        //direct child of the node the call goes through
+        assert!(root.borrow_mut().tree_delete_with_key(6));
+        //deeper descendant
+        assert!(root.borrow_mut().tree_delete_with_key(13));
+        assert!(!root.borrow_mut().tree_delete_with_key(6));
+        assert!(matches!(
+            root.borrow_mut().try_tree_delete_with_key(100),
+            Err(BstError::NotFound(100))
+        ));
+        assert_eq!(
+            BstNode::inorder_keys(&root),
+            vec![2, 3, 4, 7, 9, 15, 17, 18, 20]
+        );
+        assert!(BstNode::validate(&root).is_ok());
+    }
+
+    #[test]
+    fn tree_delete_with_key_on_the_root() {
+        let root: BstNodeLink = sample_tree();
+        let before: Vec<i32> = BstNode::inorder_keys(&root);
+        assert!(matches!(
+            root.borrow_mut().try_tree_delete_with_key(15),
+            Err(BstError::InvalidInput(_))
+        ));
+        assert!(!root.borrow_mut().tree_delete_with_key(15));
+        //the tree is left untouched, the root goes through tree_delete_node instead
+        assert_eq!(BstNode::inorder_keys(&root), before);
+        assert!(BstNode::validate(&root).is_ok());
+        let replacement: BstNodeLink = BstNode::tree_delete_replacement(&root).unwrap();
+        assert!(BstNode::tree_delete_node(&root));
+        assert!(BstNode::validate(&replacement).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&replacement),
+            vec![2, 3, 4, 6, 7, 9, 13, 17, 18, 20]
+        );
+    }
+
+    #[test]
+    fn tree_delete_with_key_on_an_inner_receiver() {
+        let root: BstNodeLink = sample_tree();
+        let six: BstNodeLink = find(&root, 6);
+        //the receiver's own key is refused, its descendants are deleted in place
+        assert!(!six.borrow_mut().tree_delete_with_key(6));
+        assert!(six.borrow_mut().tree_delete_with_key(7));
+        assert!(six.borrow_mut().tree_delete_with_key(3));
+        assert_eq!(
+            BstNode::inorder_keys(&root),
+            vec![2, 4, 6, 9, 13, 15, 17, 18, 20]
+        );
+        assert!(BstNode::validate(&root).is_ok());
+    }
+
+    #[test]
+    fn try_upgrade_weak_to_strong_cases() {
+        let root: BstNodeLink = sample_tree();
+        assert!(matches!(
+            BstNode::try_upgrade_weak_to_strong(None),
+            Ok(None)
+        ));
+        let parent = BstNode::try_upgrade_weak_to_strong(find(&root, 6).borrow().parent.clone());
+        assert!(Rc::ptr_eq(&parent.unwrap().unwrap(), &root));
+        let dropped: WeakBstNodeLink = Rc::downgrade(&BstNode::new_bst_nodelink(1));
+        assert!(matches!(
+            BstNode::try_upgrade_weak_to_strong(Some(dropped)),
+            Err(BstError::DanglingParent)
+        ));
+    }
+
+    #[test]
+    fn validate_catches_broken_order_and_links() {
+        let root: BstNodeLink = sample_tree();
+        assert!(BstNode::validate(&root).is_ok());
+        find(&root, 13).borrow_mut().key = Some(16);
+        assert!(matches!(
+            BstNode::validate(&root),
+            Err(BstError::InvariantViolation(_))
+        ));
+
+        let other: BstNodeLink = sample_tree();
+        find(&other, 3).borrow_mut().parent = None;
+        assert!(matches!(
+            BstNode::validate(&other),
+            Err(BstError::InvariantViolation(_))
+        ));
+    }
+
+    #[test]
+    fn split_around_present_and_absent_keys() {
+        let (left, right) = BstNode::split(&sample_tree(), &7);
+        assert_eq!(
+            BstNode::inorder_keys(left.as_ref().unwrap()),
+            vec![2, 3, 4, 6]
+        );
+        assert_eq!(
+            BstNode::inorder_keys(right.as_ref().unwrap()),
+            vec![7, 9, 13, 15, 17, 18, 20]
+        );
+        assert!(BstNode::validate(left.as_ref().unwrap()).is_ok());
+        assert!(BstNode::validate(right.as_ref().unwrap()).is_ok());
+
+        let (left, right) = BstNode::split(&sample_tree(), &1);
+        assert!(left.is_none());
+        assert_eq!(BstNode::inorder_keys(right.as_ref().unwrap()).len(), 11);
+        let (left, right) = BstNode::split(&sample_tree(), &21);
+        assert!(right.is_none());
+        assert_eq!(BstNode::inorder_keys(left.as_ref().unwrap()).len(), 11);
+    }
+
+    #[test]
+    fn join_restores_a_split() {
+        let (left, right) = BstNode::split(&sample_tree(), &10);
+        let joined: BstNodeLink = BstNode::join(left, right).unwrap();
+        assert!(BstNode::validate(&joined).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&joined),
+            vec![2, 3, 4, 6, 7, 9, 13, 15, 17, 18, 20]
+        );
+        assert!(BstNode::join(None, None).is_none());
+        let single: BstNodeLink = BstNode::new_bst_nodelink(1);
+        assert!(Rc::ptr_eq(
+            &BstNode::join(None, Some(single.clone())).unwrap(),
+            &single
+        ));
+        assert!(Rc::ptr_eq(
+            &BstNode::join(Some(single.clone()), None).unwrap(),
+            &single
+        ));
+    }
+
+    #[test]
+    fn try_join_checks_the_ordering() {
+        let low: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 2, 3]).unwrap();
+        let high: BstNodeLink = BstNode::build_from_sorted_keys(&[3, 4]).unwrap();
+        assert!(matches!(
+            BstNode::try_join(Some(low.clone()), Some(high)),
+            Err(BstError::InvariantViolation(_))
+        ));
+        let high: BstNodeLink = BstNode::build_from_sorted_keys(&[4, 5]).unwrap();
+        let joined: BstNodeLink = BstNode::try_join(Some(low), Some(high)).unwrap().unwrap();
+        assert_eq!(BstNode::inorder_keys(&joined), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn union_merges_without_touching_the_inputs() {
+        let first: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 3, 5]).unwrap();
+        let second: BstNodeLink = sample_tree();
+        let merged: BstNodeLink = BstNode::union(&first, &second).unwrap();
+        assert_eq!(
+            BstNode::inorder_keys(&merged),
+            vec![1, 2, 3, 4, 5, 6, 7, 9, 13, 15, 17, 18, 20]
+        );
+        assert!(BstNode::validate(&merged).is_ok());
+        assert_eq!(BstNode::inorder_keys(&first), vec![1, 3, 5]);
+        assert_eq!(BstNode::inorder_keys(&second).len(), 11);
+    }
+
+    #[test]
+    fn split_relinks_the_nodes_in_place() {
+        let root: BstNodeLink = sample_tree();
+        let six: BstNodeLink = find(&root, 6);
+        let thirteen: BstNodeLink = find(&root, 13);
+        let (left, right) = BstNode::split(&root, &13);
+        let (left, right) = (left.unwrap(), right.unwrap());
+        assert!(left.borrow().parent.is_none());
+        assert!(right.borrow().parent.is_none());
+        assert!(Rc::ptr_eq(&left, &six));
+        assert!(Rc::ptr_eq(&BstNode::leftmost(&right), &thirteen));
+        assert!(BstNode::validate(&left).is_ok());
+        assert!(BstNode::validate(&right).is_ok());
+    }
+
+    #[test]
+    fn join_rewires_the_parent_of_a_deep_maximum() {
+        let left: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 2, 3, 4, 5]).unwrap();
+        let maximum: BstNodeLink = BstNode::rightmost(&left);
+        let right: BstNodeLink = BstNode::build_from_sorted_keys(&[8, 9]).unwrap();
+        let joined: BstNodeLink = BstNode::join(Some(left), Some(right.clone())).unwrap();
+        assert!(Rc::ptr_eq(&joined, &maximum));
+        assert!(joined.borrow().parent.is_none());
+        let parent: BstNodeLink =
+            BstNode::upgrade_weak_to_strong(right.borrow().parent.clone()).unwrap();
+        assert!(Rc::ptr_eq(&parent, &joined));
+        assert!(BstNode::validate(&joined).is_ok());
+        assert_eq!(BstNode::inorder_keys(&joined), vec![1, 2, 3, 4, 5, 8, 9]);
+    }
+
+    #[test]
+    fn union_keeps_shared_keys_once() {
+        let first: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 4, 6, 9]).unwrap();
+        let second: BstNodeLink = BstNode::build_from_sorted_keys(&[4, 5, 9, 12]).unwrap();
+        let merged: BstNodeLink = BstNode::union(&first, &second).unwrap();
+        assert_eq!(BstNode::inorder_keys(&merged), vec![1, 4, 5, 6, 9, 12]);
+        assert!(merged.borrow().parent.is_none());
+        assert!(BstNode::validate(&merged).is_ok());
+        let same: BstNodeLink = BstNode::union(&first, &first).unwrap();
+        assert_eq!(BstNode::inorder_keys(&same), vec![1, 4, 6, 9]);
+        assert!(!Rc::ptr_eq(&same, &first));
+    }
+
+    #[test]
+    fn union_of_nil_keys_only_is_empty() {
+        let first: BstNodeLink = BstNode::new_bst_nodelink(1);
+        first.borrow_mut().key = None;
+        let second: BstNodeLink = BstNode::new_bst_nodelink(2);
+        second.borrow_mut().key = None;
+        assert!(BstNode::union(&first, &second).is_none());
+        second.borrow_mut().key = Some(2);
+        let merged: BstNodeLink = BstNode::union(&first, &second).unwrap();
+        assert_eq!(BstNode::inorder_keys(&merged), vec![2]);
+    }
+
+    #[test]
+    fn keys_iterates_lazily_in_order() {
+        let root: BstNodeLink = sample_tree();
+        let mut keys: InorderKeys = BstNode::keys(&root);
+        assert_eq!(keys.next(), Some(2));
+        assert_eq!(keys.next(), Some(3));
+        assert_eq!(keys.count(), 9);
+        assert_eq!(
+            BstNode::inorder_keys(&BstNode::new_bst_nodelink(8)),
+            vec![8]
+        );
+    }
+
+    #[test]
+    fn build_from_sorted_keys_is_balanced() {
+        assert!(BstNode::build_from_sorted_keys(&[]).is_none());
+        let root: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 2, 3, 4, 5, 6, 7]).unwrap();
+        assert_eq!(key_of(&root), Some(4));
+        assert_eq!(key_of(root.borrow().left.as_ref().unwrap()), Some(2));
+        assert_eq!(key_of(root.borrow().right.as_ref().unwrap()), Some(6));
+        assert!(BstNode::validate(&root).is_ok());
+    }
+
+    //a right chain 0, 1, .., depth - 1, every node is the right child of the one before
+    fn right_chain(depth: i32) -> BstNodeLink {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(0);
+        let mut last: BstNodeLink = root.clone();
+        for key in 1..depth {
+            let next: BstNodeLink = BstNode::new_with_rc_pointer(key);
+            BstNode::set_right_child(&last, Some(next.clone()));
+            last = next;
+        }
+        root
+    }
+
+    //take a tree apart node by node, the default drop recurses once per level
+    fn dismantle(root: BstNodeLink) {
+        let mut stack: Vec<BstNodeLink> = vec![root];
+        while let Some(node) = stack.pop() {
+            let mut borrowed = node.borrow_mut();
+            stack.extend(borrowed.left.take());
+            stack.extend(borrowed.right.take());
+        }
+    }
+
+    #[test]
+    fn deep_chains_fit_a_small_stack() {
+        let depth: i32 = 200_000;
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                let root: BstNodeLink = right_chain(depth);
+                assert!(BstNode::validate(&root).is_ok());
+                let copy: BstNodeLink = BstNode::deep_clone(&root);
+                assert!(BstNode::validate(&copy).is_ok());
+                assert_eq!(BstNode::rightmost(&copy).borrow().key, Some(depth - 1));
+                let (left, right) = BstNode::split(&root, &(depth / 2));
+                let (left, right) = (left.unwrap(), right.unwrap());
+                assert!(BstNode::validate(&left).is_ok());
+                assert!(BstNode::validate(&right).is_ok());
+                assert_eq!(BstNode::rightmost(&left).borrow().key, Some(depth / 2 - 1));
+                assert_eq!(right.borrow().key, Some(depth / 2));
+
+                let chain: NodeLink = Node::new_nodelink(0);
+                let mut last: NodeLink = chain.clone();
+                for value in 1..depth {
+                    last.borrow_mut().add_right_child(&last, value);
+                    let next: NodeLink = last.borrow().right.clone().unwrap();
+                    last = next;
+                }
+                let converted: BstNodeLink = BstNode::try_from_nodelink(&chain).unwrap();
+                assert!(BstNode::validate(&converted).is_ok());
+                assert_eq!(BstNode::rightmost(&converted).borrow().key, Some(depth - 1));
+
+                let mut stack: Vec<NodeLink> = vec![chain];
+                while let Some(node) = stack.pop() {
+                    stack.extend(node.borrow_mut().right.take());
+                }
+                for tree in [copy, left, right, converted] {
+                    dismantle(tree);
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,407 @@
+use crate::structure::binary_tree;
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+//this package implement comparisons between two BstNode trees, from plain structural equality
+//to a per node diff. Nodes are told apart by key, NIL keys are left out of keys and diffs.
+
+/// Which child of its parent a node is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// One difference between the trees before and after, see `diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeChange {
+    /// The key only exists after, `parent` is `None` when it is the new root.
+    Added {
+        key: i32,
+        parent: Option<(i32, Side)>,
+    },
+    /// The key only exists before.
+    Removed { key: i32 },
+    /// The key hangs under another parent, `None` stands for being the root.
+    Reparented {
+        key: i32,
+        from: Option<(i32, Side)>,
+        to: Option<(i32, Side)>,
+    },
+    /// Same parent, the other side.
+    Moved { key: i32, parent: i32, to: Side },
+}
+
+impl NodeChange {
+    pub fn key(&self) -> i32 {
+        match self {
+            NodeChange::Added { key, .. }
+            | NodeChange::Removed { key }
+            | NodeChange::Reparented { key, .. }
+            | NodeChange::Moved { key, .. } => *key,
+        }
+    }
+}
+
+//where a node hangs, written as "root" or "left of 7"
+fn placement(position: &Option<(i32, Side)>) -> String {
+    match position {
+        None => "root".to_string(),
+        Some((parent, side)) => format!("{} of {}", side, parent),
+    }
+}
+
+impl fmt::Display for NodeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeChange::Added { key, parent } => {
+                write!(f, "+ {} added as {}", key, placement(parent))
+            }
+            NodeChange::Removed { key } => write!(f, "- {} removed", key),
+            NodeChange::Reparented { key, from, to } => write!(
+                f,
+                "~ {} re-parented from {} to {}",
+                key,
+                placement(from),
+                placement(to)
+            ),
+            NodeChange::Moved { key, parent, to } => {
+                write!(f, "> {} moved to the {} of {}", key, to, parent)
+            }
+        }
+    }
+}
+
+/// Every change between two trees, ordered by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    pub changes: Vec<NodeChange>,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /**
+     * The change recorded for key, None if the node kept its place
+     */
+    pub fn change_of(&self, key: i32) -> Option<&NodeChange> {
+        self.changes.iter().find(|x| x.key() == key)
+    }
+}
+
+impl fmt::Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Same shape with the same key in every position, parent pointers are not compared
+ */
+pub fn structurally_equal(first: &BstNodeLink, second: &BstNodeLink) -> bool {
+    *first.borrow() == *second.borrow()
+}
+
+/**
+ * Structurally equal once the children of any nodes are allowed to be swapped
+ */
+pub fn is_isomorphic(first: &BstNodeLink, second: &BstNodeLink) -> bool {
+    //both trees are numbered against the same table, so equal numbers mean equal shapes
+    let mut shapes: HashMap<Shape, usize> = HashMap::new();
+    shape_number(first, &mut shapes) == shape_number(second, &mut shapes)
+}
+
+//key of a node with the numbers of its child shapes, the smaller number first
+type Shape = (Option<i32>, usize, usize);
+
+//number the shape of every subtree under node bottom-up, two subtrees get the same number
+//when they are equal once children may be swapped, 0 stands for a missing child.
+//The postorder walk keeps the numbers of the finished subtrees on a stack instead of recursing
+fn shape_number(node: &BstNodeLink, shapes: &mut HashMap<Shape, usize>) -> usize {
+    let mut numbers: Vec<usize> = Vec::new();
+    for x in binary_tree::postorder(node) {
+        let borrowed = x.borrow();
+        //the right subtree was finished last, so its number is on top
+        let right: usize = match borrowed.right {
+            Some(_) => numbers.pop().unwrap_or(0),
+            None => 0,
+        };
+        let left: usize = match borrowed.left {
+            Some(_) => numbers.pop().unwrap_or(0),
+            None => 0,
+        };
+        let next: usize = shapes.len() + 1;
+        let shape: Shape = (borrowed.key, left.min(right), left.max(right));
+        numbers.push(*shapes.entry(shape).or_insert(next));
+    }
+    numbers.pop().unwrap_or(0)
+}
+
+/**
+ * Both trees hold the same keys, whatever their shape
+ */
+pub fn same_keys(first: &BstNodeLink, second: &BstNodeLink) -> bool {
+    let mut a: Vec<i32> = BstNode::inorder_keys(first);
+    let mut b: Vec<i32> = BstNode::inorder_keys(second);
+    //inorder is already sorted for a valid BST, sort anyway so broken trees compare too
+    a.sort_unstable();
+    b.sort_unstable();
+    a == b
+}
+
+/**
+ * The same keys arranged in another shape, e.g. the same set before and after a rotation
+ */
+pub fn same_keys_different_shape(first: &BstNodeLink, second: &BstNodeLink) -> bool {
+    same_keys(first, second) && !structurally_equal(first, second)
+}
+
+//parent key and side of every keyed node, the root maps to None
+fn placements(root: &BstNodeLink) -> BTreeMap<i32, Option<(i32, Side)>> {
+    let mut result: BTreeMap<i32, Option<(i32, Side)>> = BTreeMap::new();
+    let mut stack: Vec<(BstNodeLink, Option<(i32, Side)>)> = vec![(root.clone(), None)];
+    while let Some((node, position)) = stack.pop() {
+        let borrowed = node.borrow();
+        let key: i32 = match borrowed.key {
+            Some(key) => key,
+            None => continue,
+        };
+        result.insert(key, position);
+        if let Some(ref left) = borrowed.left {
+            stack.push((left.clone(), Some((key, Side::Left))));
+        }
+        if let Some(ref right) = borrowed.right {
+            stack.push((right.clone(), Some((key, Side::Right))));
+        }
+    }
+    result
+}
+
+/// Lists how every node changed place between two trees.
+///
+/// # Arguments
+///
+/// * `before` - Root of the tree before the operation.
+/// * `after` - Root of the tree after the operation.
+///
+/// # Returns
+///
+/// One change per key that was added, removed, re-parented or moved to the other
+/// side of the same parent. Keys that kept parent and side are left out, so an
+/// empty diff means both trees are structurally equal.
+pub fn diff(before: &BstNodeLink, after: &BstNodeLink) -> TreeDiff {
+    let old: BTreeMap<i32, Option<(i32, Side)>> = placements(before);
+    let new: BTreeMap<i32, Option<(i32, Side)>> = placements(after);
+    let mut keys: Vec<i32> = old.keys().chain(new.keys()).copied().collect();
+    keys.sort_unstable();
+    keys.dedup();
+    let mut changes: Vec<NodeChange> = Vec::new();
+    for key in keys {
+        //the keys that kept parent and side are dropped before the match
+        if old.get(&key) == new.get(&key) {
+            continue;
+        }
+        let change: Option<NodeChange> = match (old.get(&key), new.get(&key)) {
+            (None, Some(to)) => Some(NodeChange::Added { key, parent: *to }),
+            (Some(_), None) => Some(NodeChange::Removed { key }),
+            (Some(Some((from, _))), Some(Some((to, side)))) if from == to => {
+                Some(NodeChange::Moved {
+                    key,
+                    parent: *to,
+                    to: *side,
+                })
+            }
+            (Some(from), Some(to)) => Some(NodeChange::Reparented {
+                key,
+                from: *from,
+                to: *to,
+            }),
+            (None, None) => None,
+        };
+        changes.extend(change);
+    }
+    TreeDiff { changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //15(6(3(2,4),7(,13(9,))),18(17,20))
+    fn sample_tree() -> BstNodeLink {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(15);
+        for key in [6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            BstNode::tree_insert(&root, &key);
+        }
+        root
+    }
+
+    fn mirror(node: &BstNodeLink) -> BstNodeLink {
+        let copy: BstNodeLink = BstNode::new_bst_nodelink(node.borrow().key.unwrap());
+        let left: Option<BstNodeLink> = node.borrow().right.as_ref().map(mirror);
+        let right: Option<BstNodeLink> = node.borrow().left.as_ref().map(mirror);
+        copy.borrow_mut().left = left;
+        copy.borrow_mut().right = right;
+        copy
+    }
+
+    #[test]
+    fn equality_isomorphism_and_keys() {
+        let root: BstNodeLink = sample_tree();
+        let copy: BstNodeLink = BstNode::deep_clone(&root);
+        assert!(structurally_equal(&root, &copy));
+        assert!(is_isomorphic(&root, &copy));
+        assert!(!same_keys_different_shape(&root, &copy));
+
+        let mirrored: BstNodeLink = mirror(&root);
+        assert!(!structurally_equal(&root, &mirrored));
+        assert!(is_isomorphic(&root, &mirrored));
+        assert!(same_keys_different_shape(&root, &mirrored));
+
+        let balanced: BstNodeLink =
+            BstNode::build_from_sorted_keys(&BstNode::inorder_keys(&root)).unwrap();
+        assert!(!is_isomorphic(&root, &balanced));
+        assert!(same_keys_different_shape(&root, &balanced));
+
+        BstNode::tree_insert(&copy, &1);
+        assert!(!same_keys(&root, &copy));
+    }
+
+    #[test]
+    fn isomorphism_of_long_chains_fits_a_small_stack() {
+        let depth: i32 = 200_000;
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                //the same keys hanging as a left chain and as a right chain
+                let chain = |left: bool, last_key: i32| -> BstNodeLink {
+                    let root: BstNodeLink = BstNode::new_bst_nodelink(0);
+                    let mut last: BstNodeLink = root.clone();
+                    for key in 1..depth {
+                        let key: i32 = if key == depth - 1 { last_key } else { key };
+                        let next: Option<BstNodeLink> = if left {
+                            last.borrow_mut().add_left_child(&last, key);
+                            last.borrow().left.clone()
+                        } else {
+                            last.borrow_mut().add_right_child(&last, key);
+                            last.borrow().right.clone()
+                        };
+                        last = next.unwrap();
+                    }
+                    root
+                };
+                let left: BstNodeLink = chain(true, depth - 1);
+                let right: BstNodeLink = chain(false, depth - 1);
+                let other: BstNodeLink = chain(false, -1);
+                assert!(is_isomorphic(&left, &right));
+                assert!(!is_isomorphic(&left, &other));
+                //the default drop recurses once per level, take the chains apart first
+                let mut stack: Vec<BstNodeLink> = vec![left, right, other];
+                while let Some(node) = stack.pop() {
+                    let mut borrowed = node.borrow_mut();
+                    stack.extend(borrowed.left.take());
+                    stack.extend(borrowed.right.take());
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn diff_of_equal_trees_is_empty() {
+        let root: BstNodeLink = sample_tree();
+        let result: TreeDiff = diff(&root, &BstNode::deep_clone(&root));
+        assert!(result.is_empty());
+        assert_eq!(result.to_string(), "no changes\n");
+    }
+
+    #[test]
+    fn diff_after_delete_and_insert() {
+        let before: BstNodeLink = sample_tree();
+        let after: BstNodeLink = BstNode::deep_clone(&before);
+        //6 has two children, its successor 7 takes its place and adopts 3
+        let six: BstNodeLink = BstNode::tree_search_link(&after, &6).unwrap();
+        assert!(BstNode::tree_delete_node(&six));
+        BstNode::tree_insert(&after, &16);
+        let result: TreeDiff = diff(&before, &after);
+        assert_eq!(result.change_of(6), Some(&NodeChange::Removed { key: 6 }));
+        assert_eq!(
+            result.change_of(16),
+            Some(&NodeChange::Added {
+                key: 16,
+                parent: Some((17, Side::Left))
+            })
+        );
+        assert_eq!(
+            result.change_of(7),
+            Some(&NodeChange::Reparented {
+                key: 7,
+                from: Some((6, Side::Right)),
+                to: Some((15, Side::Left))
+            })
+        );
+        assert_eq!(
+            result.change_of(3),
+            Some(&NodeChange::Reparented {
+                key: 3,
+                from: Some((6, Side::Left)),
+                to: Some((7, Side::Left))
+            })
+        );
+        //2 and 4 stayed under 3, 18 under 15
+        assert!(result.change_of(2).is_none());
+        assert!(result.change_of(18).is_none());
+        let text: String = result.to_string();
+        assert!(text.contains("- 6 removed"));
+        assert!(text.contains("+ 16 added as left of 17"));
+        assert!(text.contains("~ 7 re-parented from right of 6 to left of 15"));
+    }
+
+    #[test]
+    fn diff_reports_side_moves_and_new_roots() {
+        let before: BstNodeLink = sample_tree();
+        let mirrored: BstNodeLink = mirror(&before);
+        let result: TreeDiff = diff(&before, &mirrored);
+        assert_eq!(
+            result.change_of(6),
+            Some(&NodeChange::Moved {
+                key: 6,
+                parent: 15,
+                to: Side::Right
+            })
+        );
+        assert!(result.to_string().contains("> 6 moved to the right of 15"));
+
+        let other: BstNodeLink = BstNode::new_bst_nodelink(6);
+        BstNode::tree_insert(&other, &15);
+        let result: TreeDiff = diff(&before, &other);
+        assert_eq!(
+            result.change_of(6),
+            Some(&NodeChange::Reparented {
+                key: 6,
+                from: Some((15, Side::Left)),
+                to: None
+            })
+        );
+        assert!(result.to_string().contains("from left of 15 to root"));
+    }
+}
@@ -0,0 +1,435 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::rc::Rc;
+
+//this package implement cursors over a BstNode tree.
+//A cursor sits either on a node or on the ghost position, which lies past the last key
+//and before the first key at the same time: moving next from the ghost lands on the minimum,
+//moving prev from the ghost lands on the maximum.
+
+/// Read only cursor, see the package note for the ghost position.
+pub struct Cursor {
+    root: Option<BstNodeLink>,
+    current: Option<BstNodeLink>,
+}
+
+impl Cursor {
+    /**
+     * Cursor sitting on the root
+     */
+    pub fn new(root: &BstNodeLink) -> Self {
+        Cursor {
+            root: Some(root.clone()),
+            current: Some(root.clone()),
+        }
+    }
+
+    /**
+     * Cursor sitting on the minimum
+     */
+    pub fn front(root: &BstNodeLink) -> Self {
+        Cursor {
+            root: Some(root.clone()),
+            current: Some(BstNode::leftmost(root)),
+        }
+    }
+
+    /**
+     * Cursor sitting on the maximum
+     */
+    pub fn back(root: &BstNodeLink) -> Self {
+        Cursor {
+            root: Some(root.clone()),
+            current: Some(BstNode::rightmost(root)),
+        }
+    }
+
+    /**
+     * Cursor sitting on the node holding key, on the ghost position if key is absent
+     */
+    pub fn find(root: &BstNodeLink, key: &i32) -> Self {
+        Cursor {
+            root: Some(root.clone()),
+            current: BstNode::tree_search_link(root, key),
+        }
+    }
+
+    pub fn current(&self) -> Option<BstNodeLink> {
+        self.current.clone()
+    }
+
+    pub fn key(&self) -> Option<i32> {
+        self.current.as_ref().and_then(|x| x.borrow().key)
+    }
+
+    pub fn is_ghost(&self) -> bool {
+        self.current.is_none()
+    }
+
+    /**
+     * Move to the successor, past the maximum the cursor lands on the ghost position
+     */
+    pub fn move_next(&mut self) {
+        self.current = step_next(&self.root, &self.current);
+    }
+
+    /**
+     * Move to the predecessor, before the minimum the cursor lands on the ghost position
+     */
+    pub fn move_prev(&mut self) {
+        self.current = step_prev(&self.root, &self.current);
+    }
+
+    /**
+     * Move to the parent, return false and stay if there is none
+     */
+    pub fn move_parent(&mut self) -> bool {
+        step_to(&mut self.current, BstNode::get_parent)
+    }
+
+    /**
+     * Move to the left child, return false and stay if there is none
+     */
+    pub fn move_left(&mut self) -> bool {
+        step_to(&mut self.current, |x| x.borrow().left.clone())
+    }
+
+    /**
+     * Move to the right child, return false and stay if there is none
+     */
+    pub fn move_right(&mut self) -> bool {
+        step_to(&mut self.current, |x| x.borrow().right.clone())
+    }
+
+    pub fn peek_next(&self) -> Option<i32> {
+        step_next(&self.root, &self.current).and_then(|x| x.borrow().key)
+    }
+
+    pub fn peek_prev(&self) -> Option<i32> {
+        step_prev(&self.root, &self.current).and_then(|x| x.borrow().key)
+    }
+}
+
+/// Cursor that can also edit the tree at its position.
+///
+/// It borrows the root handle mutably, so removing or inserting at the root keeps the caller's handle valid.
+pub struct CursorMut<'a> {
+    root: &'a mut Option<BstNodeLink>,
+    current: Option<BstNodeLink>,
+}
+
+impl<'a> CursorMut<'a> {
+    /**
+     * Cursor sitting on the root, on the ghost position for an empty tree
+     */
+    pub fn new(root: &'a mut Option<BstNodeLink>) -> Self {
+        let current: Option<BstNodeLink> = root.clone();
+        CursorMut { root, current }
+    }
+
+    /**
+     * Cursor sitting on the node holding key, on the ghost position if key is absent
+     */
+    pub fn find(root: &'a mut Option<BstNodeLink>, key: &i32) -> Self {
+        let current: Option<BstNodeLink> = root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, key));
+        CursorMut { root, current }
+    }
+
+    pub fn current(&self) -> Option<BstNodeLink> {
+        self.current.clone()
+    }
+
+    pub fn key(&self) -> Option<i32> {
+        self.current.as_ref().and_then(|x| x.borrow().key)
+    }
+
+    pub fn is_ghost(&self) -> bool {
+        self.current.is_none()
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = step_next(self.root, &self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = step_prev(self.root, &self.current);
+    }
+
+    pub fn move_parent(&mut self) -> bool {
+        step_to(&mut self.current, BstNode::get_parent)
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        step_to(&mut self.current, |x| x.borrow().left.clone())
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        step_to(&mut self.current, |x| x.borrow().right.clone())
+    }
+
+    pub fn peek_next(&self) -> Option<i32> {
+        step_next(self.root, &self.current).and_then(|x| x.borrow().key)
+    }
+
+    pub fn peek_prev(&self) -> Option<i32> {
+        step_prev(self.root, &self.current).and_then(|x| x.borrow().key)
+    }
+
+    /// Removes the node under the cursor and moves the cursor to its successor.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(key)` of the removed node.
+    /// * `None` if the cursor sits on the ghost position.
+    pub fn remove_current(&mut self) -> Option<i32> {
+        let node: BstNodeLink = self.current.clone()?;
+        //the successor node survives the deletion, when it is moved it takes node's place
+        let successor: Option<BstNodeLink> = BstNode::tree_successor_link(&node);
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&node);
+        let is_root: bool = self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, &node));
+        BstNode::tree_delete_node(&node);
+        if is_root {
+            *self.root = replacement;
+        }
+        self.current = successor;
+        let key: Option<i32> = node.borrow().key;
+        key
+    }
+
+    /// Inserts `key` right before the cursor position in key order.
+    ///
+    /// On the ghost position the key is appended after the maximum. The cursor does not move.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(BstNodeLink)` with the new node.
+    /// * `None` if `key` does not fit strictly between the predecessor and the current key.
+    pub fn insert_before(&mut self, key: i32) -> Option<BstNodeLink> {
+        let root: BstNodeLink = match self.root {
+            Some(ref x) => x.clone(),
+            None => return Some(self.insert_root(key)),
+        };
+        let predecessor: Option<BstNodeLink> = step_prev(self.root, &self.current);
+        if !fits_between(&predecessor, key, &self.current) {
+            return None;
+        }
+        let node: BstNodeLink = match self.current {
+            None => attach_right(&BstNode::rightmost(&root), key),
+            Some(ref current) => {
+                if current.borrow().left.is_none() {
+                    attach_left(current, key)
+                } else {
+                    //a predecessor inside the left subtree has no right child
+                    attach_right(&predecessor.unwrap(), key)
+                }
+            }
+        };
+        Some(node)
+    }
+
+    /// Inserts `key` right after the cursor position in key order.
+    ///
+    /// On the ghost position the key is prepended before the minimum. The cursor does not move.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(BstNodeLink)` with the new node.
+    /// * `None` if `key` does not fit strictly between the current key and the successor.
+    pub fn insert_after(&mut self, key: i32) -> Option<BstNodeLink> {
+        let root: BstNodeLink = match self.root {
+            Some(ref x) => x.clone(),
+            None => return Some(self.insert_root(key)),
+        };
+        let successor: Option<BstNodeLink> = step_next(self.root, &self.current);
+        if !fits_between(&self.current, key, &successor) {
+            return None;
+        }
+        let node: BstNodeLink = match self.current {
+            None => attach_left(&BstNode::leftmost(&root), key),
+            Some(ref current) => {
+                if current.borrow().right.is_none() {
+                    attach_right(current, key)
+                } else {
+                    //a successor inside the right subtree has no left child
+                    attach_left(&successor.unwrap(), key)
+                }
+            }
+        };
+        Some(node)
+    }
+
+    fn insert_root(&mut self, key: i32) -> BstNodeLink {
+        let node: BstNodeLink = BstNode::new_bst_nodelink(key);
+        *self.root = Some(node.clone());
+        node
+    }
+}
+
+fn step_next(root: &Option<BstNodeLink>, current: &Option<BstNodeLink>) -> Option<BstNodeLink> {
+    match current {
+        Some(x) => BstNode::tree_successor_link(x),
+        None => root.as_ref().map(BstNode::leftmost),
+    }
+}
+
+fn step_prev(root: &Option<BstNodeLink>, current: &Option<BstNodeLink>) -> Option<BstNodeLink> {
+    match current {
+        Some(x) => BstNode::tree_predecessor_link(x),
+        None => root.as_ref().map(BstNode::rightmost),
+    }
+}
+
+//move current along next_of, stay put if there is nothing there
+fn step_to(
+    current: &mut Option<BstNodeLink>,
+    next_of: impl Fn(&BstNodeLink) -> Option<BstNodeLink>,
+) -> bool {
+    match current.as_ref().and_then(next_of) {
+        Some(x) => {
+            *current = Some(x);
+            true
+        }
+        None => false,
+    }
+}
+
+//true if lower < key < upper, a missing bound is unbounded
+fn fits_between(lower: &Option<BstNodeLink>, key: i32, upper: &Option<BstNodeLink>) -> bool {
+    let above_lower: bool = lower.as_ref().is_none_or(|x| x.borrow().key < Some(key));
+    let below_upper: bool = upper.as_ref().is_none_or(|x| x.borrow().key > Some(key));
+    above_lower && below_upper
+}
+
+fn attach_left(node: &BstNodeLink, key: i32) -> BstNodeLink {
+    let mut borrowed = node.borrow_mut();
+    borrowed.add_left_child(node, key);
+    borrowed.left.clone().unwrap()
+}
+
+fn attach_right(node: &BstNodeLink, key: i32) -> BstNodeLink {
+    let mut borrowed = node.borrow_mut();
+    borrowed.add_right_child(node, key);
+    borrowed.right.clone().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> BstNodeLink {
+        BstNode::build_from_sorted_keys(&[2, 4, 6, 8, 10, 12, 14]).unwrap()
+    }
+
+    #[test]
+    fn walking_forward_and_back_through_the_ghost() {
+        let root: BstNodeLink = sample_tree();
+        let mut cursor: Cursor = Cursor::front(&root);
+        let mut keys: Vec<i32> = Vec::new();
+        while let Some(key) = cursor.key() {
+            keys.push(key);
+            cursor.move_next();
+        }
+        assert_eq!(keys, vec![2, 4, 6, 8, 10, 12, 14]);
+        assert!(cursor.is_ghost());
+        assert_eq!(cursor.peek_next(), Some(2));
+        assert_eq!(cursor.peek_prev(), Some(14));
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(14));
+        let mut back: Cursor = Cursor::back(&root);
+        back.move_prev();
+        assert_eq!(back.key(), Some(12));
+        assert_eq!(Cursor::new(&root).key(), Some(8));
+        assert!(Cursor::find(&root, &5).is_ghost());
+        assert_eq!(Cursor::find(&root, &6).peek_prev(), Some(4));
+    }
+
+    #[test]
+    fn moving_along_the_links() {
+        let root: BstNodeLink = sample_tree();
+        let mut cursor: Cursor = Cursor::new(&root);
+        assert!(!cursor.move_parent());
+        assert!(cursor.move_left());
+        assert!(cursor.move_right());
+        assert_eq!(cursor.key(), Some(6));
+        assert!(!cursor.move_left());
+        assert_eq!(cursor.key(), Some(6));
+        assert!(cursor.move_parent());
+        assert_eq!(cursor.key(), Some(4));
+        let mut ghost: Cursor = Cursor::find(&root, &1);
+        assert!(!ghost.move_parent());
+        assert!(ghost.is_ghost());
+    }
+
+    #[test]
+    fn remove_current_moves_to_the_successor() {
+        let mut root: Option<BstNodeLink> = Some(sample_tree());
+        let mut cursor: CursorMut = CursorMut::find(&mut root, &4);
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.key(), Some(6));
+        //the root has both children, its successor takes its place
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(8));
+        assert_eq!(cursor.key(), Some(10));
+        let root_node: BstNodeLink = root.clone().unwrap();
+        assert_eq!(root_node.borrow().key, Some(10));
+        assert!(BstNode::get_parent(&root_node).is_none());
+        assert_eq!(BstNode::inorder_keys(&root_node), vec![2, 6, 10, 12, 14]);
+        let left: BstNodeLink = root_node.borrow().left.clone().unwrap();
+        assert!(std::rc::Rc::ptr_eq(
+            &BstNode::get_parent(&left).unwrap(),
+            &root_node
+        ));
+    }
+
+    #[test]
+    fn removing_everything_empties_the_root_handle() {
+        let mut root: Option<BstNodeLink> = Some(sample_tree());
+        let mut cursor: CursorMut = CursorMut::new(&mut root);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(12));
+        while cursor.remove_current().is_some() {}
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(2));
+        while cursor.remove_current().is_some() {}
+        assert!(cursor.is_ghost());
+        assert!(cursor.remove_current().is_none());
+        assert!(root.is_none());
+    }
+
+    #[test]
+    fn insert_before_and_after_keep_the_order() {
+        let mut root: Option<BstNodeLink> = Some(sample_tree());
+        let mut cursor: CursorMut = CursorMut::find(&mut root, &8);
+        assert!(cursor.insert_before(7).is_some());
+        assert!(cursor.insert_after(9).is_some());
+        assert!(cursor.insert_before(5).is_none());
+        assert!(cursor.insert_after(8).is_none());
+        assert_eq!(cursor.key(), Some(8));
+        assert_eq!(cursor.peek_prev(), Some(7));
+        assert_eq!(cursor.peek_next(), Some(9));
+        while !cursor.is_ghost() {
+            cursor.move_next();
+        }
+        assert!(cursor.insert_before(20).is_some());
+        assert!(cursor.insert_after(0).is_some());
+        assert!(cursor.insert_after(1).is_none());
+        let root_node: BstNodeLink = root.unwrap();
+        assert_eq!(
+            BstNode::inorder_keys(&root_node),
+            vec![0, 2, 4, 6, 7, 8, 9, 10, 12, 14, 20]
+        );
+    }
+
+    #[test]
+    fn inserting_into_an_empty_tree_sets_the_root() {
+        let mut root: Option<BstNodeLink> = None;
+        let mut cursor: CursorMut = CursorMut::new(&mut root);
+        assert!(cursor.is_ghost());
+        assert!(cursor.insert_after(3).is_some());
+        assert!(cursor.is_ghost());
+        assert_eq!(cursor.peek_next(), Some(3));
+        assert_eq!(root.unwrap().borrow().key, Some(3));
+    }
+}
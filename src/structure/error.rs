@@ -0,0 +1,103 @@
+use std::fmt;
+
+//this package hold the error type returned by the Result flavoured tree operations,
+//so library users decide how failures are reported instead of reading stdout
+#[derive(Debug)]
+pub enum BstError {
+    /// No node holds the requested key.
+    NotFound(i32),
+    /// A node with the key already exists.
+    DuplicateKey(i32),
+    /// A parent pointer could not be upgraded because the parent node was dropped.
+    DanglingParent,
+    /// Writing a dot file or printing a graph failed.
+    Io(std::io::Error),
+    /// The tree or the arguments break an ordering or linking rule.
+    InvariantViolation(String),
+    /// Text or sequences handed to a parser or constructor are malformed.
+    InvalidInput(String),
+}
+
+pub type BstResult<T> = Result<T, BstError>;
+
+impl fmt::Display for BstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BstError::NotFound(key) => write!(
+                f,
+                "a node with the key value of {} is not found inside this tree or subtree",
+                key
+            ),
+            BstError::DuplicateKey(key) => {
+                write!(f, "a node with the key value of {} already exists", key)
+            }
+            BstError::DanglingParent => write!(
+                f,
+                "cannot upgrade parent pointer from Weak<RefCell<_>> to Rc<RefCell<_>>, the parent was dropped"
+            ),
+            BstError::Io(err) => write!(f, "i/o error: {}", err),
+            BstError::InvariantViolation(reason) => write!(f, "invariant violation: {}", reason),
+            BstError::InvalidInput(reason) => write!(f, "invalid input: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for BstError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BstError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BstError {
+    fn from(err: std::io::Error) -> Self {
+        BstError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn display_names_the_key_or_the_reason() {
+        assert_eq!(
+            BstError::NotFound(7).to_string(),
+            "a node with the key value of 7 is not found inside this tree or subtree"
+        );
+        assert_eq!(
+            BstError::DuplicateKey(-3).to_string(),
+            "a node with the key value of -3 already exists"
+        );
+        assert!(BstError::DanglingParent
+            .to_string()
+            .contains("parent was dropped"));
+        assert_eq!(
+            BstError::InvariantViolation("key 4 is out of range".to_string()).to_string(),
+            "invariant violation: key 4 is out of range"
+        );
+        assert_eq!(
+            BstError::InvalidInput("bad key".to_string()).to_string(),
+            "invalid input: bad key"
+        );
+    }
+
+    #[test]
+    fn io_errors_convert_and_keep_their_source() {
+        fn failing() -> BstResult<()> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no such file",
+            ))?
+        }
+        let err: BstError = failing().unwrap_err();
+        assert!(matches!(err, BstError::Io(_)));
+        assert_eq!(err.to_string(), "i/o error: no such file");
+        assert_eq!(err.source().unwrap().to_string(), "no such file");
+        assert!(BstError::NotFound(1).source().is_none());
+        assert!(BstError::DanglingParent.source().is_none());
+    }
+}
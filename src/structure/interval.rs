@@ -0,0 +1,466 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+pub type IntervalNodeLink = Rc<RefCell<IntervalNode>>;
+pub type WeakIntervalNodeLink = Weak<RefCell<IntervalNode>>;
+
+/// Closed interval `[low, high]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub low: i32,
+    pub high: i32,
+}
+
+impl Interval {
+    /**
+     * Build an interval, the endpoints are swapped if given in reverse
+     */
+    pub fn new(low: i32, high: i32) -> Self {
+        if low <= high {
+            Interval { low, high }
+        } else {
+            Interval {
+                low: high,
+                high: low,
+            }
+        }
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.low <= other.high && other.low <= self.high
+    }
+
+    pub fn contains_point(&self, point: i32) -> bool {
+        self.low <= point && point <= self.high
+    }
+}
+
+//this package implement an interval tree, a BstNode shaped tree keyed by the interval start
+//where every node also keeps the maximum endpoint found in its subtree
+#[derive(Debug, Clone)]
+pub struct IntervalNode {
+    pub interval: Interval,
+    pub max: i32,
+    pub parent: Option<WeakIntervalNodeLink>,
+    pub left: Option<IntervalNodeLink>,
+    pub right: Option<IntervalNodeLink>,
+}
+
+impl IntervalNode {
+    pub fn new_interval_nodelink(interval: Interval) -> IntervalNodeLink {
+        Rc::new(RefCell::new(IntervalNode {
+            interval,
+            max: interval.high,
+            parent: None,
+            left: None,
+            right: None,
+        }))
+    }
+
+    pub fn get_parent(node: &IntervalNodeLink) -> Option<IntervalNodeLink> {
+        node.borrow().parent.as_ref().and_then(|x| x.upgrade())
+    }
+
+    /**
+     * Recompute max of a single node from its own endpoint and its children
+     */
+    pub fn update_max(node: &IntervalNodeLink) {
+        let mut max: i32 = node.borrow().interval.high;
+        if let Some(ref left) = node.borrow().left {
+            max = max.max(left.borrow().max);
+        }
+        if let Some(ref right) = node.borrow().right {
+            max = max.max(right.borrow().max);
+        }
+        node.borrow_mut().max = max;
+    }
+
+    /**
+     * Recompute max from node up to the root, used after any structural change below node
+     */
+    pub fn update_max_upward(node: Option<IntervalNodeLink>) {
+        let mut current: Option<IntervalNodeLink> = node;
+        while let Some(x) = current {
+            IntervalNode::update_max(&x);
+            current = IntervalNode::get_parent(&x);
+        }
+    }
+
+    /// Inserts a new interval into the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Reference to the root node of the tree.
+    /// * `interval` - The interval to insert, intervals sharing a start go to the right.
+    ///
+    /// # Returns
+    ///
+    /// * The inserted node, every max on the path from it to the root is updated.
+    pub fn tree_insert(root: &IntervalNodeLink, interval: Interval) -> IntervalNodeLink {
+        let z: IntervalNodeLink = IntervalNode::new_interval_nodelink(interval);
+        let mut y: IntervalNodeLink = root.clone();
+        loop {
+            //every node on the path gains z in its subtree
+            if y.borrow().max < interval.high {
+                y.borrow_mut().max = interval.high;
+            }
+            let go_left: bool = interval.low < y.borrow().interval.low;
+            let next: Option<IntervalNodeLink> = if go_left {
+                y.borrow().left.clone()
+            } else {
+                y.borrow().right.clone()
+            };
+            match next {
+                Some(x) => y = x,
+                None => {
+                    z.borrow_mut().parent = Some(Rc::downgrade(&y));
+                    if go_left {
+                        y.borrow_mut().left = Some(z.clone());
+                    } else {
+                        y.borrow_mut().right = Some(z.clone());
+                    }
+                    return z;
+                }
+            }
+        }
+    }
+
+    /// Replaces one subtree as a child of its parent with another subtree.
+    ///
+    /// Same as `BstNode::transplant`, additionally the max of every node from the parent
+    /// of `u` up to the root is recomputed.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the transplant operation is successful.
+    /// * `false` if the parent pointer cannot be upgraded.
+    pub fn transplant(u: &IntervalNodeLink, v: &Option<IntervalNodeLink>) -> bool {
+        let u_parent_weak: Option<WeakIntervalNodeLink> = u.borrow().parent.clone();
+        let u_parent: Option<IntervalNodeLink> = match u_parent_weak {
+            None => None,
+            Some(ref weak) => match weak.upgrade() {
+                Some(x) => Some(x),
+                None => return false,
+            },
+        };
+        if let Some(ref parent) = u_parent {
+            let is_left_children: bool = parent
+                .borrow()
+                .left
+                .as_ref()
+                .is_some_and(|x| Rc::ptr_eq(x, u));
+            if is_left_children {
+                parent.borrow_mut().left = v.clone();
+            } else {
+                parent.borrow_mut().right = v.clone();
+            }
+        }
+        if let Some(ref v_rc_pointer) = v {
+            v_rc_pointer.borrow_mut().parent = u_parent_weak;
+        }
+        IntervalNode::update_max_upward(u_parent);
+        true
+    }
+
+    /// Deletes the specified node from the tree.
+    ///
+    /// # Returns
+    ///
+    /// * The node taking the place of `z`, so callers holding the root handle can update it.
+    pub fn tree_delete(z: &IntervalNodeLink) -> Option<IntervalNodeLink> {
+        let left: Option<IntervalNodeLink> = z.borrow().left.clone();
+        let right: Option<IntervalNodeLink> = z.borrow().right.clone();
+        match (left, right) {
+            (None, right) => {
+                IntervalNode::transplant(z, &right);
+                right
+            }
+            (left, None) => {
+                IntervalNode::transplant(z, &left);
+                left
+            }
+            (Some(left), Some(right)) => {
+                let mut successor: IntervalNodeLink = right.clone();
+                loop {
+                    let next: Option<IntervalNodeLink> = successor.borrow().left.clone();
+                    match next {
+                        Some(x) => successor = x,
+                        None => break,
+                    }
+                }
+                if !Rc::ptr_eq(&successor, &right) {
+                    let successor_right: Option<IntervalNodeLink> =
+                        successor.borrow().right.clone();
+                    IntervalNode::transplant(&successor, &successor_right);
+                    right.borrow_mut().parent = Some(Rc::downgrade(&successor));
+                    successor.borrow_mut().right = Some(right);
+                }
+                IntervalNode::transplant(z, &Some(successor.clone()));
+                left.borrow_mut().parent = Some(Rc::downgrade(&successor));
+                successor.borrow_mut().left = Some(left);
+                IntervalNode::update_max_upward(Some(successor.clone()));
+                Some(successor)
+            }
+        }
+    }
+
+    //search the tree for the node holding exactly this interval
+    pub fn tree_search(root: &IntervalNodeLink, interval: &Interval) -> Option<IntervalNodeLink> {
+        let mut current: Option<IntervalNodeLink> = Some(root.clone());
+        while let Some(x) = current {
+            if x.borrow().interval == *interval {
+                return Some(x);
+            }
+            //equal starts are stored to the right
+            if interval.low < x.borrow().interval.low {
+                current = x.borrow().left.clone();
+            } else {
+                current = x.borrow().right.clone();
+            }
+        }
+        None
+    }
+
+    //collect every interval of the subtree overlapping query, subtrees whose max ends before query are skipped
+    fn collect_overlapping(
+        node: &Option<IntervalNodeLink>,
+        query: &Interval,
+        found: &mut Vec<Interval>,
+    ) {
+        let x: &IntervalNodeLink = match node {
+            Some(x) => x,
+            None => return,
+        };
+        let borrowed = x.borrow();
+        if borrowed.max < query.low {
+            return;
+        }
+        IntervalNode::collect_overlapping(&borrowed.left, query, found);
+        if borrowed.interval.overlaps(query) {
+            found.push(borrowed.interval);
+        }
+        //every start in the right subtree is at least this start
+        if borrowed.interval.low <= query.high {
+            IntervalNode::collect_overlapping(&borrowed.right, query, found);
+        }
+    }
+}
+
+/// Interval tree owning the root handle, so deleting the root keeps working.
+#[derive(Debug, Default)]
+pub struct IntervalTree {
+    root: Option<IntervalNodeLink>,
+    len: usize,
+}
+
+impl IntervalTree {
+    pub fn new() -> Self {
+        IntervalTree::default()
+    }
+
+    pub fn root(&self) -> Option<IntervalNodeLink> {
+        self.root.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, interval: Interval) -> IntervalNodeLink {
+        self.len += 1;
+        match self.root {
+            Some(ref root) => IntervalNode::tree_insert(root, interval),
+            None => {
+                let node: IntervalNodeLink = IntervalNode::new_interval_nodelink(interval);
+                self.root = Some(node.clone());
+                node
+            }
+        }
+    }
+
+    /**
+     * Remove one node holding exactly interval, return false if there is none
+     */
+    pub fn remove(&mut self, interval: &Interval) -> bool {
+        let node: IntervalNodeLink = match self
+            .root
+            .as_ref()
+            .and_then(|x| IntervalNode::tree_search(x, interval))
+        {
+            Some(x) => x,
+            None => return false,
+        };
+        let is_root: bool = self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, &node));
+        let replacement: Option<IntervalNodeLink> = IntervalNode::tree_delete(&node);
+        if is_root {
+            self.root = replacement;
+        }
+        self.len -= 1;
+        true
+    }
+
+    /**
+     * Every stored interval overlapping query, ordered by start
+     */
+    pub fn overlapping(&self, query: &Interval) -> Vec<Interval> {
+        let mut found: Vec<Interval> = Vec::new();
+        IntervalNode::collect_overlapping(&self.root, query, &mut found);
+        found
+    }
+
+    /**
+     * Every stored interval containing point, ordered by start
+     */
+    pub fn stabbing(&self, point: i32) -> Vec<Interval> {
+        self.overlapping(&Interval::new(point, point))
+    }
+
+    /**
+     * Any single interval overlapping query, found along one root to leaf path
+     */
+    pub fn any_overlap(&self, query: &Interval) -> Option<Interval> {
+        let mut current: Option<IntervalNodeLink> = self.root.clone();
+        while let Some(x) = current {
+            if x.borrow().interval.overlaps(query) {
+                return Some(x.borrow().interval);
+            }
+            //if the left subtree reaches far enough it must hold an overlap, otherwise none is there
+            let left: Option<IntervalNodeLink> = x.borrow().left.clone();
+            current = match left {
+                Some(l) if l.borrow().max >= query.low => Some(l.clone()),
+                _ => x.borrow().right.clone(),
+            };
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //return the max of the subtree after checking every stored max and parent pointer below node
+    fn checked_max(node: &IntervalNodeLink) -> i32 {
+        let borrowed = node.borrow();
+        let mut max: i32 = borrowed.interval.high;
+        for child in [&borrowed.left, &borrowed.right].into_iter().flatten() {
+            assert!(Rc::ptr_eq(&IntervalNode::get_parent(child).unwrap(), node));
+            max = max.max(checked_max(child));
+        }
+        assert_eq!(borrowed.max, max);
+        max
+    }
+
+    fn sample() -> IntervalTree {
+        let mut tree: IntervalTree = IntervalTree::new();
+        for (low, high) in [
+            (16, 21),
+            (8, 9),
+            (25, 30),
+            (5, 8),
+            (15, 23),
+            (17, 19),
+            (26, 26),
+            (0, 3),
+            (6, 10),
+            (19, 20),
+        ] {
+            tree.insert(Interval::new(low, high));
+        }
+        tree
+    }
+
+    #[test]
+    fn interval_basics() {
+        assert_eq!(Interval::new(7, 2), Interval { low: 2, high: 7 });
+        let interval: Interval = Interval::new(2, 7);
+        assert!(interval.overlaps(&Interval::new(7, 9)));
+        assert!(interval.overlaps(&Interval::new(0, 2)));
+        assert!(!interval.overlaps(&Interval::new(8, 9)));
+        assert!(interval.contains_point(2));
+        assert!(!interval.contains_point(8));
+    }
+
+    #[test]
+    fn queries_on_a_sample() {
+        let tree: IntervalTree = sample();
+        assert_eq!(tree.len(), 10);
+        checked_max(&tree.root().unwrap());
+        assert_eq!(
+            tree.overlapping(&Interval::new(9, 15)),
+            vec![
+                Interval::new(6, 10),
+                Interval::new(8, 9),
+                Interval::new(15, 23)
+            ]
+        );
+        assert_eq!(
+            tree.stabbing(26),
+            vec![Interval::new(25, 30), Interval::new(26, 26)]
+        );
+        assert!(tree.stabbing(11).is_empty());
+        assert!(tree.any_overlap(&Interval::new(11, 14)).is_none());
+        let found: Interval = tree.any_overlap(&Interval::new(22, 25)).unwrap();
+        assert!(found.overlaps(&Interval::new(22, 25)));
+        assert!(IntervalTree::new()
+            .overlapping(&Interval::new(0, 1))
+            .is_empty());
+    }
+
+    #[test]
+    fn removing_keeps_every_max() {
+        let mut tree: IntervalTree = sample();
+        assert!(tree.remove(&Interval::new(16, 21)));
+        assert!(!tree.remove(&Interval::new(16, 21)));
+        assert!(tree.remove(&Interval::new(25, 30)));
+        assert_eq!(tree.len(), 8);
+        let root: IntervalNodeLink = tree.root().unwrap();
+        assert!(root.borrow().parent.is_none());
+        assert_eq!(checked_max(&root), 26);
+        assert!(tree.stabbing(28).is_empty());
+    }
+
+    #[test]
+    fn random_operations_match_a_scan() {
+        //a small linear congruential generator keeps the run reproducible
+        let mut state: u64 = 11;
+        let mut below = |bound: u64| -> u64 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        let mut tree: IntervalTree = IntervalTree::new();
+        let mut model: Vec<Interval> = Vec::new();
+        for _ in 0..600 {
+            let low: i32 = below(100) as i32;
+            let interval: Interval = Interval::new(low, low + below(15) as i32);
+            if below(3) == 0 && !model.is_empty() {
+                let removed: Interval = model.remove(below(model.len() as u64) as usize);
+                assert!(tree.remove(&removed));
+            } else {
+                tree.insert(interval);
+                model.push(interval);
+            }
+            if let Some(root) = tree.root() {
+                checked_max(&root);
+            }
+            let query: Interval = Interval::new(below(110) as i32, below(110) as i32);
+            let mut expected: Vec<Interval> = model
+                .iter()
+                .copied()
+                .filter(|x| x.overlaps(&query))
+                .collect();
+            let mut found: Vec<Interval> = tree.overlapping(&query);
+            assert_eq!(tree.any_overlap(&query).is_some(), !expected.is_empty());
+            expected.sort_by_key(|x| (x.low, x.high));
+            found.sort_by_key(|x| (x.low, x.high));
+            assert_eq!(found, expected);
+        }
+        assert_eq!(tree.len(), model.len());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::structure::bst::{BstNode, BstNodeLink, WeakBstNodeLink};
+use std::rc::Rc;
+
+//this package implement an undo/redo journal on top of the mutating BstNode operations.
+//Instead of replaying the opposite key operation, every entry stores the wiring
+//(parent, left, right) of each node the operation may touch, before and after it ran.
+//Restoring a wiring puts every node back at the exact structural position it held.
+
+/// Mutating operation recorded by the journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Insert(i32),
+    Transplant { u: Option<i32>, v: Option<i32> },
+    Delete(Option<i32>),
+}
+
+//pointer fields of a single node at one moment
+struct Wiring {
+    node: BstNodeLink,
+    parent: Option<WeakBstNodeLink>,
+    left: Option<BstNodeLink>,
+    right: Option<BstNodeLink>,
+}
+
+impl Wiring {
+    fn capture(node: &BstNodeLink) -> Self {
+        let borrowed = node.borrow();
+        Wiring {
+            node: node.clone(),
+            parent: borrowed.parent.clone(),
+            left: borrowed.left.clone(),
+            right: borrowed.right.clone(),
+        }
+    }
+
+    fn restore(&self) {
+        let mut borrowed = self.node.borrow_mut();
+        borrowed.parent = self.parent.clone();
+        borrowed.left = self.left.clone();
+        borrowed.right = self.right.clone();
+    }
+}
+
+struct JournalEntry {
+    operation: Operation,
+    before: Vec<Wiring>,
+    after: Vec<Wiring>,
+    root_before: Option<BstNodeLink>,
+    root_after: Option<BstNodeLink>,
+}
+
+/// Owns the root handle of a tree and records every mutation done through it.
+pub struct Journal {
+    root: Option<BstNodeLink>,
+    undo_stack: Vec<JournalEntry>,
+    redo_stack: Vec<JournalEntry>,
+    //checkpoint name and the undo stack depth it was taken at
+    checkpoints: Vec<(String, usize)>,
+}
+
+impl Journal {
+    pub fn new(root: Option<BstNodeLink>) -> Self {
+        Journal {
+            root,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /**
+     * Current root of the journaled tree, it changes when the root node is deleted or transplanted
+     */
+    pub fn root(&self) -> Option<BstNodeLink> {
+        self.root.clone()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Inserts a key through `BstNode::tree_insert` and records it.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(BstNodeLink)` with the new node if the insertion is successful.
+    /// * `None` if a node with the same key already exists, nothing is recorded.
+    pub fn tree_insert(&mut self, key: &i32) -> Option<BstNodeLink> {
+        let root: BstNodeLink = match self.root {
+            Some(ref x) => x.clone(),
+            None => {
+                let node: BstNodeLink = BstNode::new_bst_nodelink(*key);
+                self.commit(
+                    Operation::Insert(*key),
+                    Vec::new(),
+                    vec![node.clone()],
+                    Some(node.clone()),
+                );
+                return Some(node);
+            }
+        };
+        if BstNode::tree_search_link(&root, key).is_some() {
+            return None;
+        }
+        //the only node that changes is the leaf the new node hangs under
+        let leaf: BstNodeLink = Journal::insertion_leaf(&root, key);
+        let before: Vec<Wiring> = vec![Wiring::capture(&leaf)];
+        let inserted: Option<BstNodeLink> = BstNode::tree_insert(&root, key);
+        let mut touched: Vec<BstNodeLink> = vec![leaf];
+        touched.extend(inserted.clone());
+        self.commit(Operation::Insert(*key), before, touched, Some(root));
+        inserted
+    }
+
+    /// Runs `BstNode::transplant_node` and records it.
+    ///
+    /// When `u` is the root of the journaled tree, `v` becomes the new root.
+    pub fn transplant(&mut self, u: &BstNodeLink, v: &Option<BstNodeLink>) -> bool {
+        let mut touched: Vec<BstNodeLink> = Vec::new();
+        touched.extend(BstNode::get_parent(u));
+        if let Some(ref x) = v {
+            touched.push(x.clone());
+            touched.extend(x.borrow().left.clone());
+            touched.extend(x.borrow().right.clone());
+        }
+        let before: Vec<Wiring> = touched.iter().map(Wiring::capture).collect();
+        if !BstNode::transplant_node(u, v) {
+            return false;
+        }
+        let new_root: Option<BstNodeLink> = if self.is_root(u) {
+            v.clone()
+        } else {
+            self.root.clone()
+        };
+        let operation: Operation = Operation::Transplant {
+            u: u.borrow().key,
+            v: v.as_ref().and_then(|x| x.borrow().key),
+        };
+        self.commit(operation, before, touched, new_root);
+        true
+    }
+
+    /// Runs `BstNode::tree_delete_node` and records it.
+    ///
+    /// When `z` is the root of the journaled tree, the node taking its place becomes the new root.
+    pub fn tree_delete(&mut self, z: &BstNodeLink) -> bool {
+        let left: Option<BstNodeLink> = z.borrow().left.clone();
+        let right: Option<BstNodeLink> = z.borrow().right.clone();
+        let mut touched: Vec<BstNodeLink> = vec![z.clone()];
+        touched.extend(BstNode::get_parent(z));
+        touched.extend(left.clone());
+        touched.extend(right.clone());
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(z);
+        if left.is_some() && right.is_some() {
+            //the successor is spliced out of its own position first
+            let successor: &BstNodeLink = replacement.as_ref().unwrap();
+            touched.extend(BstNode::get_parent(successor));
+            touched.extend(successor.borrow().right.clone());
+            touched.push(successor.clone());
+        }
+        let before: Vec<Wiring> = touched.iter().map(Wiring::capture).collect();
+        if !BstNode::tree_delete_node(z) {
+            return false;
+        }
+        let new_root: Option<BstNodeLink> = if self.is_root(z) {
+            replacement
+        } else {
+            self.root.clone()
+        };
+        let key: Option<i32> = z.borrow().key;
+        self.commit(Operation::Delete(key), before, touched, new_root);
+        true
+    }
+
+    /// Deletes the node holding `key` and records it.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the node is found and deleted.
+    /// * `false` if the node with the given key does not exist.
+    pub fn tree_delete_with_key(&mut self, key: i32) -> bool {
+        let node: Option<BstNodeLink> = self
+            .root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, &key));
+        match node {
+            Some(x) => self.tree_delete(&x),
+            None => false,
+        }
+    }
+
+    /**
+     * Revert the latest recorded operation, return the operation that was reverted
+     */
+    pub fn undo(&mut self) -> Option<Operation> {
+        let entry: JournalEntry = self.undo_stack.pop()?;
+        for wiring in entry.before.iter() {
+            wiring.restore();
+        }
+        self.root = entry.root_before.clone();
+        let operation: Operation = entry.operation.clone();
+        self.redo_stack.push(entry);
+        Some(operation)
+    }
+
+    /**
+     * Apply the latest reverted operation again, return the operation that was reapplied
+     */
+    pub fn redo(&mut self) -> Option<Operation> {
+        let entry: JournalEntry = self.redo_stack.pop()?;
+        for wiring in entry.after.iter() {
+            wiring.restore();
+        }
+        self.root = entry.root_after.clone();
+        let operation: Operation = entry.operation.clone();
+        self.undo_stack.push(entry);
+        Some(operation)
+    }
+
+    /**
+     * Remember the current state under name, an existing checkpoint with the same name is replaced
+     */
+    pub fn checkpoint(&mut self, name: &str) {
+        self.checkpoints.retain(|(n, _)| n != name);
+        self.checkpoints
+            .push((name.to_string(), self.undo_stack.len()));
+    }
+
+    /**
+     * Undo or redo until the tree is back at the named checkpoint.
+     * Return false if the checkpoint is unknown
+     */
+    pub fn restore_checkpoint(&mut self, name: &str) -> bool {
+        let depth: usize = match self.checkpoints.iter().find(|(n, _)| n == name) {
+            Some((_, depth)) => *depth,
+            None => return false,
+        };
+        while self.undo_stack.len() > depth {
+            self.undo();
+        }
+        while self.undo_stack.len() < depth && self.redo().is_some() {}
+        true
+    }
+
+    pub fn checkpoint_names(&self) -> Vec<String> {
+        self.checkpoints.iter().map(|(n, _)| n.clone()).collect()
+    }
+
+    fn is_root(&self, node: &BstNodeLink) -> bool {
+        self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, node))
+    }
+
+    //walk down the same way tree_insert does and return the node the new key will hang under
+    fn insertion_leaf(root: &BstNodeLink, key: &i32) -> BstNodeLink {
+        let mut current: BstNodeLink = root.clone();
+        loop {
+            let next: Option<BstNodeLink> = if Some(*key) < current.borrow().key {
+                current.borrow().left.clone()
+            } else {
+                current.borrow().right.clone()
+            };
+            match next {
+                Some(x) => current = x,
+                None => return current,
+            }
+        }
+    }
+
+    //capture the wiring after the operation ran and push the entry
+    fn commit(
+        &mut self,
+        operation: Operation,
+        before: Vec<Wiring>,
+        touched: Vec<BstNodeLink>,
+        new_root: Option<BstNodeLink>,
+    ) {
+        let after: Vec<Wiring> = touched.iter().map(Wiring::capture).collect();
+        let root_before: Option<BstNodeLink> = self.root.clone();
+        self.root = new_root;
+        self.push_entry(JournalEntry {
+            operation,
+            before,
+            after,
+            root_before,
+            root_after: self.root.clone(),
+        });
+    }
+
+    fn push_entry(&mut self, entry: JournalEntry) {
+        self.undo_stack.push(entry);
+        //a new operation forks the history, checkpoints on the discarded branch are gone
+        self.redo_stack.clear();
+        let depth: usize = self.undo_stack.len();
+        self.checkpoints.retain(|(_, d)| *d < depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_journal() -> Journal {
+        let mut journal: Journal = Journal::new(None);
+        for key in [15, 6, 18, 3, 7, 17, 20, 13, 9] {
+            journal.tree_insert(&key);
+        }
+        journal
+    }
+
+    //key, parent key, left key and right key of every node in key order, it pins the whole shape
+    type Links = Vec<(Option<i32>, Option<i32>, Option<i32>, Option<i32>)>;
+
+    fn links(journal: &Journal) -> Links {
+        let key_of = |x: &Option<BstNodeLink>| x.as_ref().and_then(|x| x.borrow().key);
+        let mut links: Links = Vec::new();
+        let mut stack: Vec<BstNodeLink> = Vec::new();
+        let mut current: Option<BstNodeLink> = journal.root();
+        while current.is_some() || !stack.is_empty() {
+            while let Some(x) = current {
+                current = x.borrow().left.clone();
+                stack.push(x);
+            }
+            if let Some(x) = stack.pop() {
+                let borrowed = x.borrow();
+                links.push((
+                    borrowed.key,
+                    key_of(&BstNode::get_parent(&x)),
+                    key_of(&borrowed.left),
+                    key_of(&borrowed.right),
+                ));
+                current = borrowed.right.clone();
+            }
+        }
+        links
+    }
+
+    fn find(journal: &Journal, key: i32) -> BstNodeLink {
+        BstNode::tree_search_link(&journal.root().unwrap(), &key).unwrap()
+    }
+
+    #[test]
+    fn undo_and_redo_an_insert() {
+        let mut journal: Journal = sample_journal();
+        let snapshot: Links = links(&journal);
+        assert!(journal.tree_insert(&8).is_some());
+        assert!(journal.tree_insert(&8).is_none());
+        assert_eq!(journal.undo(), Some(Operation::Insert(8)));
+        assert_eq!(links(&journal), snapshot);
+        assert_eq!(journal.redo(), Some(Operation::Insert(8)));
+        assert_eq!(
+            BstNode::get_parent(&find(&journal, 8))
+                .unwrap()
+                .borrow()
+                .key,
+            Some(9)
+        );
+    }
+
+    #[test]
+    fn undo_puts_the_same_nodes_back() {
+        let mut journal: Journal = sample_journal();
+        let snapshot: Links = links(&journal);
+        let six: BstNodeLink = find(&journal, 6);
+        let seven: BstNodeLink = find(&journal, 7);
+        assert!(journal.tree_delete(&six));
+        assert!(journal.undo().is_some());
+        assert_eq!(links(&journal), snapshot);
+        assert!(Rc::ptr_eq(&find(&journal, 6), &six));
+        assert!(Rc::ptr_eq(&BstNode::get_parent(&seven).unwrap(), &six));
+    }
+
+    #[test]
+    fn deleting_the_root_moves_the_root_handle() {
+        let mut journal: Journal = sample_journal();
+        let old_root: BstNodeLink = journal.root().unwrap();
+        assert!(journal.tree_delete_with_key(15));
+        assert!(!journal.tree_delete_with_key(15));
+        let new_root: BstNodeLink = journal.root().unwrap();
+        assert_eq!(new_root.borrow().key, Some(17));
+        assert!(BstNode::get_parent(&new_root).is_none());
+        assert_eq!(
+            BstNode::inorder_keys(&new_root),
+            vec![3, 6, 7, 9, 13, 17, 18, 20]
+        );
+        assert_eq!(journal.undo(), Some(Operation::Delete(Some(15))));
+        assert!(Rc::ptr_eq(&journal.root().unwrap(), &old_root));
+        assert!(BstNode::get_parent(&old_root).is_none());
+        assert_eq!(BstNode::inorder_keys(&old_root).len(), 9);
+    }
+
+    #[test]
+    fn transplant_is_recorded() {
+        let mut journal: Journal = sample_journal();
+        let snapshot: Links = links(&journal);
+        let seven: BstNodeLink = find(&journal, 7);
+        let thirteen: Option<BstNodeLink> = Some(find(&journal, 13));
+        assert!(journal.transplant(&seven, &thirteen));
+        assert_eq!(
+            BstNode::inorder_keys(&journal.root().unwrap()),
+            vec![3, 6, 9, 13, 15, 17, 18, 20]
+        );
+        assert_eq!(
+            journal.undo(),
+            Some(Operation::Transplant {
+                u: Some(7),
+                v: Some(13)
+            })
+        );
+        assert_eq!(links(&journal), snapshot);
+    }
+
+    #[test]
+    fn a_new_operation_clears_the_redo_stack() {
+        let mut journal: Journal = sample_journal();
+        journal.tree_insert(&1);
+        journal.undo();
+        assert!(journal.can_redo());
+        journal.tree_insert(&2);
+        assert!(!journal.can_redo());
+        assert!(journal.redo().is_none());
+        assert!(Journal::new(None).undo().is_none());
+    }
+
+    #[test]
+    fn checkpoints_undo_and_redo_to_their_state() {
+        let mut journal: Journal = sample_journal();
+        journal.checkpoint("start");
+        journal.tree_insert(&1);
+        journal.tree_delete_with_key(6);
+        journal.checkpoint("end");
+        let end: Links = links(&journal);
+        journal.tree_delete_with_key(18);
+        assert!(journal.restore_checkpoint("start"));
+        assert_eq!(BstNode::inorder_keys(&journal.root().unwrap()).len(), 9);
+        assert!(journal.restore_checkpoint("end"));
+        assert_eq!(links(&journal), end);
+        assert!(!journal.restore_checkpoint("missing"));
+        assert_eq!(journal.checkpoint_names(), vec!["start", "end"]);
+        //the history forks below "end", so the checkpoint is dropped
+        journal.restore_checkpoint("start");
+        journal.tree_insert(&2);
+        assert_eq!(journal.checkpoint_names(), vec!["start"]);
+    }
+}
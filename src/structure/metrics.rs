@@ -0,0 +1,334 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub};
+
+//this package implement operation counters for the tree structures.
+//The BstNode operations bump thread local counters for key comparisons, visited nodes,
+//pointer rewires and rotations. measure() attributes the counts of a closure to a named operation,
+//so per operation figures and the cumulative totals can be reported after a workload.
+//Counting is off unless enabled with set_enabled or inside measure, so plain use pays one flag check.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpMetrics {
+    pub comparisons: u64,
+    pub nodes_visited: u64,
+    pub pointer_rewires: u64,
+    pub rotations: u64,
+}
+
+impl Add for OpMetrics {
+    type Output = OpMetrics;
+
+    fn add(self, other: OpMetrics) -> OpMetrics {
+        OpMetrics {
+            comparisons: self.comparisons + other.comparisons,
+            nodes_visited: self.nodes_visited + other.nodes_visited,
+            pointer_rewires: self.pointer_rewires + other.pointer_rewires,
+            rotations: self.rotations + other.rotations,
+        }
+    }
+}
+
+impl AddAssign for OpMetrics {
+    fn add_assign(&mut self, other: OpMetrics) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for OpMetrics {
+    type Output = OpMetrics;
+
+    fn sub(self, other: OpMetrics) -> OpMetrics {
+        OpMetrics {
+            comparisons: self.comparisons.saturating_sub(other.comparisons),
+            nodes_visited: self.nodes_visited.saturating_sub(other.nodes_visited),
+            pointer_rewires: self.pointer_rewires.saturating_sub(other.pointer_rewires),
+            rotations: self.rotations.saturating_sub(other.rotations),
+        }
+    }
+}
+
+/// Aggregated figures of every measured call of one named operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationStats {
+    pub name: String,
+    pub calls: u64,
+    pub total: OpMetrics,
+    /// Highest figure seen in a single call, per counter.
+    pub max: OpMetrics,
+}
+
+impl OperationStats {
+    fn record(&mut self, metrics: OpMetrics) {
+        self.calls += 1;
+        self.total += metrics;
+        self.max = OpMetrics {
+            comparisons: self.max.comparisons.max(metrics.comparisons),
+            nodes_visited: self.max.nodes_visited.max(metrics.nodes_visited),
+            pointer_rewires: self.max.pointer_rewires.max(metrics.pointer_rewires),
+            rotations: self.max.rotations.max(metrics.rotations),
+        };
+    }
+
+    fn average(total: u64, calls: u64) -> f64 {
+        if calls == 0 {
+            return 0.0;
+        }
+        total as f64 / calls as f64
+    }
+}
+
+/// Snapshot of the counters, see `report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsReport {
+    pub operations: Vec<OperationStats>,
+    pub cumulative: OpMetrics,
+}
+
+impl fmt::Display for MetricsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12} {:>8} {:>12} {:>12} {:>12} {:>14} {:>10}",
+            "operation",
+            "calls",
+            "avg cmp",
+            "avg visited",
+            "avg rewires",
+            "avg rotations",
+            "max cmp"
+        )?;
+        for stats in self.operations.iter() {
+            writeln!(
+                f,
+                "{:<12} {:>8} {:>12.2} {:>12.2} {:>12.2} {:>14.2} {:>10}",
+                stats.name,
+                stats.calls,
+                OperationStats::average(stats.total.comparisons, stats.calls),
+                OperationStats::average(stats.total.nodes_visited, stats.calls),
+                OperationStats::average(stats.total.pointer_rewires, stats.calls),
+                OperationStats::average(stats.total.rotations, stats.calls),
+                stats.max.comparisons
+            )?;
+        }
+        write!(
+            f,
+            "cumulative: comparisons={} nodes_visited={} pointer_rewires={} rotations={}",
+            self.cumulative.comparisons,
+            self.cumulative.nodes_visited,
+            self.cumulative.pointer_rewires,
+            self.cumulative.rotations
+        )
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    cumulative: OpMetrics,
+    operations: Vec<OperationStats>,
+}
+
+thread_local! {
+    static COUNTERS: RefCell<Counters> = RefCell::new(Counters::default());
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn bump(update: impl FnOnce(&mut OpMetrics)) {
+    if !is_enabled() {
+        return;
+    }
+    COUNTERS.with(|counters| update(&mut counters.borrow_mut().cumulative));
+}
+
+/**
+ * Turn counting on or off for the current thread, it is off by default
+ */
+pub fn set_enabled(enabled: bool) {
+    ENABLED.with(|x| x.set(enabled));
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.with(|x| x.get())
+}
+
+pub fn record_comparisons(amount: u64) {
+    bump(|m| m.comparisons += amount);
+}
+
+pub fn record_visit() {
+    bump(|m| m.nodes_visited += 1);
+}
+
+pub fn record_rewires(amount: u64) {
+    bump(|m| m.pointer_rewires += amount);
+}
+
+pub fn record_rotation() {
+    bump(|m| m.rotations += 1);
+}
+
+/**
+ * Cumulative counters since the last reset
+ */
+pub fn snapshot() -> OpMetrics {
+    COUNTERS.with(|counters| counters.borrow().cumulative)
+}
+
+/**
+ * Clear the cumulative counters and every per operation figure
+ */
+pub fn reset() {
+    COUNTERS.with(|counters| *counters.borrow_mut() = Counters::default());
+}
+
+/**
+ * Run f with counting enabled and attribute every count it causes to the operation called name.
+ * Return the result of f along with the counts of this single call
+ */
+pub fn measure<R>(name: &str, f: impl FnOnce() -> R) -> (R, OpMetrics) {
+    let was_enabled: bool = is_enabled();
+    set_enabled(true);
+    let before: OpMetrics = snapshot();
+    let result: R = f();
+    //a reset inside f would make the difference negative, saturate to zero instead
+    let metrics: OpMetrics = snapshot() - before;
+    set_enabled(was_enabled);
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        match counters.operations.iter_mut().find(|x| x.name == name) {
+            Some(stats) => stats.record(metrics),
+            None => {
+                let mut stats: OperationStats = OperationStats {
+                    name: name.to_string(),
+                    calls: 0,
+                    total: OpMetrics::default(),
+                    max: OpMetrics::default(),
+                };
+                stats.record(metrics);
+                counters.operations.push(stats);
+            }
+        }
+    });
+    (result, metrics)
+}
+
+/**
+ * Per operation figures in first measured order, plus the cumulative counters
+ */
+pub fn report() -> MetricsReport {
+    COUNTERS.with(|counters| {
+        let counters = counters.borrow();
+        MetricsReport {
+            operations: counters.operations.clone(),
+            cumulative: counters.cumulative,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::{BstNode, BstNodeLink};
+
+    #[test]
+    fn counting_is_off_outside_measure() {
+        reset();
+        let root: BstNodeLink = BstNode::new_bst_nodelink(10);
+        BstNode::tree_insert(&root, &5);
+        assert_eq!(snapshot(), OpMetrics::default());
+        let (_, counted) = measure("insert", || BstNode::tree_insert(&root, &7));
+        assert!(counted.comparisons > 0);
+        assert!(!is_enabled());
+        set_enabled(true);
+        BstNode::tree_search_link(&root, &7);
+        set_enabled(false);
+        assert!(snapshot().nodes_visited > counted.nodes_visited);
+    }
+
+    #[test]
+    fn reset_inside_measure_does_not_underflow() {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(10);
+        let (_, counted) = measure("insert", || {
+            BstNode::tree_insert(&root, &5);
+            reset();
+        });
+        assert_eq!(counted, OpMetrics::default());
+        assert_eq!(report().operations.len(), 1);
+    }
+
+    #[test]
+    fn report_aggregates_calls_per_operation() {
+        reset();
+        let (value, first) = measure("search", || {
+            record_comparisons(3);
+            record_visit();
+            7
+        });
+        assert_eq!(value, 7);
+        assert_eq!(first.comparisons, 3);
+        measure("rotate", || {
+            record_rotation();
+            record_rewires(3);
+        });
+        measure("search", || record_comparisons(5));
+        let report: MetricsReport = report();
+        let names: Vec<&str> = report.operations.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, vec!["search", "rotate"]);
+        let search: &OperationStats = &report.operations[0];
+        assert_eq!(search.calls, 2);
+        assert_eq!(search.total.comparisons, 8);
+        assert_eq!(search.max.comparisons, 5);
+        assert_eq!(search.max.nodes_visited, 1);
+        assert_eq!(
+            report.cumulative,
+            OpMetrics {
+                comparisons: 8,
+                nodes_visited: 1,
+                pointer_rewires: 3,
+                rotations: 1,
+            }
+        );
+        reset();
+        assert!(super::report().operations.is_empty());
+    }
+
+    #[test]
+    fn report_display_has_a_row_per_operation() {
+        reset();
+        measure("insert", || record_comparisons(4));
+        measure("insert", || record_comparisons(1));
+        let text: String = report().to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("operation"));
+        assert!(lines[1].starts_with("insert"));
+        assert!(lines[1].contains("2.50"));
+        assert_eq!(
+            lines[2],
+            "cumulative: comparisons=5 nodes_visited=0 pointer_rewires=0 rotations=0"
+        );
+    }
+
+    #[test]
+    fn arithmetic_is_per_counter() {
+        let a: OpMetrics = OpMetrics {
+            comparisons: 5,
+            nodes_visited: 1,
+            pointer_rewires: 0,
+            rotations: 2,
+        };
+        let b: OpMetrics = OpMetrics {
+            comparisons: 2,
+            nodes_visited: 3,
+            pointer_rewires: 1,
+            rotations: 2,
+        };
+        let mut sum: OpMetrics = a;
+        sum += b;
+        assert_eq!(sum, a + b);
+        assert_eq!(sum.nodes_visited, 4);
+        assert_eq!(sum - b, a);
+        assert_eq!((a - b).nodes_visited, 0);
+        assert_eq!((a - b).comparisons, 3);
+    }
+}
@@ -1,6 +1,28 @@
+pub mod augment;
+pub mod binary_tree;
 pub mod bst;
+pub mod compare;
+pub mod cursor;
+pub mod error;
+pub mod interval;
+pub mod journal;
+pub mod metrics;
+pub mod multiset;
+pub mod observer;
+mod parenthesized;
+pub mod persistent;
+#[cfg(feature = "serialization")]
+pub mod serialize;
+pub mod set_ops;
+pub mod weight_balanced;
 pub mod tree {
+    use crate::structure::binary_tree;
+    use crate::structure::bst::BstNodeLink;
+    use crate::structure::error::{BstError, BstResult};
+    use crate::structure::parenthesized::{self, ParsedNode};
     use std::cell::RefCell;
+    use std::collections::{HashMap, VecDeque};
+    use std::hash::{Hash, Hasher};
     use std::rc::{Rc, Weak};
 
     pub type NodeLink = Rc<RefCell<Node>>;
@@ -14,6 +36,21 @@ pub mod tree {
         pub right: Option<NodeLink>,
     }
 
+    //two nodes are equal when their subtrees have the same shape and values, parents are not looked at
+    impl PartialEq for Node {
+        fn eq(&self, other: &Self) -> bool {
+            binary_tree::structurally_equal(self, other)
+        }
+    }
+
+    impl Eq for Node {}
+
+    impl Hash for Node {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            binary_tree::hash_structure(self, state);
+        }
+    }
+
     impl Node {
         //private interface
         fn new(value: i32) -> Self {
@@ -25,6 +62,7 @@ pub mod tree {
             }
         }
 
+        #[allow(clippy::let_and_return)]
         pub fn new_nodelink(value: i32) -> NodeLink {
             let currentnode = Node::new(value);
             let currentlink = Rc::new(RefCell::new(currentnode));
@@ -34,18 +72,41 @@ pub mod tree {
         /**
          * Consumptive, this function can only be called once for the whole lifetime
          */
+        #[allow(dead_code)]
         fn get_nodelink(self) -> NodeLink {
             Rc::new(RefCell::new(self))
         }
 
         /**
          * Get a copy of node link
+         * Only this node is copied, the children are shared with the original, use deep_clone for an independent tree
          */
         pub fn get_nodelink_copy(&self) -> NodeLink {
             Rc::new(RefCell::new(self.clone()))
         }
 
+        /**
+         * Copy the whole subtree under node, the copy shares no node with the original
+         * Every parent pointer is rebuilt inside the copy and its root is detached
+         */
+        pub fn deep_clone(node: &NodeLink) -> NodeLink {
+            let copy_of = |x: &NodeLink| -> NodeLink { Node::new_nodelink(x.borrow().value) };
+            let root: NodeLink = copy_of(node);
+            //pairs of an original node and its copy, the children of the copy are still missing
+            let mut stack: Vec<(NodeLink, NodeLink)> = vec![(node.clone(), root.clone())];
+            while let Some((original, copy)) = stack.pop() {
+                let borrowed = original.borrow();
+                let left: Option<NodeLink> = borrowed.left.as_ref().map(copy_of);
+                let right: Option<NodeLink> = borrowed.right.as_ref().map(copy_of);
+                Node::set_children(&copy, left.clone(), right.clone());
+                stack.extend(borrowed.left.clone().zip(left));
+                stack.extend(borrowed.right.clone().zip(right));
+            }
+            root
+        }
+
         //private interface
+        #[allow(clippy::let_and_return)]
         fn new_with_parent(parent: &NodeLink, value: i32) -> NodeLink {
             let mut currentnode = Node::new(value);
             currentnode.add_parent(Rc::<RefCell<Node>>::downgrade(parent));
@@ -53,6 +114,7 @@ pub mod tree {
             currentlink
         }
 
+        #[allow(clippy::let_and_return)]
         fn new_from_node(node: Node) -> NodeLink {
             let currentlink = Rc::new(RefCell::new(node));
             currentlink
@@ -78,6 +140,7 @@ pub mod tree {
         /**
          * As the name implied, used to upgrade parent node to strong nodelink
          */
+        #[allow(clippy::manual_map)]
         pub fn upgrade_weak_to_strong(node: Option<WeakNodeLink>) -> Option<NodeLink> {
             match node {
                 None => None,
@@ -85,9 +148,22 @@ pub mod tree {
             }
         }
 
+        /**
+         * Same as upgrade_weak_to_strong, but a dropped parent is reported instead of panicking
+         */
+        pub fn try_upgrade_weak_to_strong(
+            node: Option<WeakNodeLink>,
+        ) -> BstResult<Option<NodeLink>> {
+            match node {
+                None => Ok(None),
+                Some(x) => x.upgrade().map(Some).ok_or(BstError::DanglingParent),
+            }
+        }
+
         /**
          * Unused
          */
+        #[allow(dead_code)]
         fn is_node_match_weak_strong(node1: Option<WeakNodeLink>, node2: Option<NodeLink>) -> bool {
             let node1s: Option<Rc<RefCell<Node>>> = Node::upgrade_weak_to_strong(node1);
             if node1s.is_none() && node2.is_none() {
@@ -100,6 +176,7 @@ pub mod tree {
         }
 
         //helper function
+        #[allow(clippy::needless_return)]
         fn is_node_match_both_weak(
             node1: Option<WeakNodeLink>,
             node2: Option<WeakNodeLink>,
@@ -113,6 +190,7 @@ pub mod tree {
         }
 
         //helper function to compare both nodelink
+        #[allow(clippy::needless_return)]
         fn is_node_match_both_strong(node1: Option<NodeLink>, node2: Option<NodeLink>) -> bool {
             if node1.is_none() && node2.is_none() {
                 return true;
@@ -126,23 +204,19 @@ pub mod tree {
         /**
          * This function will return the node that match value
          * Let's assume the tree won't have any value duplicates
+         * A match below self is the live node, a match on self itself can only be handed back as a copy,
+         * use find_by_value on the NodeLink to always get the live node
          */
         pub fn get_node_by_value(&self, value: i32) -> Option<NodeLink> {
             //check current node value
             if self.value == value {
-                //create clone of NodeLink
-                let node = self.clone();
-                let nodelink = Rc::new(RefCell::new(node));
-                return Rc::<RefCell<Node>>::downgrade(&nodelink).upgrade();
-            }
-            //go left if exist
-            if let Some(x) = &self.left {
-                return x.borrow().get_node_by_value(value);
-            }
-            if let Some(x) = &self.right {
-                return x.borrow().get_node_by_value(value);
+                return Some(self.get_nodelink_copy());
             }
-            return None;
+            //search the left subtree first, then the right one
+            [&self.left, &self.right]
+                .into_iter()
+                .flatten()
+                .find_map(|x| Node::find_by_value(x, value, SearchMode::DepthFirst))
         }
 
         /**
@@ -151,41 +225,23 @@ pub mod tree {
          * 2). node parent value,
          * 3). both child values
          * Let's assume the tree won't have any value duplicates
-         * Asssume 2nd parameter is not None
+         * Same as get_node_by_value, a match below self is the live node
          */
         pub fn get_node_by_full_property(&self, node: &NodeLink) -> Option<NodeLink> {
-            //check current node value
-            let nodevalue = node.borrow().value;
-            let check_parent_eq =
-                Node::is_node_match_both_weak(node.borrow().parent.clone(), self.parent.clone());
-            let check_left_child_eq =
-                Node::is_node_match_both_strong(node.borrow().left.clone(), self.left.clone());
-            let check_right_child_eq =
-                Node::is_node_match_both_strong(node.borrow().right.clone(), self.right.clone());
-            if self.value == nodevalue
-                && check_parent_eq
-                && check_left_child_eq
-                && check_right_child_eq
-            {
+            if Node::matches_full_property(self, node) {
                 return Some(self.get_nodelink_copy());
-            } else {
-                //recurse deeper if not found
-                //recurse to left
-                if let Some(left_subtree) = &self.left {
-                    return left_subtree.borrow().get_node_by_full_property(node);
-                } else if let Some(right_subtree) = &self.right {
-                    //recurse to right
-                    return right_subtree.borrow().get_node_by_full_property(node);
-                }
             }
-            None
+            [&self.left, &self.right]
+                .into_iter()
+                .flatten()
+                .find_map(|x| Node::find_by_full_property(x, node, SearchMode::DepthFirst))
         }
 
         /**
          * This function will discard a node that match the value, the whole node tree that match the description will be discarded
          * Along with its child
-         * The concept how we discard the node is, if the current node match sever the connection with parent,
-         * Then after return from the completion of immediate recursive, sever the connection with the child
+         * The matching node cuts off its parent connection, its parent then cuts off the connection to it,
+         * every other node is left alone
          */
         pub fn discard_node_by_value(&mut self, value: i32) -> bool {
             //check current node value
@@ -193,15 +249,28 @@ pub mod tree {
                 //cut off parent connection
                 self.parent = None;
                 return true;
-            } else if let Some(left_node) = &self.left {
-                let result_flag = left_node.borrow_mut().discard_node_by_value(value);
-                //cut this child connection
-                self.left = None;
-                return result_flag;
-            } else if let Some(right_node) = &self.right {
-                let result_flag = right_node.borrow_mut().discard_node_by_value(value);
-                self.right = None;
-                return result_flag;
+            }
+            for is_left in [true, false] {
+                let child: Option<NodeLink> = if is_left {
+                    self.left.clone()
+                } else {
+                    self.right.clone()
+                };
+                let child: NodeLink = match child {
+                    Some(x) => x,
+                    None => continue,
+                };
+                if child.borrow_mut().discard_node_by_value(value) {
+                    //only a direct match loses its parent, cut this child connection then
+                    if child.borrow().parent.is_none() {
+                        if is_left {
+                            self.left = None;
+                        } else {
+                            self.right = None;
+                        }
+                    }
+                    return true;
+                }
             }
             false
         }
@@ -210,6 +279,7 @@ pub mod tree {
          * Count the amount of nodes in the whole subtree, in the current node
          * assume when enter the function the current node isn't a null
          */
+        #[allow(clippy::needless_return)]
         pub fn count_nodes(&self) -> i32 {
             let mut count = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
@@ -219,43 +289,15 @@ pub mod tree {
 
         //the same as above except start the count from nodelink reference parameter
         pub fn count_nodes_by_nodelink(node: &NodeLink, count: i32) -> i32 {
-            let mut left_count: i32 = 0;
-            let mut right_count: i32 = 0;
-            if let Some(left_child) = &node.borrow().left {
-                left_count = Node::count_nodes_by_nodelink(&left_child, count);
-            }
-            if let Some(right_child) = &node.borrow().right {
-                right_count = Node::count_nodes_by_nodelink(&right_child, count);
-            }
-            return count + left_count + right_count + 1;
+            count + binary_tree::count_nodes(node) as i32
         }
 
         /**Count depth of the tree in the current node
          * Count from root is started from 0
          */
         pub fn tree_depth(&self) -> i32 {
-            let depth: i32 = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
-            return self.track_depth(&nodelink, depth);
-        }
-
-        //track depth by traversing all nodes but returned depth count per path. The highest number will be returned
-        fn track_depth(&self, node: &NodeLink, depth: i32) -> i32 {
-            let mut left_depth: i32 = 0;
-            let mut right_depth: i32 = 0;
-            if let Some(left_child) = &node.borrow().left {
-                left_depth = self.track_depth(left_child, depth) + 1;
-            }
-
-            if let Some(right_child) = &node.borrow().right {
-                right_depth = self.track_depth(right_child, depth) + 1;
-            }
-
-            if left_depth > right_depth {
-                return left_depth;
-            }
-
-            right_depth
+            binary_tree::height(&nodelink) as i32
         }
 
         /**
@@ -287,4 +329,1178 @@ pub mod tree {
             None
         }
     }
+
+    //the walks are shared with BstNode, see binary_tree
+    pub type PreorderIter = binary_tree::PreorderIter<Node>;
+    pub type InorderIter = binary_tree::InorderIter<Node>;
+    pub type PostorderIter = binary_tree::PostorderIter<Node>;
+    pub type LevelOrderIter = binary_tree::LevelOrderIter<Node>;
+
+    //traversals and shape queries, unlike count_nodes and tree_depth they work on the live nodes
+    impl Node {
+        pub fn preorder(node: &NodeLink) -> PreorderIter {
+            binary_tree::preorder(node)
+        }
+
+        pub fn inorder(node: &NodeLink) -> InorderIter {
+            binary_tree::inorder(node)
+        }
+
+        pub fn postorder(node: &NodeLink) -> PostorderIter {
+            binary_tree::postorder(node)
+        }
+
+        pub fn level_order(node: &NodeLink) -> LevelOrderIter {
+            binary_tree::level_order(node)
+        }
+
+        pub fn is_leaf(node: &NodeLink) -> bool {
+            node.borrow().left.is_none() && node.borrow().right.is_none()
+        }
+
+        /**
+         * Amount of nodes without children in the subtree
+         */
+        pub fn leaf_count(node: &NodeLink) -> usize {
+            Node::preorder(node).filter(Node::is_leaf).count()
+        }
+
+        /**
+         * Edges between node and the root of its tree, the root has depth 0
+         */
+        pub fn node_depth(node: &NodeLink) -> usize {
+            binary_tree::depth(node)
+        }
+
+        /**
+         * Edges on the longest downward path from node, a leaf has height 0
+         */
+        pub fn height(node: &NodeLink) -> usize {
+            binary_tree::height(node)
+        }
+
+        /**
+         * Edges on the longest path between any two nodes of the subtree
+         */
+        pub fn diameter(node: &NodeLink) -> usize {
+            Node::diameter_and_height(node).0
+        }
+
+        //(diameter, height) of the subtree in a single pass
+        fn diameter_and_height(node: &NodeLink) -> (usize, usize) {
+            let left: Option<(usize, usize)> =
+                node.borrow().left.as_ref().map(Node::diameter_and_height);
+            let right: Option<(usize, usize)> =
+                node.borrow().right.as_ref().map(Node::diameter_and_height);
+            //a child subtree of height h adds h + 1 edges to a path through node
+            let left_reach: usize = left.map_or(0, |(_, h)| h + 1);
+            let right_reach: usize = right.map_or(0, |(_, h)| h + 1);
+            let through_node: usize = left_reach + right_reach;
+            let diameter: usize = [
+                through_node,
+                left.map_or(0, |x| x.0),
+                right.map_or(0, |x| x.0),
+            ]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+            (diameter, left_reach.max(right_reach))
+        }
+
+        /**
+         * Return the parent of a node, None for the root or when the parent has been dropped
+         */
+        pub fn get_parent(node: &NodeLink) -> Option<NodeLink> {
+            node.borrow().parent.as_ref().and_then(|x| x.upgrade())
+        }
+
+        /**
+         * Every node from the root down to node, both included
+         */
+        pub fn path_from_root(node: &NodeLink) -> Vec<NodeLink> {
+            let mut path: Vec<NodeLink> = vec![node.clone()];
+            while let Some(parent) = Node::get_parent(path.last().unwrap()) {
+                path.push(parent);
+            }
+            path.reverse();
+            path
+        }
+
+        /**
+         * Deepest node having both a and b in its subtree, a node counts as its own ancestor.
+         * None if a and b live in different trees
+         */
+        pub fn lowest_common_ancestor(a: &NodeLink, b: &NodeLink) -> Option<NodeLink> {
+            let a_path: Vec<NodeLink> = Node::path_from_root(a);
+            let b_path: Vec<NodeLink> = Node::path_from_root(b);
+            a_path
+                .iter()
+                .zip(b_path.iter())
+                .take_while(|(x, y)| Rc::ptr_eq(x, y))
+                .last()
+                .map(|(x, _)| x.clone())
+        }
+
+        /**
+         * Every level is filled except maybe the last one, which is filled from the left
+         */
+        pub fn is_complete(node: &NodeLink) -> bool {
+            let mut queue: VecDeque<Option<NodeLink>> = VecDeque::from([Some(node.clone())]);
+            let mut seen_gap: bool = false;
+            while let Some(entry) = queue.pop_front() {
+                match entry {
+                    None => seen_gap = true,
+                    Some(x) => {
+                        if seen_gap {
+                            return false;
+                        }
+                        queue.push_back(x.borrow().left.clone());
+                        queue.push_back(x.borrow().right.clone());
+                    }
+                }
+            }
+            true
+        }
+
+        /**
+         * Every node has either no child or two children
+         */
+        pub fn is_full(node: &NodeLink) -> bool {
+            Node::preorder(node).all(|x| x.borrow().left.is_some() == x.borrow().right.is_some())
+        }
+
+        /**
+         * Full, with every leaf on the same level
+         */
+        pub fn is_perfect(node: &NodeLink) -> bool {
+            let count: usize = Node::preorder(node).count();
+            //a perfect tree of height h holds 2^(h+1) - 1 nodes, no tree that tall fits in memory
+            let levels: u32 = u32::try_from(Node::height(node) + 1).unwrap_or(u32::MAX);
+            1usize.checked_shl(levels).is_some_and(|x| count + 1 == x)
+        }
+
+        /**
+         * The heights of both subtrees of every node differ by at most one
+         */
+        pub fn is_balanced(node: &NodeLink) -> bool {
+            Node::balanced_height(node).is_some()
+        }
+
+        //height of the subtree if it is balanced, counting a missing child as -1
+        fn balanced_height(node: &NodeLink) -> Option<i64> {
+            let left: i64 = match node.borrow().left {
+                Some(ref x) => Node::balanced_height(x)?,
+                None => -1,
+            };
+            let right: i64 = match node.borrow().right {
+                Some(ref x) => Node::balanced_height(x)?,
+                None => -1,
+            };
+            if (left - right).abs() > 1 {
+                return None;
+            }
+            Some(left.max(right) + 1)
+        }
+
+        /**
+         * Amount of nodes on each level, index 0 is the level of node itself
+         */
+        pub fn width_per_level(node: &NodeLink) -> Vec<usize> {
+            let mut widths: Vec<usize> = Vec::new();
+            let mut level: Vec<NodeLink> = vec![node.clone()];
+            while !level.is_empty() {
+                widths.push(level.len());
+                level = level
+                    .iter()
+                    .flat_map(|x| {
+                        let borrowed = x.borrow();
+                        [borrowed.left.clone(), borrowed.right.clone()]
+                    })
+                    .flatten()
+                    .collect();
+            }
+            widths
+        }
+
+        /**
+         * Widest level, counted in nodes
+         */
+        pub fn max_width(node: &NodeLink) -> usize {
+            Node::width_per_level(node).into_iter().max().unwrap_or(0)
+        }
+    }
+
+    //constructors from traversal sequences and text, every child gets its parent weak pointer
+    impl Node {
+        //hang left and right under node
+        fn set_children(node: &NodeLink, left: Option<NodeLink>, right: Option<NodeLink>) {
+            for child in [&left, &right].into_iter().flatten() {
+                child.borrow_mut().parent = Some(Rc::downgrade(node));
+            }
+            node.borrow_mut().left = left;
+            node.borrow_mut().right = right;
+        }
+
+        //position of every value in the inorder, values have to be unique to tell the nodes apart
+        fn inorder_positions(inorder: &[i32]) -> BstResult<HashMap<i32, usize>> {
+            let mut positions: HashMap<i32, usize> = HashMap::new();
+            for (position, value) in inorder.iter().enumerate() {
+                if positions.insert(*value, position).is_some() {
+                    return Err(BstError::InvalidInput(format!(
+                        "value {} repeats in the inorder",
+                        value
+                    )));
+                }
+            }
+            Ok(positions)
+        }
+
+        //roots lists each subtree root before its subtrees, right first when it is a reversed postorder
+        fn build_with_inorder(
+            roots: &[i32],
+            positions: &HashMap<i32, usize>,
+            next: &mut usize,
+            low: usize,
+            high: usize,
+            right_first: bool,
+        ) -> BstResult<Option<NodeLink>> {
+            if low == high {
+                return Ok(None);
+            }
+            let value: i32 = roots[*next];
+            *next += 1;
+            let position: usize = positions
+                .get(&value)
+                .copied()
+                .filter(|x| low <= *x && *x < high)
+                .ok_or_else(|| {
+                    BstError::InvalidInput(format!("value {} does not fit the inorder", value))
+                })?;
+            let node: NodeLink = Node::new_nodelink(value);
+            let (left, right): (Option<NodeLink>, Option<NodeLink>) = if right_first {
+                let right =
+                    Node::build_with_inorder(roots, positions, next, position + 1, high, true)?;
+                let left = Node::build_with_inorder(roots, positions, next, low, position, true)?;
+                (left, right)
+            } else {
+                let left = Node::build_with_inorder(roots, positions, next, low, position, false)?;
+                let right =
+                    Node::build_with_inorder(roots, positions, next, position + 1, high, false)?;
+                (left, right)
+            };
+            Node::set_children(&node, left, right);
+            Ok(Some(node))
+        }
+
+        fn check_same_length(order: &str, sequence: &[i32], inorder: &[i32]) -> BstResult<()> {
+            if sequence.len() != inorder.len() {
+                return Err(BstError::InvalidInput(format!(
+                    "{} has {} values but the inorder has {}",
+                    order,
+                    sequence.len(),
+                    inorder.len()
+                )));
+            }
+            Ok(())
+        }
+
+        /// Rebuilds a tree from its preorder and inorder.
+        ///
+        /// # Arguments
+        ///
+        /// * `preorder` - Values in preorder.
+        /// * `inorder` - The same values in inorder, without duplicates.
+        ///
+        /// # Returns
+        ///
+        /// The root, `None` for two empty sequences.
+        ///
+        /// # Errors
+        ///
+        /// * `BstError::InvalidInput` if the lengths differ, a value repeats or the
+        ///   sequences do not describe the same tree.
+        pub fn from_preorder_inorder(
+            preorder: &[i32],
+            inorder: &[i32],
+        ) -> BstResult<Option<NodeLink>> {
+            Node::check_same_length("preorder", preorder, inorder)?;
+            let positions: HashMap<i32, usize> = Node::inorder_positions(inorder)?;
+            let mut next: usize = 0;
+            Node::build_with_inorder(preorder, &positions, &mut next, 0, inorder.len(), false)
+        }
+
+        /// Rebuilds a tree from its postorder and inorder.
+        ///
+        /// # Returns
+        ///
+        /// The root, `None` for two empty sequences.
+        ///
+        /// # Errors
+        ///
+        /// * `BstError::InvalidInput` on the same conditions as `from_preorder_inorder`.
+        pub fn from_postorder_inorder(
+            postorder: &[i32],
+            inorder: &[i32],
+        ) -> BstResult<Option<NodeLink>> {
+            Node::check_same_length("postorder", postorder, inorder)?;
+            let positions: HashMap<i32, usize> = Node::inorder_positions(inorder)?;
+            //a reversed postorder is a preorder that visits the right subtree first
+            let roots: Vec<i32> = postorder.iter().rev().copied().collect();
+            let mut next: usize = 0;
+            Node::build_with_inorder(&roots, &positions, &mut next, 0, inorder.len(), true)
+        }
+
+        /// Builds a tree from a level order where `None` marks a missing child, e.g.
+        /// `[Some(5), Some(3), Some(7), Some(2), Some(4), None, Some(10)]`.
+        ///
+        /// Only existing nodes have their two children listed, so a `None` has no
+        /// entries for children of its own. Trailing `None`s may be left out.
+        ///
+        /// # Returns
+        ///
+        /// The root, `None` for an empty slice or a `None` root.
+        ///
+        /// # Errors
+        ///
+        /// * `BstError::InvalidInput` if a value is left over without a parent.
+        pub fn from_level_order(values: &[Option<i32>]) -> BstResult<Option<NodeLink>> {
+            let root: NodeLink = match values.first() {
+                Some(Some(value)) => Node::new_nodelink(*value),
+                _ => {
+                    return match values.iter().position(|x| x.is_some()) {
+                        Some(index) => Err(BstError::InvalidInput(format!(
+                            "value at index {} has no parent",
+                            index
+                        ))),
+                        None => Ok(None),
+                    }
+                }
+            };
+            let mut queue: VecDeque<NodeLink> = VecDeque::from([root.clone()]);
+            let mut index: usize = 1;
+            while index < values.len() {
+                let parent: NodeLink = match queue.pop_front() {
+                    Some(x) => x,
+                    None => break,
+                };
+                let mut children: [Option<NodeLink>; 2] = [None, None];
+                for child in children.iter_mut() {
+                    if let Some(Some(value)) = values.get(index) {
+                        let node: NodeLink = Node::new_nodelink(*value);
+                        queue.push_back(node.clone());
+                        *child = Some(node);
+                    }
+                    index += 1;
+                }
+                let [left, right] = children;
+                Node::set_children(&parent, left, right);
+            }
+            if let Some(position) = values.iter().skip(index).position(|x| x.is_some()) {
+                return Err(BstError::InvalidInput(format!(
+                    "value at index {} has no parent",
+                    index + position
+                )));
+            }
+            Ok(Some(root))
+        }
+
+        /// Reads a tree written as `value(left,right)` in preorder, e.g. `5(3(2,4),7(,10))`.
+        ///
+        /// A missing child is left empty and a leaf is just its value, whitespace is
+        /// allowed between tokens.
+        ///
+        /// # Returns
+        ///
+        /// The root, `None` for an empty or blank text.
+        ///
+        /// # Errors
+        ///
+        /// * `BstError::InvalidInput` if the text is not in the format.
+        pub fn from_parenthesized(text: &str) -> BstResult<Option<NodeLink>> {
+            let parsed: Vec<ParsedNode> = parenthesized::parse(text)?;
+            let links: Vec<NodeLink> = parsed.iter().map(|x| Node::new_nodelink(x.key)).collect();
+            for (link, x) in links.iter().zip(parsed.iter()) {
+                let left: Option<NodeLink> = x.left.map(|i| links[i].clone());
+                let right: Option<NodeLink> = x.right.map(|i| links[i].clone());
+                Node::set_children(link, left, right);
+            }
+            Ok(links.first().cloned())
+        }
+
+        /**
+         * Write the subtree under node in the format read by from_parenthesized
+         */
+        pub fn to_parenthesized(node: &NodeLink) -> String {
+            parenthesized::write(node)
+        }
+    }
+
+    //bridge to bst::BstNode, see BstNode::try_from_nodelink for the other direction
+    impl Node {
+        /**
+         * Strict BST order, every value is greater than the whole left subtree and smaller than the whole right subtree
+         */
+        pub fn is_bst(node: &NodeLink) -> bool {
+            let values: Vec<i32> = Node::inorder(node).map(|x| x.borrow().value).collect();
+            values.windows(2).all(|x| x[0] < x[1])
+        }
+
+        /// Copies a `BstNode` tree into a fresh `Node` tree with the same shape.
+        ///
+        /// A plain function rather than `From`, both sides are `Rc` links and the
+        /// orphan rule forbids the impl.
+        ///
+        /// # Returns
+        ///
+        /// The root of the copy with every parent pointer rebuilt, `None` if any node
+        /// has a NIL key, see `try_from_bst_nodelink`.
+        pub fn from_bst_nodelink(node: &BstNodeLink) -> Option<NodeLink> {
+            Node::try_from_bst_nodelink(node).ok()
+        }
+
+        /// Copies a `BstNode` tree into a fresh `Node` tree with the same shape.
+        ///
+        /// # Errors
+        ///
+        /// * `BstError::InvalidInput` if a node has a NIL key, a `Node` always holds a value.
+        pub fn try_from_bst_nodelink(node: &BstNodeLink) -> BstResult<NodeLink> {
+            let copy_of = |x: &BstNodeLink| -> BstResult<NodeLink> {
+                let value: i32 = x.borrow().key.ok_or_else(|| {
+                    BstError::InvalidInput("a NIL key has no value in a tree::Node".to_string())
+                })?;
+                Ok(Node::new_nodelink(value))
+            };
+            let root: NodeLink = copy_of(node)?;
+            //pairs of an original node and its copy, the children of the copy are still missing
+            let mut stack: Vec<(BstNodeLink, NodeLink)> = vec![(node.clone(), root.clone())];
+            while let Some((original, copy)) = stack.pop() {
+                let borrowed = original.borrow();
+                let left: Option<NodeLink> = borrowed.left.as_ref().map(copy_of).transpose()?;
+                let right: Option<NodeLink> = borrowed.right.as_ref().map(copy_of).transpose()?;
+                Node::set_children(&copy, left.clone(), right.clone());
+                stack.extend(borrowed.left.clone().zip(left));
+                stack.extend(borrowed.right.clone().zip(right));
+            }
+            Ok(root)
+        }
+    }
+
+    /// Visiting order of the whole-tree searches on `Node`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SearchMode {
+        /// Preorder, the left subtree is searched before the right one.
+        DepthFirst,
+        /// Level by level, left to right.
+        BreadthFirst,
+    }
+
+    //whole-tree searches, every node below the given one is visited and the live NodeLink is returned
+    impl Node {
+        //visit the subtree under node in the order of mode
+        fn walk(node: &NodeLink, mode: SearchMode) -> Box<dyn Iterator<Item = NodeLink>> {
+            match mode {
+                SearchMode::DepthFirst => Box::new(Node::preorder(node)),
+                SearchMode::BreadthFirst => Box::new(Node::level_order(node)),
+            }
+        }
+
+        //same value, parent value and child values as the wanted node
+        fn matches_full_property(current: &Node, node: &NodeLink) -> bool {
+            let wanted = node.borrow();
+            current.value == wanted.value
+                && Node::is_node_match_both_weak(wanted.parent.clone(), current.parent.clone())
+                && Node::is_node_match_both_strong(wanted.left.clone(), current.left.clone())
+                && Node::is_node_match_both_strong(wanted.right.clone(), current.right.clone())
+        }
+
+        /// Finds the first node in the subtree under `node` that satisfies `predicate`.
+        ///
+        /// # Arguments
+        ///
+        /// * `node` - Root of the subtree to search, it is visited too.
+        /// * `mode` - Visiting order, decides which match is first.
+        /// * `predicate` - Called with each node borrowed.
+        ///
+        /// # Returns
+        ///
+        /// The live node, not a copy, or `None` if nothing matches.
+        pub fn find<P>(node: &NodeLink, mode: SearchMode, predicate: P) -> Option<NodeLink>
+        where
+            P: Fn(&Node) -> bool,
+        {
+            Node::walk(node, mode).find(|x| predicate(&x.borrow()))
+        }
+
+        /// Collects every node in the subtree under `node` that satisfies `predicate`, in the order of `mode`.
+        pub fn find_all<P>(node: &NodeLink, mode: SearchMode, predicate: P) -> Vec<NodeLink>
+        where
+            P: Fn(&Node) -> bool,
+        {
+            Node::walk(node, mode)
+                .filter(|x| predicate(&x.borrow()))
+                .collect()
+        }
+
+        /**
+         * Live node holding value, the whole subtree is searched
+         */
+        pub fn find_by_value(node: &NodeLink, value: i32, mode: SearchMode) -> Option<NodeLink> {
+            Node::find(node, mode, |x| x.value == value)
+        }
+
+        /**
+         * Live node matching the value, parent value and both child values of the wanted node
+         */
+        pub fn find_by_full_property(
+            node: &NodeLink,
+            wanted: &NodeLink,
+            mode: SearchMode,
+        ) -> Option<NodeLink> {
+            Node::find(node, mode, |x| Node::matches_full_property(x, wanted))
+        }
+
+        /// Detaches the first node holding `value` from its parent, together with its subtree.
+        ///
+        /// # Arguments
+        ///
+        /// * `node` - Root of the subtree to search.
+        /// * `value` - Value to discard.
+        /// * `mode` - Visiting order, decides which node goes if the value repeats.
+        ///
+        /// # Returns
+        ///
+        /// The detached subtree, its root no longer has a parent. `None` if the value is
+        /// missing, in which case nothing is changed.
+        pub fn discard_by_value(node: &NodeLink, value: i32, mode: SearchMode) -> Option<NodeLink> {
+            let found: NodeLink = Node::find_by_value(node, value, mode)?;
+            let parent: Option<NodeLink> = Node::get_parent(&found);
+            if let Some(parent) = parent {
+                let mut borrowed = parent.borrow_mut();
+                if borrowed
+                    .left
+                    .as_ref()
+                    .is_some_and(|x| Rc::ptr_eq(x, &found))
+                {
+                    borrowed.left = None;
+                } else {
+                    borrowed.right = None;
+                }
+            }
+            found.borrow_mut().parent = None;
+            Some(found)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::structure::bst::BstNode;
+
+        //the tree of test_binary_tree in main.rs
+        //        5
+        //     3     7
+        //    2 4      10
+        fn sample_tree() -> NodeLink {
+            let root: NodeLink = Node::new_nodelink(5);
+            root.borrow_mut().add_left_child(&root, 3);
+            root.borrow_mut().add_right_child(&root, 7);
+            let left: NodeLink = root.borrow().left.clone().unwrap();
+            left.borrow_mut().add_left_child(&left, 2);
+            left.borrow_mut().add_right_child(&left, 4);
+            let right: NodeLink = root.borrow().right.clone().unwrap();
+            right.borrow_mut().add_right_child(&right, 10);
+            root
+        }
+
+        fn child(node: &NodeLink, left: bool) -> NodeLink {
+            if left {
+                node.borrow().left.clone().unwrap()
+            } else {
+                node.borrow().right.clone().unwrap()
+            }
+        }
+
+        #[test]
+        fn new_nodelink_is_a_detached_leaf() {
+            let node: NodeLink = Node::new_nodelink(1);
+            assert_eq!(node.borrow().value, 1);
+            assert!(node.borrow().parent.is_none());
+            assert!(node.borrow().left.is_none());
+            assert!(node.borrow().right.is_none());
+        }
+
+        #[test]
+        fn get_nodelink_copy_is_a_different_node() {
+            let root: NodeLink = sample_tree();
+            let copy: NodeLink = root.borrow().get_nodelink_copy();
+            assert_eq!(copy.borrow().value, 5);
+            assert!(!Rc::ptr_eq(&copy, &root));
+            assert!(Rc::ptr_eq(&child(&copy, true), &child(&root, true)));
+        }
+
+        #[test]
+        fn add_children_point_back_to_the_parent() {
+            let root: NodeLink = sample_tree();
+            let left: NodeLink = child(&root, true);
+            let right: NodeLink = child(&root, false);
+            assert_eq!(left.borrow().value, 3);
+            assert_eq!(right.borrow().value, 7);
+            let parent: Option<NodeLink> =
+                Node::upgrade_weak_to_strong(left.borrow().parent.clone());
+            assert!(Rc::ptr_eq(&parent.unwrap(), &root));
+        }
+
+        #[test]
+        fn upgrade_weak_to_strong_cases() {
+            let root: NodeLink = sample_tree();
+            assert!(Node::upgrade_weak_to_strong(None).is_none());
+            assert!(matches!(Node::try_upgrade_weak_to_strong(None), Ok(None)));
+            let parent =
+                Node::try_upgrade_weak_to_strong(child(&root, false).borrow().parent.clone());
+            assert!(Rc::ptr_eq(&parent.unwrap().unwrap(), &root));
+            let dropped: WeakNodeLink = Rc::downgrade(&Node::new_nodelink(1));
+            assert!(matches!(
+                Node::try_upgrade_weak_to_strong(Some(dropped)),
+                Err(BstError::DanglingParent)
+            ));
+        }
+
+        #[test]
+        fn count_nodes_counts_the_whole_subtree() {
+            let root: NodeLink = sample_tree();
+            assert_eq!(root.borrow().count_nodes(), 6);
+            assert_eq!(Node::count_nodes_by_nodelink(&root, 0), 6);
+            assert_eq!(child(&root, true).borrow().count_nodes(), 3);
+            assert_eq!(child(&root, false).borrow().count_nodes(), 2);
+            assert_eq!(Node::new_nodelink(1).borrow().count_nodes(), 1);
+        }
+
+        #[test]
+        fn tree_depth_counts_edges_from_the_node() {
+            let root: NodeLink = sample_tree();
+            assert_eq!(root.borrow().tree_depth(), 2);
+            assert_eq!(child(&root, false).borrow().tree_depth(), 1);
+            assert_eq!(Node::new_nodelink(1).borrow().tree_depth(), 0);
+        }
+
+        #[test]
+        fn get_sibling_of_each_side() {
+            let root: NodeLink = sample_tree();
+            let left: NodeLink = child(&root, true);
+            let right: NodeLink = child(&root, false);
+            assert!(Rc::ptr_eq(&Node::get_sibling(&left).unwrap(), &right));
+            assert!(Rc::ptr_eq(&Node::get_sibling(&right).unwrap(), &left));
+            assert_eq!(
+                Node::get_sibling(&child(&left, true)).map(|x| x.borrow().value),
+                Some(4)
+            );
+            //10 is an only child, the root has no parent at all
+            assert!(Node::get_sibling(&child(&right, false)).is_none());
+            assert!(Node::get_sibling(&root).is_none());
+        }
+
+        #[test]
+        fn get_node_by_value_along_the_left_spine() {
+            let root: NodeLink = sample_tree();
+            assert_eq!(
+                root.borrow().get_node_by_value(5).map(|x| x.borrow().value),
+                Some(5)
+            );
+            assert_eq!(
+                root.borrow().get_node_by_value(3).map(|x| x.borrow().value),
+                Some(3)
+            );
+            assert_eq!(
+                root.borrow().get_node_by_value(2).map(|x| x.borrow().value),
+                Some(2)
+            );
+        }
+
+        #[test]
+        fn get_node_by_full_property_matches_value_and_links() {
+            let root: NodeLink = sample_tree();
+            let left: NodeLink = child(&root, true);
+            let found: NodeLink = root.borrow().get_node_by_full_property(&left).unwrap();
+            assert_eq!(found.borrow().value, 3);
+            assert!(Rc::ptr_eq(&child(&found, true), &child(&left, true)));
+            assert!(root.borrow().get_node_by_full_property(&root).is_some());
+        }
+
+        #[test]
+        fn discard_node_by_value_severs_the_subtree() {
+            let root: NodeLink = sample_tree();
+            let left: NodeLink = child(&root, true);
+            assert!(root.borrow_mut().discard_node_by_value(3));
+            assert!(root.borrow().left.is_none());
+            assert!(left.borrow().parent.is_none());
+            assert_eq!(root.borrow().count_nodes(), 3);
+            assert_eq!(left.borrow().count_nodes(), 3);
+        }
+
+        #[test]
+        fn discard_node_by_value_on_the_node_itself() {
+            let root: NodeLink = sample_tree();
+            assert!(root.borrow_mut().discard_node_by_value(5));
+            assert_eq!(root.borrow().count_nodes(), 6);
+        }
+
+        fn values(nodes: impl Iterator<Item = NodeLink>) -> Vec<i32> {
+            nodes.map(|x| x.borrow().value).collect()
+        }
+
+        //5(3(2,4),7), complete and full but not perfect
+        fn complete_tree() -> NodeLink {
+            let root: NodeLink = sample_tree();
+            child(&root, false).borrow_mut().right = None;
+            root
+        }
+
+        #[test]
+        fn traversal_orders() {
+            let root: NodeLink = sample_tree();
+            assert_eq!(values(Node::preorder(&root)), vec![5, 3, 2, 4, 7, 10]);
+            assert_eq!(values(Node::inorder(&root)), vec![2, 3, 4, 5, 7, 10]);
+            assert_eq!(values(Node::postorder(&root)), vec![2, 4, 3, 10, 7, 5]);
+            assert_eq!(values(Node::level_order(&root)), vec![5, 3, 7, 2, 4, 10]);
+            let leaf: NodeLink = Node::new_nodelink(1);
+            assert_eq!(values(Node::postorder(&leaf)), vec![1]);
+        }
+
+        #[test]
+        fn traversals_yield_live_nodes() {
+            let root: NodeLink = sample_tree();
+            let first: NodeLink = Node::preorder(&root).next().unwrap();
+            assert!(Rc::ptr_eq(&first, &root));
+            let last: NodeLink = Node::level_order(&root).last().unwrap();
+            assert!(Rc::ptr_eq(&last, &child(&child(&root, false), false)));
+        }
+
+        #[test]
+        fn leaf_count_depth_and_height() {
+            let root: NodeLink = sample_tree();
+            let two: NodeLink = child(&child(&root, true), true);
+            assert_eq!(Node::leaf_count(&root), 3);
+            assert_eq!(Node::leaf_count(&two), 1);
+            assert_eq!(Node::node_depth(&root), 0);
+            assert_eq!(Node::node_depth(&two), 2);
+            assert_eq!(Node::height(&root), 2);
+            assert_eq!(Node::height(&two), 0);
+            assert!(Node::is_leaf(&two));
+            assert!(!Node::is_leaf(&root));
+        }
+
+        #[test]
+        fn diameter_may_skip_the_root() {
+            let root: NodeLink = sample_tree();
+            assert_eq!(Node::diameter(&root), 4);
+            assert_eq!(Node::diameter(&Node::new_nodelink(1)), 0);
+            //a long path hanging under a single child of the root
+            let top: NodeLink = Node::new_nodelink(0);
+            top.borrow_mut().add_left_child(&top, 1);
+            let one: NodeLink = child(&top, true);
+            one.borrow_mut().add_left_child(&one, 2);
+            one.borrow_mut().add_right_child(&one, 3);
+            let two: NodeLink = child(&one, true);
+            two.borrow_mut().add_left_child(&two, 4);
+            let three: NodeLink = child(&one, false);
+            three.borrow_mut().add_right_child(&three, 5);
+            assert_eq!(Node::diameter(&top), 4);
+        }
+
+        #[test]
+        fn path_from_root_and_lowest_common_ancestor() {
+            let root: NodeLink = sample_tree();
+            let three: NodeLink = child(&root, true);
+            let two: NodeLink = child(&three, true);
+            let four: NodeLink = child(&three, false);
+            let ten: NodeLink = child(&child(&root, false), false);
+            assert_eq!(
+                values(Node::path_from_root(&four).into_iter()),
+                vec![5, 3, 4]
+            );
+            assert_eq!(values(Node::path_from_root(&root).into_iter()), vec![5]);
+            let lca = |a: &NodeLink, b: &NodeLink| Node::lowest_common_ancestor(a, b).unwrap();
+            assert!(Rc::ptr_eq(&lca(&two, &four), &three));
+            assert!(Rc::ptr_eq(&lca(&two, &ten), &root));
+            assert!(Rc::ptr_eq(&lca(&three, &two), &three));
+            assert!(Rc::ptr_eq(&lca(&ten, &ten), &ten));
+            assert!(Node::lowest_common_ancestor(&two, &Node::new_nodelink(2)).is_none());
+        }
+
+        #[test]
+        fn shape_predicates() {
+            let sample: NodeLink = sample_tree();
+            assert!(!Node::is_complete(&sample));
+            assert!(!Node::is_full(&sample));
+            assert!(!Node::is_perfect(&sample));
+            assert!(Node::is_balanced(&sample));
+
+            let complete: NodeLink = complete_tree();
+            assert!(Node::is_complete(&complete));
+            assert!(Node::is_full(&complete));
+            assert!(!Node::is_perfect(&complete));
+
+            let perfect: NodeLink = complete_tree();
+            let seven: NodeLink = child(&perfect, false);
+            seven.borrow_mut().add_left_child(&seven, 6);
+            seven.borrow_mut().add_right_child(&seven, 8);
+            assert!(Node::is_perfect(&perfect));
+            assert!(Node::is_complete(&perfect));
+
+            let leaf: NodeLink = Node::new_nodelink(1);
+            assert!(Node::is_complete(&leaf) && Node::is_full(&leaf));
+            assert!(Node::is_perfect(&leaf) && Node::is_balanced(&leaf));
+
+            let spine: NodeLink = Node::new_nodelink(3);
+            spine.borrow_mut().add_left_child(&spine, 2);
+            let two: NodeLink = child(&spine, true);
+            two.borrow_mut().add_left_child(&two, 1);
+            assert!(!Node::is_balanced(&spine));
+            assert!(!Node::is_complete(&spine));
+        }
+
+        #[test]
+        fn is_perfect_on_a_long_spine() {
+            //height 63 and beyond used to overflow the shift
+            let root: NodeLink = Node::new_nodelink(64);
+            let mut current: NodeLink = root.clone();
+            for value in (1..64).rev() {
+                current.borrow_mut().add_left_child(&current, value);
+                let next: NodeLink = child(&current, true);
+                current = next;
+            }
+            assert_eq!(Node::height(&root), 63);
+            assert!(!Node::is_perfect(&root));
+            current.borrow_mut().add_left_child(&current, 0);
+            assert!(!Node::is_perfect(&root));
+        }
+
+        #[test]
+        fn width_per_level_and_max_width() {
+            let root: NodeLink = sample_tree();
+            assert_eq!(Node::width_per_level(&root), vec![1, 2, 3]);
+            assert_eq!(Node::max_width(&root), 3);
+            assert_eq!(Node::width_per_level(&Node::new_nodelink(1)), vec![1]);
+        }
+
+        //every child points back to the node holding it
+        fn assert_parents(root: &NodeLink) {
+            assert!(root.borrow().parent.is_none());
+            for node in Node::preorder(root) {
+                for x in [node.borrow().left.clone(), node.borrow().right.clone()]
+                    .into_iter()
+                    .flatten()
+                {
+                    assert!(Rc::ptr_eq(&Node::get_parent(&x).unwrap(), &node));
+                }
+            }
+        }
+
+        #[test]
+        fn from_preorder_and_postorder_with_inorder() {
+            let inorder: [i32; 6] = [2, 3, 4, 5, 7, 10];
+            let root: NodeLink = Node::from_preorder_inorder(&[5, 3, 2, 4, 7, 10], &inorder)
+                .unwrap()
+                .unwrap();
+            assert_eq!(Node::to_parenthesized(&root), "5(3(2,4),7(,10))");
+            assert_parents(&root);
+            let root: NodeLink = Node::from_postorder_inorder(&[2, 4, 3, 10, 7, 5], &inorder)
+                .unwrap()
+                .unwrap();
+            assert_eq!(Node::to_parenthesized(&root), "5(3(2,4),7(,10))");
+            assert_parents(&root);
+            assert!(Node::from_preorder_inorder(&[], &[]).unwrap().is_none());
+        }
+
+        #[test]
+        fn inconsistent_traversals_are_rejected() {
+            let invalid =
+                |x: BstResult<Option<NodeLink>>| matches!(x, Err(BstError::InvalidInput(_)));
+            assert!(invalid(Node::from_preorder_inorder(&[1, 2], &[1])));
+            assert!(invalid(Node::from_preorder_inorder(&[1, 1], &[1, 1])));
+            assert!(invalid(Node::from_preorder_inorder(&[1, 2], &[1, 3])));
+            assert!(invalid(Node::from_preorder_inorder(&[1, 1], &[1, 2])));
+            //after the root 2 the preorder has to go on with its left part, which is only 1
+            assert!(invalid(Node::from_preorder_inorder(&[2, 3, 1], &[1, 2, 3])));
+            assert!(invalid(Node::from_postorder_inorder(
+                &[3, 1, 2],
+                &[1, 2, 3]
+            )));
+        }
+
+        #[test]
+        fn from_level_order_with_gaps() {
+            let root: NodeLink = Node::from_level_order(&[
+                Some(5),
+                Some(3),
+                Some(7),
+                Some(2),
+                Some(4),
+                None,
+                Some(10),
+            ])
+            .unwrap()
+            .unwrap();
+            assert_eq!(Node::to_parenthesized(&root), "5(3(2,4),7(,10))");
+            assert_parents(&root);
+            //children of a missing node are not listed
+            let root: NodeLink =
+                Node::from_level_order(&[Some(1), None, Some(2), Some(3), None, None, Some(4)])
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(Node::to_parenthesized(&root), "1(,2(3(,4),))");
+            assert!(Node::from_level_order(&[]).unwrap().is_none());
+            assert!(Node::from_level_order(&[None, None]).unwrap().is_none());
+            assert!(matches!(
+                Node::from_level_order(&[None, Some(1)]),
+                Err(BstError::InvalidInput(_))
+            ));
+            assert!(matches!(
+                Node::from_level_order(&[Some(1), None, None, Some(2)]),
+                Err(BstError::InvalidInput(_))
+            ));
+        }
+
+        #[test]
+        fn from_parenthesized_round_trip() {
+            let text: &str = "5(3(2,4),7(,10))";
+            let root: NodeLink = Node::from_parenthesized(text).unwrap().unwrap();
+            assert_eq!(values(Node::preorder(&root)), vec![5, 3, 2, 4, 7, 10]);
+            assert_eq!(Node::to_parenthesized(&root), text);
+            assert_eq!(Node::to_parenthesized(&sample_tree()), text);
+            assert_parents(&root);
+            //no ordering is required from a plain binary tree
+            let root: NodeLink = Node::from_parenthesized(" 1 ( 9 , -3 ) ").unwrap().unwrap();
+            assert_eq!(Node::to_parenthesized(&root), "1(9,-3)");
+            assert!(Node::from_parenthesized("").unwrap().is_none());
+            for text in [
+                "1(",
+                "1(2",
+                "1(,)",
+                "(1,2)",
+                "1(0,2)3",
+                "x",
+                "99999999999",
+                "1 2",
+                "5(3,1 0)",
+            ] {
+                assert!(
+                    matches!(
+                        Node::from_parenthesized(text),
+                        Err(BstError::InvalidInput(_))
+                    ),
+                    "{}",
+                    text
+                );
+            }
+        }
+
+        #[test]
+        fn discarding_from_a_deep_clone_leaves_the_original_alone() {
+            let root: NodeLink = sample_tree();
+            let copy: NodeLink = Node::deep_clone(&root);
+            assert_parents(&copy);
+            //the discard in test_binary_tree of main.rs, the original keeps its left subtree
+            assert!(copy.borrow_mut().discard_node_by_value(3));
+            assert_eq!(copy.borrow().count_nodes(), 3);
+            assert_eq!(root.borrow().count_nodes(), 6);
+            assert!(Rc::ptr_eq(
+                &Node::get_parent(&child(&root, true)).unwrap(),
+                &root
+            ));
+            assert_ne!(root, copy);
+        }
+
+        #[test]
+        fn is_bst_checks_the_whole_order() {
+            assert!(Node::is_bst(&sample_tree()));
+            assert!(Node::is_bst(&Node::new_nodelink(1)));
+            //4 is fine for its parent 3 but not for the root 3 sits under
+            let root: NodeLink = Node::from_parenthesized("3(1(,4),5)").unwrap().unwrap();
+            assert!(!Node::is_bst(&root));
+            let repeated: NodeLink = Node::from_parenthesized("3(3,5)").unwrap().unwrap();
+            assert!(!Node::is_bst(&repeated));
+        }
+
+        #[test]
+        fn conversion_to_and_from_bst_node() {
+            let root: NodeLink = sample_tree();
+            let bst: BstNodeLink = BstNode::try_from_nodelink(&root).unwrap();
+            assert!(BstNode::validate(&bst).is_ok());
+            assert_eq!(BstNode::inorder_keys(&bst), vec![2, 3, 4, 5, 7, 10]);
+
+            let back: NodeLink = Node::from_bst_nodelink(&bst).unwrap();
+            assert_eq!(Node::to_parenthesized(&back), "5(3(2,4),7(,10))");
+            assert_parents(&back);
+            assert!(!Rc::ptr_eq(&back, &root));
+
+            let unordered: NodeLink = Node::from_parenthesized("5(7,3)").unwrap().unwrap();
+            assert!(matches!(
+                BstNode::try_from_nodelink(&unordered),
+                Err(BstError::InvariantViolation(_))
+            ));
+        }
+
+        #[test]
+        fn nil_keys_are_refused_by_the_bst_node_copy() {
+            let bst: BstNodeLink = BstNode::new_bst_nodelink(5);
+            bst.borrow_mut().add_left_child(&bst, 3);
+            bst.borrow_mut().add_right_child(&bst, 7);
+            bst.borrow().left.clone().unwrap().borrow_mut().key = None;
+            assert!(matches!(
+                Node::try_from_bst_nodelink(&bst),
+                Err(BstError::InvalidInput(_))
+            ));
+            assert!(Node::from_bst_nodelink(&bst).is_none());
+            bst.borrow_mut().key = None;
+            assert!(Node::from_bst_nodelink(&bst).is_none());
+        }
+
+        #[test]
+        fn get_node_by_value_reaches_the_right_subtrees() {
+            let root: NodeLink = sample_tree();
+            let four: NodeLink = root.borrow().get_node_by_value(4).unwrap();
+            assert!(Rc::ptr_eq(&four, &child(&child(&root, true), false)));
+            let ten: NodeLink = root.borrow().get_node_by_value(10).unwrap();
+            assert!(Rc::ptr_eq(&ten, &child(&child(&root, false), false)));
+            assert!(root.borrow().get_node_by_value(6).is_none());
+        }
+
+        #[test]
+        fn get_node_by_full_property_reaches_the_right_subtrees() {
+            let root: NodeLink = sample_tree();
+            let seven: NodeLink = child(&root, false);
+            let found: NodeLink = root.borrow().get_node_by_full_property(&seven).unwrap();
+            assert!(Rc::ptr_eq(&found, &seven));
+            //same value, different children
+            let other: NodeLink = Node::new_nodelink(7);
+            assert!(root.borrow().get_node_by_full_property(&other).is_none());
+        }
+
+        #[test]
+        fn find_by_value_in_both_modes() {
+            let root: NodeLink = sample_tree();
+            for mode in [SearchMode::DepthFirst, SearchMode::BreadthFirst] {
+                let ten: NodeLink = Node::find_by_value(&root, 10, mode).unwrap();
+                assert!(Rc::ptr_eq(&ten, &child(&child(&root, false), false)));
+                let found: NodeLink = Node::find_by_value(&root, 5, mode).unwrap();
+                assert!(Rc::ptr_eq(&found, &root));
+                assert!(Node::find_by_value(&root, 6, mode).is_none());
+            }
+        }
+
+        #[test]
+        fn find_all_follows_the_mode() {
+            let root: NodeLink = sample_tree();
+            let above_two = |x: &Node| x.value > 2;
+            let depth: Vec<NodeLink> = Node::find_all(&root, SearchMode::DepthFirst, above_two);
+            assert_eq!(values(depth.into_iter()), vec![5, 3, 4, 7, 10]);
+            let breadth: Vec<NodeLink> = Node::find_all(&root, SearchMode::BreadthFirst, above_two);
+            assert_eq!(values(breadth.into_iter()), vec![5, 3, 7, 4, 10]);
+            let first: NodeLink =
+                Node::find(&root, SearchMode::BreadthFirst, |x| x.value % 2 == 0).unwrap();
+            assert_eq!(first.borrow().value, 2);
+            assert!(Node::find_all(&root, SearchMode::DepthFirst, |x| x.value > 10).is_empty());
+        }
+
+        #[test]
+        fn find_by_full_property_returns_the_live_node() {
+            let root: NodeLink = sample_tree();
+            let three: NodeLink = child(&root, true);
+            let found: NodeLink =
+                Node::find_by_full_property(&root, &three, SearchMode::BreadthFirst).unwrap();
+            assert!(Rc::ptr_eq(&found, &three));
+        }
+
+        #[test]
+        fn discard_node_by_value_in_the_right_subtree() {
+            let root: NodeLink = sample_tree();
+            assert!(root.borrow_mut().discard_node_by_value(10));
+            assert!(child(&root, false).borrow().right.is_none());
+            assert!(root.borrow().left.is_some());
+            assert_eq!(root.borrow().count_nodes(), 5);
+        }
+
+        #[test]
+        fn discard_node_by_value_missing_leaves_the_tree_intact() {
+            let root: NodeLink = sample_tree();
+            assert!(!root.borrow_mut().discard_node_by_value(6));
+            assert_eq!(root.borrow().count_nodes(), 6);
+            assert_eq!(values(Node::preorder(&root)), vec![5, 3, 2, 4, 7, 10]);
+        }
+
+        #[test]
+        fn discard_by_value_detaches_the_subtree() {
+            let root: NodeLink = sample_tree();
+            let seven: NodeLink = Node::discard_by_value(&root, 7, SearchMode::DepthFirst).unwrap();
+            assert!(seven.borrow().parent.is_none());
+            assert!(root.borrow().right.is_none());
+            assert_eq!(Node::count_nodes_by_nodelink(&seven, 0), 2);
+            assert_eq!(Node::count_nodes_by_nodelink(&root, 0), 4);
+            assert!(Node::discard_by_value(&root, 7, SearchMode::BreadthFirst).is_none());
+            assert_eq!(Node::count_nodes_by_nodelink(&root, 0), 4);
+        }
+
+        //take a tree apart node by node, the default drop recurses once per level
+        fn dismantle(root: NodeLink) {
+            let mut stack: Vec<NodeLink> = vec![root];
+            while let Some(node) = stack.pop() {
+                let mut borrowed = node.borrow_mut();
+                stack.extend(borrowed.left.take());
+                stack.extend(borrowed.right.take());
+            }
+        }
+
+        #[test]
+        fn converting_a_deep_bst_fits_a_small_stack() {
+            let depth: i32 = 200_000;
+            std::thread::Builder::new()
+                .stack_size(64 * 1024)
+                .spawn(move || {
+                    let root: BstNodeLink = BstNode::new_bst_nodelink(0);
+                    let mut last: BstNodeLink = root.clone();
+                    for key in 1..depth {
+                        last.borrow_mut().add_right_child(&last, key);
+                        let next: BstNodeLink = last.borrow().right.clone().unwrap();
+                        last = next;
+                    }
+                    let copy: NodeLink = Node::from_bst_nodelink(&root).unwrap();
+                    assert_eq!(Node::inorder(&copy).count(), depth as usize);
+                    assert!(Node::is_bst(&copy));
+                    dismantle(copy);
+                    let mut stack: Vec<BstNodeLink> = vec![root];
+                    while let Some(node) = stack.pop() {
+                        stack.extend(node.borrow_mut().right.take());
+                    }
+                })
+                .unwrap()
+                .join()
+                .unwrap();
+        }
+
+        #[test]
+        fn deep_clone_of_a_long_chain_fits_a_small_stack() {
+            let depth: i32 = 200_000;
+            std::thread::Builder::new()
+                .stack_size(64 * 1024)
+                .spawn(move || {
+                    let root: NodeLink = Node::new_nodelink(0);
+                    let mut last: NodeLink = root.clone();
+                    for value in 1..depth {
+                        last.borrow_mut().add_left_child(&last, -value);
+                        let next: NodeLink = last.borrow().left.clone().unwrap();
+                        last = next;
+                    }
+                    let copy: NodeLink = Node::deep_clone(&root);
+                    assert!(!Rc::ptr_eq(&copy, &root));
+                    assert_eq!(Node::inorder(&copy).count(), depth as usize);
+                    assert!(Node::is_bst(&copy));
+                    dismantle(copy);
+                    dismantle(root);
+                })
+                .unwrap()
+                .join()
+                .unwrap();
+        }
+    }
 }
@@ -0,0 +1,508 @@
+use crate::structure::binary_tree::{self, InorderIter};
+use crate::structure::bst::{BstNode, BstNodeLink, WeakBstNodeLink};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+//this package implement a multiset on top of BstNode, so the same key can be stored more than once
+
+/// How repeated keys are kept inside the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateMode {
+    /// Every distinct key owns exactly one node and the node keeps a repeat count.
+    Counted,
+    /// Every repeat gets its own node, placed to the right: left < node <= right.
+    StoreRight,
+}
+
+fn node_id(node: &BstNodeLink) -> usize {
+    node.as_ptr() as usize
+}
+
+//repeat count of one node, the weak link keeps the address from being reused
+//by another node while the entry is alive
+struct Entry {
+    node: WeakBstNodeLink,
+    count: usize,
+}
+
+pub struct BstMultiset {
+    root: Option<BstNodeLink>,
+    mode: DuplicateMode,
+    //count per node in Counted mode, keyed by node address like the values of AugmentedBst
+    counts: HashMap<usize, Entry>,
+    len: usize,
+}
+
+impl BstMultiset {
+    pub fn new(mode: DuplicateMode) -> Self {
+        BstMultiset {
+            root: None,
+            mode,
+            counts: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn mode(&self) -> DuplicateMode {
+        self.mode
+    }
+
+    pub fn root(&self) -> Option<BstNodeLink> {
+        self.root.clone()
+    }
+
+    /**
+     * Total amount of keys, repeats included
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts one occurrence of `key`.
+    ///
+    /// # Returns
+    ///
+    /// * The node holding the key. In `Counted` mode a repeat returns the existing node.
+    pub fn insert(&mut self, key: i32) -> BstNodeLink {
+        self.len += 1;
+        if self.mode == DuplicateMode::Counted {
+            if let Some(node) = self.find(key) {
+                match self.entry_mut(&node) {
+                    Some(entry) => entry.count += 1,
+                    None => self.set_count(&node, 2),
+                }
+                return node;
+            }
+        }
+        let node: BstNodeLink = self.insert_node(key);
+        if self.mode == DuplicateMode::Counted {
+            self.set_count(&node, 1);
+        }
+        node
+    }
+
+    //plain insertion of a new node, equal keys go right
+    fn insert_node(&mut self, key: i32) -> BstNodeLink {
+        let root: BstNodeLink = match self.root {
+            Some(ref x) => x.clone(),
+            None => {
+                let node: BstNodeLink = BstNode::new_bst_nodelink(key);
+                self.root = Some(node.clone());
+                return node;
+            }
+        };
+        //same descent as tree_insert, equal keys keep going right
+        let mut current: BstNodeLink = root;
+        loop {
+            let go_left: bool = Some(key) < current.borrow().key;
+            let next: Option<BstNodeLink> = if go_left {
+                current.borrow().left.clone()
+            } else {
+                current.borrow().right.clone()
+            };
+            match next {
+                Some(x) => current = x,
+                None => {
+                    let mut parent = current.borrow_mut();
+                    if go_left {
+                        parent.add_left_child(&current, key);
+                        return parent.left.clone().unwrap();
+                    }
+                    parent.add_right_child(&current, key);
+                    return parent.right.clone().unwrap();
+                }
+            }
+        }
+    }
+
+    /**
+     * Amount of occurrences of key
+     */
+    pub fn count(&self, key: i32) -> usize {
+        match self.mode {
+            DuplicateMode::Counted => self.find(key).map_or(0, |x| self.count_of(&x)),
+            DuplicateMode::StoreRight => BstMultiset::count_right(&self.root, key),
+        }
+    }
+
+    pub fn contains(&self, key: i32) -> bool {
+        self.find(key).is_some()
+    }
+
+    /**
+     * Remove a single occurrence of key, return false if key is absent
+     */
+    pub fn remove_one(&mut self, key: i32) -> bool {
+        let node: BstNodeLink = match self.find(key) {
+            Some(x) => x,
+            None => return false,
+        };
+        self.len -= 1;
+        if self.mode == DuplicateMode::Counted {
+            if let Some(entry) = self.entry_mut(&node) {
+                entry.count -= 1;
+                if entry.count > 0 {
+                    return true;
+                }
+            }
+        }
+        self.delete_node(&node);
+        true
+    }
+
+    /**
+     * Remove every occurrence of key, return how many were removed
+     */
+    pub fn remove_all(&mut self, key: i32) -> usize {
+        let mut removed: usize = 0;
+        match self.mode {
+            DuplicateMode::Counted => {
+                if let Some(node) = self.find(key) {
+                    removed = self.count_of(&node);
+                    self.len -= removed;
+                    self.delete_node(&node);
+                }
+            }
+            DuplicateMode::StoreRight => {
+                while self.remove_one(key) {
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    /**
+     * Iterate the keys in ascending order, a key is yielded once per occurrence
+     */
+    pub fn iter(&self) -> MultisetIter<'_> {
+        MultisetIter {
+            multiset: self,
+            nodes: self.root.as_ref().map(binary_tree::inorder),
+            pending: None,
+        }
+    }
+
+    /**
+     * Smallest key strictly greater than key, repeats of key itself are skipped
+     */
+    pub fn successor(&self, key: i32) -> Option<i32> {
+        let node: BstNodeLink = self.successor_node(key)?;
+        let key: Option<i32> = node.borrow().key;
+        key
+    }
+
+    /**
+     * Node holding the smallest key strictly greater than key, the one carrying the count in Counted mode
+     */
+    pub fn successor_node(&self, key: i32) -> Option<BstNodeLink> {
+        let mut candidate: Option<BstNodeLink> = None;
+        let mut current: Option<BstNodeLink> = self.root.clone();
+        while let Some(x) = current {
+            if x.borrow().key > Some(key) {
+                current = x.borrow().left.clone();
+                candidate = Some(x);
+            } else {
+                current = x.borrow().right.clone();
+            }
+        }
+        candidate
+    }
+
+    /**
+     * Greatest key strictly smaller than key, repeats of key itself are skipped
+     */
+    pub fn predecessor(&self, key: i32) -> Option<i32> {
+        let node: BstNodeLink = self.predecessor_node(key)?;
+        let key: Option<i32> = node.borrow().key;
+        key
+    }
+
+    /**
+     * Node holding the greatest key strictly smaller than key. In StoreRight mode this is
+     * the topmost of the repeats, the others sit in its right subtree
+     */
+    pub fn predecessor_node(&self, key: i32) -> Option<BstNodeLink> {
+        let mut candidate: Option<BstNodeLink> = None;
+        let mut current: Option<BstNodeLink> = self.root.clone();
+        while let Some(x) = current {
+            let x_key: Option<i32> = x.borrow().key;
+            if x_key.is_some() && x_key < Some(key) {
+                let is_repeat: bool = candidate.as_ref().is_some_and(|c| c.borrow().key == x_key);
+                current = x.borrow().right.clone();
+                if !is_repeat {
+                    candidate = Some(x);
+                }
+            } else {
+                current = x.borrow().left.clone();
+            }
+        }
+        candidate
+    }
+
+    /**
+     * Every key within low..=high in ascending order, a key is listed once per occurrence
+     */
+    pub fn range(&self, low: i32, high: i32) -> Vec<i32> {
+        let mut keys: Vec<i32> = Vec::new();
+        if low > high {
+            return keys;
+        }
+        //in order walk with an explicit stack, left holds strictly smaller keys and right
+        //holds greater or equal keys, so subtrees outside low..=high are never entered
+        let mut stack: Vec<BstNodeLink> = Vec::new();
+        let mut current: Option<BstNodeLink> = self.root.clone();
+        loop {
+            while let Some(x) = current {
+                current = if x.borrow().key > Some(low) {
+                    x.borrow().left.clone()
+                } else {
+                    None
+                };
+                stack.push(x);
+            }
+            let x: BstNodeLink = match stack.pop() {
+                Some(x) => x,
+                None => break,
+            };
+            let key: i32 = match x.borrow().key {
+                Some(k) => k,
+                None => continue,
+            };
+            if key > high {
+                break;
+            }
+            if key >= low {
+                keys.extend(std::iter::repeat_n(key, self.count_of(&x)));
+            }
+            current = x.borrow().right.clone();
+        }
+        keys
+    }
+
+    //occurrences held by one node, a node without an entry holds a single key
+    fn count_of(&self, node: &BstNodeLink) -> usize {
+        match self.mode {
+            DuplicateMode::Counted => self
+                .counts
+                .get(&node_id(node))
+                .filter(|x| std::ptr::eq(x.node.as_ptr(), Rc::as_ptr(node)))
+                .map_or(1, |x| x.count),
+            DuplicateMode::StoreRight => 1,
+        }
+    }
+
+    //the entry of node, an entry left by another node at the same address is ignored
+    fn entry_mut(&mut self, node: &BstNodeLink) -> Option<&mut Entry> {
+        self.counts
+            .get_mut(&node_id(node))
+            .filter(|x| std::ptr::eq(x.node.as_ptr(), Rc::as_ptr(node)))
+    }
+
+    fn set_count(&mut self, node: &BstNodeLink, count: usize) {
+        let entry: Entry = Entry {
+            node: Rc::downgrade(node),
+            count,
+        };
+        self.counts.insert(node_id(node), entry);
+    }
+
+    //first node on the search path holding key, the root of every other repeat in StoreRight mode
+    fn find(&self, key: i32) -> Option<BstNodeLink> {
+        self.root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, &key))
+    }
+
+    //count in StoreRight mode, repeats can only hide in the right subtree of an equal node
+    fn count_right(node: &Option<BstNodeLink>, key: i32) -> usize {
+        let mut count: usize = 0;
+        let mut current: Option<BstNodeLink> = node.clone();
+        while let Some(x) = current {
+            let x_key: Option<i32> = x.borrow().key;
+            if x_key == Some(key) {
+                count += 1;
+            }
+            current = if x_key > Some(key) {
+                x.borrow().left.clone()
+            } else {
+                x.borrow().right.clone()
+            };
+        }
+        count
+    }
+
+    //unlink node from the tree and keep the root handle valid
+    fn delete_node(&mut self, node: &BstNodeLink) {
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(node);
+        let is_root: bool = self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, node));
+        BstNode::tree_delete_node(node);
+        self.counts.remove(&node_id(node));
+        if is_root {
+            self.root = replacement;
+        }
+    }
+}
+
+/// Iterator over a multiset in ascending order, see `BstMultiset::iter`.
+pub struct MultisetIter<'a> {
+    multiset: &'a BstMultiset,
+    nodes: Option<InorderIter<BstNode>>,
+    //key being repeated and how many repeats are left
+    pending: Option<(i32, usize)>,
+}
+
+impl Iterator for MultisetIter<'_> {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        if let Some((key, left)) = self.pending {
+            self.pending = if left > 1 {
+                Some((key, left - 1))
+            } else {
+                None
+            };
+            return Some(key);
+        }
+        //NIL keys are skipped
+        let (node, key) = self
+            .nodes
+            .as_mut()?
+            .find_map(|x| x.borrow().key.map(|k| (x.clone(), k)))?;
+        let repeat: usize = self.multiset.count_of(&node);
+        if repeat > 1 {
+            self.pending = Some((key, repeat - 1));
+        }
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [DuplicateMode; 2] = [DuplicateMode::Counted, DuplicateMode::StoreRight];
+
+    fn sample(mode: DuplicateMode) -> BstMultiset {
+        let mut multiset: BstMultiset = BstMultiset::new(mode);
+        for key in [5, 3, 8, 5, 3, 5, 9, 1] {
+            multiset.insert(key);
+        }
+        multiset
+    }
+
+    #[test]
+    fn counts_and_iteration_agree_in_both_modes() {
+        for mode in MODES {
+            let multiset: BstMultiset = sample(mode);
+            assert_eq!(multiset.mode(), mode);
+            assert_eq!(multiset.len(), 8);
+            assert_eq!(multiset.count(5), 3);
+            assert_eq!(multiset.count(3), 2);
+            assert_eq!(multiset.count(4), 0);
+            assert!(multiset.contains(9));
+            assert_eq!(
+                multiset.iter().collect::<Vec<i32>>(),
+                vec![1, 3, 3, 5, 5, 5, 8, 9]
+            );
+        }
+    }
+
+    #[test]
+    fn counted_mode_keeps_one_node_per_key() {
+        let mut multiset: BstMultiset = sample(DuplicateMode::Counted);
+        let node: BstNodeLink = BstNode::tree_search_link(&multiset.root().unwrap(), &5).unwrap();
+        assert!(std::rc::Rc::ptr_eq(&multiset.insert(5), &node));
+        assert_eq!(
+            BstNode::inorder_keys(&multiset.root().unwrap()),
+            vec![1, 3, 5, 8, 9]
+        );
+        let stored: BstMultiset = sample(DuplicateMode::StoreRight);
+        assert_eq!(BstNode::inorder_keys(&stored.root().unwrap()).len(), 8);
+    }
+
+    #[test]
+    fn remove_one_and_remove_all() {
+        for mode in MODES {
+            let mut multiset: BstMultiset = sample(mode);
+            assert!(multiset.remove_one(5));
+            assert_eq!(multiset.count(5), 2);
+            assert_eq!(multiset.remove_all(5), 2);
+            assert!(!multiset.contains(5));
+            assert!(!multiset.remove_one(5));
+            assert_eq!(multiset.remove_all(5), 0);
+            assert_eq!(multiset.len(), 5);
+            assert_eq!(multiset.iter().collect::<Vec<i32>>(), vec![1, 3, 3, 8, 9]);
+        }
+    }
+
+    #[test]
+    fn removing_the_root_keeps_the_handle() {
+        for mode in MODES {
+            let mut multiset: BstMultiset = sample(mode);
+            while !multiset.is_empty() {
+                let root_key: i32 = multiset.root().unwrap().borrow().key.unwrap();
+                assert!(multiset.remove_one(root_key));
+                if let Some(root) = multiset.root() {
+                    assert!(BstNode::get_parent(&root).is_none());
+                }
+            }
+            assert!(multiset.root().is_none());
+            assert_eq!(multiset.iter().next(), None);
+        }
+    }
+
+    #[test]
+    fn neighbours_skip_the_repeats() {
+        for mode in MODES {
+            let multiset: BstMultiset = sample(mode);
+            assert_eq!(multiset.successor(5), Some(8));
+            assert_eq!(multiset.successor(3), Some(5));
+            assert_eq!(multiset.successor(9), None);
+            assert_eq!(multiset.predecessor(5), Some(3));
+            assert_eq!(multiset.predecessor(1), None);
+            assert_eq!(multiset.predecessor(100), Some(9));
+        }
+    }
+
+    #[test]
+    fn range_lists_every_occurrence() {
+        for mode in MODES {
+            let multiset: BstMultiset = sample(mode);
+            assert_eq!(multiset.range(3, 5), vec![3, 3, 5, 5, 5]);
+            assert_eq!(multiset.range(6, 7), Vec::<i32>::new());
+            assert_eq!(multiset.range(9, 1), Vec::<i32>::new());
+            assert_eq!(multiset.range(i32::MIN, i32::MAX).len(), 8);
+        }
+    }
+
+    #[test]
+    fn counts_stay_with_their_node() {
+        let mut multiset: BstMultiset = sample(DuplicateMode::Counted);
+        let three: BstNodeLink = multiset.successor_node(2).unwrap();
+        assert_eq!(multiset.count_of(&three), 2);
+        //the root 5 has two children, deleting it splices its successor 8 into place
+        assert_eq!(multiset.remove_all(5), 3);
+        assert_eq!(multiset.root().unwrap().borrow().key, Some(8));
+        assert_eq!(multiset.count_of(&three), 2);
+        assert_eq!(multiset.count(8), 1);
+        assert_eq!(multiset.counts.len(), 4);
+        assert_eq!(multiset.range(i32::MIN, i32::MAX), vec![1, 3, 3, 8, 9]);
+    }
+
+    #[test]
+    fn neighbour_nodes_carry_every_repeat() {
+        let counted: BstMultiset = sample(DuplicateMode::Counted);
+        let five: BstNodeLink = counted.successor_node(4).unwrap();
+        assert_eq!(counted.count_of(&five), 3);
+        let stored: BstMultiset = sample(DuplicateMode::StoreRight);
+        let topmost: BstNodeLink = stored.find(5).unwrap();
+        assert!(Rc::ptr_eq(&stored.predecessor_node(6).unwrap(), &topmost));
+        assert!(Rc::ptr_eq(&stored.successor_node(4).unwrap(), &topmost));
+        assert!(stored.successor_node(9).is_none());
+    }
+}
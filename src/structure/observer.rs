@@ -0,0 +1,461 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::rc::Rc;
+
+//this package implement structured event hooks for the tree operations.
+//The BstNode operations emit a TreeEvent for every insert, delete, rotation, transplant and search,
+//every observer registered on the current thread receives the events its filter lets through.
+//Nothing is formatted or allocated while no observer is registered.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RotateDirection {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEvent {
+    /// A key was inserted, depth counts from the root at 0.
+    Insert { key: i32, depth: usize },
+    /// A node was unlinked from the tree.
+    Delete { key: Option<i32> },
+    /// A node was rotated down, its child took its place.
+    Rotate {
+        pivot: Option<i32>,
+        direction: RotateDirection,
+    },
+    /// Subtree v took the place of subtree u.
+    Transplant { u: Option<i32>, v: Option<i32> },
+    /// A key was looked up, path_length counts the visited nodes.
+    Search {
+        key: i32,
+        path_length: usize,
+        found: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Insert,
+    Delete,
+    Rotate,
+    Transplant,
+    Search,
+}
+
+impl TreeEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            TreeEvent::Insert { .. } => EventKind::Insert,
+            TreeEvent::Delete { .. } => EventKind::Delete,
+            TreeEvent::Rotate { .. } => EventKind::Rotate,
+            TreeEvent::Transplant { .. } => EventKind::Transplant,
+            TreeEvent::Search { .. } => EventKind::Search,
+        }
+    }
+}
+
+impl fmt::Display for TreeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeEvent::Insert { key, depth } => write!(f, "insert key={} depth={}", key, depth),
+            TreeEvent::Delete { key } => write!(f, "delete key={:?}", key),
+            TreeEvent::Rotate { pivot, direction } => {
+                write!(f, "rotate pivot={:?} direction={:?}", pivot, direction)
+            }
+            TreeEvent::Transplant { u, v } => write!(f, "transplant u={:?} v={:?}", u, v),
+            TreeEvent::Search {
+                key,
+                path_length,
+                found,
+            } => write!(
+                f,
+                "search key={} path_length={} found={}",
+                key, path_length, found
+            ),
+        }
+    }
+}
+
+/// Which event kinds an observer wants to receive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventFilter {
+    kinds: Vec<EventKind>,
+}
+
+impl EventFilter {
+    pub fn all() -> Self {
+        EventFilter {
+            kinds: vec![
+                EventKind::Insert,
+                EventKind::Delete,
+                EventKind::Rotate,
+                EventKind::Transplant,
+                EventKind::Search,
+            ],
+        }
+    }
+
+    pub fn only(kinds: &[EventKind]) -> Self {
+        EventFilter {
+            kinds: kinds.to_vec(),
+        }
+    }
+
+    pub fn accepts(&self, kind: EventKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+}
+
+pub trait TreeObserver {
+    fn on_event(&self, event: &TreeEvent);
+}
+
+/// Writes every event as one line to stderr.
+pub struct StderrObserver;
+
+impl TreeObserver for StderrObserver {
+    fn on_event(&self, event: &TreeEvent) {
+        eprintln!("binarysearchtree: {}", event);
+    }
+}
+
+/// Writes every event as one line to a file.
+pub struct FileObserver {
+    writer: RefCell<BufWriter<File>>,
+}
+
+impl FileObserver {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        Ok(FileObserver {
+            writer: RefCell::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+}
+
+impl TreeObserver for FileObserver {
+    fn on_event(&self, event: &TreeEvent) {
+        //a failing log write must never break the tree operation that emitted it
+        let _ = writeln!(self.writer.borrow_mut(), "{}", event);
+    }
+}
+
+/// Keeps every event in memory, meant for tests.
+#[derive(Default)]
+pub struct CollectingObserver {
+    events: RefCell<Vec<TreeEvent>>,
+}
+
+impl CollectingObserver {
+    pub fn new() -> Self {
+        CollectingObserver::default()
+    }
+
+    pub fn events(&self) -> Vec<TreeEvent> {
+        self.events.borrow().clone()
+    }
+
+    pub fn clear(&self) {
+        self.events.borrow_mut().clear();
+    }
+}
+
+impl TreeObserver for CollectingObserver {
+    fn on_event(&self, event: &TreeEvent) {
+        self.events.borrow_mut().push(event.clone());
+    }
+}
+
+/// Handle returned by `register`, used to remove the observer again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+struct Registry {
+    next_id: usize,
+    observers: Vec<(ObserverId, EventFilter, Rc<dyn TreeObserver>)>,
+}
+
+thread_local! {
+    //nodes are Rc based and never leave their thread, so neither do the observers
+    static REGISTRY: RefCell<Registry> = const {
+        RefCell::new(Registry {
+            next_id: 0,
+            observers: Vec::new(),
+        })
+    };
+    static SUSPENDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/**
+ * Run f without handing any event to the observers of the current thread, e.g. while timing
+ */
+pub fn suspended<R>(f: impl FnOnce() -> R) -> R {
+    let _guard: SuspendGuard = SuspendGuard {
+        was_suspended: SUSPENDED.with(|x| x.replace(true)),
+    };
+    f()
+}
+
+//puts the previous state back when suspended returns, and also when f panics
+struct SuspendGuard {
+    was_suspended: bool,
+}
+
+impl Drop for SuspendGuard {
+    fn drop(&mut self) {
+        SUSPENDED.with(|x| x.set(self.was_suspended));
+    }
+}
+
+/**
+ * Register an observer on the current thread, it receives every event accepted by filter
+ */
+pub fn register(observer: Rc<dyn TreeObserver>, filter: EventFilter) -> ObserverId {
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let id: ObserverId = ObserverId(registry.next_id);
+        registry.next_id += 1;
+        registry.observers.push((id, filter, observer));
+        id
+    })
+}
+
+/**
+ * Remove an observer, return false if it was not registered
+ */
+pub fn unregister(id: ObserverId) -> bool {
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let before: usize = registry.observers.len();
+        registry.observers.retain(|(x, _, _)| *x != id);
+        registry.observers.len() != before
+    })
+}
+
+pub fn has_observers() -> bool {
+    REGISTRY.with(|registry| !registry.borrow().observers.is_empty())
+}
+
+/**
+ * Hand an event to every interested observer, make_event only runs if someone listens
+ */
+pub fn emit(make_event: impl FnOnce() -> TreeEvent) {
+    if SUSPENDED.with(|x| x.get()) {
+        return;
+    }
+    //copy the interested observers out, so an observer may itself register or touch a tree
+    let observers: Vec<(EventFilter, Rc<dyn TreeObserver>)> = REGISTRY.with(|registry| {
+        registry
+            .borrow()
+            .observers
+            .iter()
+            .map(|(_, filter, observer)| (filter.clone(), observer.clone()))
+            .collect()
+    });
+    if observers.is_empty() {
+        return;
+    }
+    let event: TreeEvent = make_event();
+    for (filter, observer) in observers.iter() {
+        if filter.accepts(event.kind()) {
+            observer.on_event(&event);
+        }
+    }
+}
+
+/**
+ * Register a stderr observer when RUST_LOG asks for debug or trace output of this crate,
+ * e.g. RUST_LOG=binarysearchtree=debug as set by the flatpak manifest
+ */
+pub fn init_from_env() -> Option<ObserverId> {
+    let directives: String = std::env::var("RUST_LOG").ok()?;
+    if !wants_debug(&directives) {
+        return None;
+    }
+    Some(register(Rc::new(StderrObserver), EventFilter::all()))
+}
+
+//RUST_LOG target naming this crate, its modules are named binarysearchtree::structure and so on
+const CRATE_TARGET: &str = "binarysearchtree";
+
+//true if the RUST_LOG value enables debug or trace for this crate. As in env_logger the most
+//specific directive wins: the longest target naming this crate or one of its modules, then a
+//bare level as the fallback for every target. A bare target enables every level
+fn wants_debug(directives: &str) -> bool {
+    //length of the winning target and whether its level enables debug, a bare level has length 0
+    let mut best: Option<(usize, bool)> = None;
+    for directive in directives.split(',').map(str::trim) {
+        if directive.is_empty() {
+            continue;
+        }
+        let (target, enabled): (&str, bool) = match directive.split_once('=') {
+            Some((target, level)) => match level_enables_debug(level.trim()) {
+                Some(enabled) => (target.trim(), enabled),
+                //env_logger ignores a directive with an unknown level
+                None => continue,
+            },
+            None => match level_enables_debug(directive) {
+                Some(enabled) => ("", enabled),
+                None => (directive, true),
+            },
+        };
+        let matches: bool = target.is_empty()
+            || target == CRATE_TARGET
+            || target
+                .strip_prefix(CRATE_TARGET)
+                .is_some_and(|x| x.starts_with("::"));
+        //a later directive with a target of the same length overrides an earlier one
+        if matches && best.is_none_or(|(length, _)| target.len() >= length) {
+            best = Some((target.len(), enabled));
+        }
+    }
+    best.is_some_and(|(_, enabled)| enabled)
+}
+
+//Some(true) for debug and trace, Some(false) for the quieter levels, None if level is not a level
+fn level_enables_debug(level: &str) -> Option<bool> {
+    match level.to_ascii_lowercase().as_str() {
+        "debug" | "trace" => Some(true),
+        "off" | "error" | "warn" | "info" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(key: i32) -> TreeEvent {
+        TreeEvent::Insert { key, depth: 0 }
+    }
+
+    #[test]
+    fn filters_pick_the_event_kinds() {
+        let searches: Rc<CollectingObserver> = Rc::new(CollectingObserver::new());
+        let everything: Rc<CollectingObserver> = Rc::new(CollectingObserver::new());
+        let first: ObserverId = register(searches.clone(), EventFilter::only(&[EventKind::Search]));
+        let second: ObserverId = register(everything.clone(), EventFilter::all());
+        let search: TreeEvent = TreeEvent::Search {
+            key: 4,
+            path_length: 2,
+            found: false,
+        };
+        emit(|| insert(1));
+        emit(|| search.clone());
+        emit(|| TreeEvent::Delete { key: Some(1) });
+        assert_eq!(searches.events(), vec![search.clone()]);
+        assert_eq!(everything.events().len(), 3);
+        everything.clear();
+        assert!(everything.events().is_empty());
+        assert!(unregister(first));
+        assert!(unregister(second));
+    }
+
+    #[test]
+    fn unregistered_observers_hear_nothing() {
+        let collector: Rc<CollectingObserver> = Rc::new(CollectingObserver::new());
+        let id: ObserverId = register(collector.clone(), EventFilter::all());
+        assert!(has_observers());
+        emit(|| insert(1));
+        assert!(unregister(id));
+        assert!(!unregister(id));
+        assert!(!has_observers());
+        emit(|| insert(2));
+        assert_eq!(collector.events(), vec![insert(1)]);
+    }
+
+    #[test]
+    fn events_are_only_built_for_a_listener() {
+        emit(|| panic!("nobody listens, the event must not be built"));
+        let collector: Rc<CollectingObserver> = Rc::new(CollectingObserver::new());
+        let id: ObserverId = register(collector.clone(), EventFilter::all());
+        suspended(|| {
+            emit(|| panic!("observers are suspended"));
+            //nesting keeps the outer suspension
+            suspended(|| ());
+            emit(|| panic!("observers are still suspended"));
+        });
+        emit(|| insert(3));
+        assert_eq!(collector.events(), vec![insert(3)]);
+        unregister(id);
+    }
+
+    #[test]
+    fn a_panic_inside_suspended_lifts_the_suspension() {
+        let collector: Rc<CollectingObserver> = Rc::new(CollectingObserver::new());
+        let id: ObserverId = register(collector.clone(), EventFilter::all());
+        let result = std::panic::catch_unwind(|| suspended(|| panic!("timing failed")));
+        assert!(result.is_err());
+        emit(|| insert(4));
+        assert_eq!(collector.events(), vec![insert(4)]);
+        unregister(id);
+    }
+
+    #[test]
+    fn rust_log_directives() {
+        for directives in [
+            "debug",
+            "TRACE",
+            "binarysearchtree",
+            "binarysearchtree=debug",
+            "warn, binarysearchtree = trace",
+            "binarysearchtree::structure=debug",
+            "info,binarysearchtree::structure::bst=trace",
+        ] {
+            assert!(wants_debug(directives), "{}", directives);
+        }
+        for directives in [
+            "",
+            "info",
+            "other=debug",
+            "other",
+            "binarysearchtree=info",
+            "binarysearchtreeextra=debug",
+            "binarysearchtree=verbose",
+        ] {
+            assert!(!wants_debug(directives), "{}", directives);
+        }
+    }
+
+    #[test]
+    fn the_most_specific_directive_wins() {
+        //a crate directive overrides the global level, whichever comes first
+        assert!(!wants_debug("debug,binarysearchtree=info"));
+        assert!(!wants_debug("binarysearchtree=info,debug"));
+        assert!(wants_debug("off,binarysearchtree=debug"));
+        //a module directive overrides the crate directive
+        assert!(wants_debug(
+            "binarysearchtree=info,binarysearchtree::structure=debug"
+        ));
+        assert!(!wants_debug(
+            "binarysearchtree=debug,binarysearchtree::structure=warn"
+        ));
+        //directives for other crates take no part
+        assert!(wants_debug("debug,other=info"));
+        //of two directives for the same target the later one wins
+        assert!(!wants_debug("binarysearchtree=debug,binarysearchtree=info"));
+    }
+
+    #[test]
+    fn display_is_one_line_per_event() {
+        assert_eq!(insert(5).to_string(), "insert key=5 depth=0");
+        assert_eq!(
+            TreeEvent::Rotate {
+                pivot: Some(2),
+                direction: RotateDirection::Left
+            }
+            .to_string(),
+            "rotate pivot=Some(2) direction=Left"
+        );
+        assert_eq!(
+            TreeEvent::Transplant {
+                u: Some(1),
+                v: None
+            }
+            .kind(),
+            EventKind::Transplant
+        );
+    }
+}
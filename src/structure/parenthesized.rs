@@ -0,0 +1,273 @@
+use crate::structure::binary_tree::{BinaryTreeNode, TreeLink};
+use crate::structure::error::{BstError, BstResult};
+
+//this package implement the key(left,right) preorder text shared by tree::Node and serialize.
+//The text is split into tokens first, whitespace is only skipped between tokens, so "1 2" is two keys.
+//Both the reader and the writer keep an explicit stack, deeply nested text does not grow the call stack.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Key(i32),
+    Open,
+    Comma,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Key(key) => key.to_string(),
+            Token::Open => "'('".to_string(),
+            Token::Comma => "','".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+}
+
+//every token along with the char position it starts at
+fn tokenize(text: &str) -> BstResult<Vec<(usize, Token)>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut position: usize = 0;
+    while position < chars.len() {
+        let start: usize = position;
+        let token: Token = match chars[position] {
+            x if x.is_whitespace() => {
+                position += 1;
+                continue;
+            }
+            '(' => Token::Open,
+            ',' => Token::Comma,
+            ')' => Token::Close,
+            '-' | '0'..='9' => {
+                position += 1;
+                while position < chars.len() && chars[position].is_ascii_digit() {
+                    position += 1;
+                }
+                let digits: String = chars[start..position].iter().collect();
+                let key: i32 = digits.parse().map_err(|_| {
+                    BstError::InvalidInput(format!("bad key {} at position {}", digits, start))
+                })?;
+                tokens.push((start, Token::Key(key)));
+                continue;
+            }
+            x => {
+                return Err(BstError::InvalidInput(format!(
+                    "unexpected character '{}' at position {}",
+                    x, start
+                )))
+            }
+        };
+        tokens.push((start, token));
+        position += 1;
+    }
+    Ok(tokens)
+}
+
+/// One node read from the text, the children are indices into the parsed nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedNode {
+    pub key: i32,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Left,
+    Right,
+}
+
+/// Reads a tree written as `key(left,right)` in preorder, e.g. `5(3(2,4),7(,10))`.
+///
+/// A missing child is left empty and a leaf is just its key.
+///
+/// # Returns
+///
+/// The nodes in preorder, the root first, so every parent comes before its children.
+/// Empty for an empty or blank text.
+///
+/// # Errors
+///
+/// * `BstError::InvalidInput` if the text is not in the format.
+pub fn parse(text: &str) -> BstResult<Vec<ParsedNode>> {
+    let tokens: Vec<(usize, Token)> = tokenize(text)?;
+    let mut nodes: Vec<ParsedNode> = Vec::new();
+    if tokens.is_empty() {
+        return Ok(nodes);
+    }
+    let end: usize = text.chars().count();
+    let mut next: usize = 0;
+    //nodes whose parentheses are open, with the child being read
+    let mut open: Vec<(usize, Slot)> = Vec::new();
+    loop {
+        //a child position, only the root may not be empty
+        if let Some((_, Token::Key(key))) = tokens.get(next) {
+            let index: usize = nodes.len();
+            nodes.push(ParsedNode {
+                key: *key,
+                left: None,
+                right: None,
+            });
+            if let Some((parent, slot)) = open.last() {
+                match slot {
+                    Slot::Left => nodes[*parent].left = Some(index),
+                    Slot::Right => nodes[*parent].right = Some(index),
+                }
+            }
+            next += 1;
+            if let Some((_, Token::Open)) = tokens.get(next) {
+                next += 1;
+                open.push((index, Slot::Left));
+                continue;
+            }
+        } else if open.is_empty() {
+            return Err(unexpected(&tokens, next, end, "a key"));
+        }
+        //the child is complete, close every node whose right child it was
+        loop {
+            match open.last_mut() {
+                None => {
+                    if next != tokens.len() {
+                        return Err(unexpected(&tokens, next, end, "the end of the text"));
+                    }
+                    return Ok(nodes);
+                }
+                Some((_, slot)) if *slot == Slot::Left => {
+                    if tokens.get(next).map(|x| x.1) != Some(Token::Comma) {
+                        return Err(unexpected(&tokens, next, end, "','"));
+                    }
+                    next += 1;
+                    *slot = Slot::Right;
+                    break;
+                }
+                Some((parent, _)) => {
+                    if tokens.get(next).map(|x| x.1) != Some(Token::Close) {
+                        return Err(unexpected(&tokens, next, end, "')'"));
+                    }
+                    let parent: usize = *parent;
+                    if nodes[parent].left.is_none() && nodes[parent].right.is_none() {
+                        return Err(BstError::InvalidInput(format!(
+                            "a leaf is written without parentheses at position {}",
+                            tokens[next].0
+                        )));
+                    }
+                    next += 1;
+                    open.pop();
+                }
+            }
+        }
+    }
+}
+
+fn unexpected(tokens: &[(usize, Token)], next: usize, end: usize, expected: &str) -> BstError {
+    match tokens.get(next) {
+        Some((position, token)) => BstError::InvalidInput(format!(
+            "expected {}, found {} at position {}",
+            expected,
+            token.describe(),
+            position
+        )),
+        None => BstError::InvalidInput(format!("expected {} at position {}", expected, end)),
+    }
+}
+
+enum Piece<T: BinaryTreeNode> {
+    Node(TreeLink<T>),
+    Text(char),
+}
+
+/**
+ * Write the subtree under node in the format read by parse, NIL keys are skipped with their subtree
+ */
+pub fn write<T: BinaryTreeNode>(node: &TreeLink<T>) -> String {
+    let mut text: String = String::new();
+    let mut stack: Vec<Piece<T>> = vec![Piece::Node(node.clone())];
+    while let Some(piece) = stack.pop() {
+        let x: TreeLink<T> = match piece {
+            Piece::Text(c) => {
+                text.push(c);
+                continue;
+            }
+            Piece::Node(x) => x,
+        };
+        let borrowed = x.borrow();
+        let key: i32 = match borrowed.key() {
+            Some(key) => key,
+            None => continue,
+        };
+        text.push_str(&key.to_string());
+        let (left, right) = (borrowed.left(), borrowed.right());
+        if left.is_some() || right.is_some() {
+            //pushed back to front, the stack pops them in reading order
+            stack.push(Piece::Text(')'));
+            stack.extend(right.map(Piece::Node));
+            stack.push(Piece::Text(','));
+            stack.extend(left.map(Piece::Node));
+            stack.push(Piece::Text('('));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(key: i32) -> ParsedNode {
+        ParsedNode {
+            key,
+            left: None,
+            right: None,
+        }
+    }
+
+    #[test]
+    fn parse_lists_the_nodes_in_preorder() {
+        assert_eq!(
+            parse(" 5 ( -3 ,\n7(,10) ) ").unwrap(),
+            vec![
+                ParsedNode {
+                    key: 5,
+                    left: Some(1),
+                    right: Some(2)
+                },
+                leaf(-3),
+                ParsedNode {
+                    key: 7,
+                    left: None,
+                    right: Some(3)
+                },
+                leaf(10),
+            ]
+        );
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse(" \t").unwrap().is_empty());
+    }
+
+    #[test]
+    fn whitespace_only_separates_tokens() {
+        for text in ["1 2", "1(2 3,)", "- 5", "5(3,4) 6"] {
+            assert!(
+                matches!(parse(text), Err(BstError::InvalidInput(_))),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn deep_nesting_fits_a_small_stack() {
+        let depth: usize = 100_000;
+        let text: String = "1(".repeat(depth) + "1" + &",)".repeat(depth);
+        let unterminated: String = "1(".repeat(depth);
+        let (parsed, error) = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || (parse(&text).map(|x| x.len()), parse(&unterminated).is_err()))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(parsed.unwrap(), depth + 1);
+        assert!(error);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+pub type PersistentNodeLink = Rc<PersistentNode>;
+
+//this package implement a persistent (immutable) BST
+//nodes are never mutated after creation, insert and remove copy only the search path
+//and share every other subtree with the previous version through Rc.
+//Since a shared subtree may hang below several versions at once, nodes keep no parent pointer.
+#[derive(Debug)]
+pub struct PersistentNode {
+    pub key: i32,
+    pub left: Option<PersistentNodeLink>,
+    pub right: Option<PersistentNodeLink>,
+}
+
+//dropping a version frees its nodes one by one, the default drop recurses once per level
+//and overflows the stack on the long paths sorted insertions build
+impl Drop for PersistentNode {
+    fn drop(&mut self) {
+        let mut stack: Vec<PersistentNodeLink> = Vec::new();
+        stack.extend(self.left.take());
+        stack.extend(self.right.take());
+        while let Some(x) = stack.pop() {
+            //nodes still shared with another version stay alive
+            if let Ok(mut node) = Rc::try_unwrap(x) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+impl PersistentNode {
+    fn new_link(
+        key: i32,
+        left: Option<PersistentNodeLink>,
+        right: Option<PersistentNodeLink>,
+    ) -> PersistentNodeLink {
+        Rc::new(PersistentNode { key, left, right })
+    }
+
+    /// Inserts a key into the tree rooted at `node` without touching it.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - Root of the version to insert into (can be `None`).
+    /// * `key` - The key value to insert.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(root)` of the new version; when `key` is already present the old root is returned as is.
+    pub fn insert(node: &Option<PersistentNodeLink>, key: i32) -> Option<PersistentNodeLink> {
+        let mut path: Vec<(PersistentNodeLink, Ordering)> = Vec::new();
+        let mut current: Option<PersistentNodeLink> = node.clone();
+        while let Some(x) = current {
+            let direction: Ordering = key.cmp(&x.key);
+            current = match direction {
+                Ordering::Equal => return node.clone(),
+                Ordering::Less => x.left.clone(),
+                Ordering::Greater => x.right.clone(),
+            };
+            path.push((x, direction));
+        }
+        PersistentNode::copy_path(path, Some(PersistentNode::new_link(key, None, None)))
+    }
+
+    //copy every node of path bottom-up, child takes the place of the last step
+    //the walk is iterative so sorted insertions do not grow the call stack
+    fn copy_path(
+        path: Vec<(PersistentNodeLink, Ordering)>,
+        child: Option<PersistentNodeLink>,
+    ) -> Option<PersistentNodeLink> {
+        let mut result: Option<PersistentNodeLink> = child;
+        for (x, direction) in path.into_iter().rev() {
+            result = Some(match direction {
+                Ordering::Less => PersistentNode::new_link(x.key, result, x.right.clone()),
+                _ => PersistentNode::new_link(x.key, x.left.clone(), result),
+            });
+        }
+        result
+    }
+
+    /// Removes a key from the tree rooted at `node` without touching it.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - Root of the version to remove from (can be `None`).
+    /// * `key` - The key value to remove.
+    ///
+    /// # Returns
+    ///
+    /// * The root of the new version, `None` if it ends up empty.
+    ///   When `key` is absent the old root is returned as is.
+    pub fn remove(node: &Option<PersistentNodeLink>, key: i32) -> Option<PersistentNodeLink> {
+        let mut path: Vec<(PersistentNodeLink, Ordering)> = Vec::new();
+        let mut current: Option<PersistentNodeLink> = node.clone();
+        while let Some(x) = current {
+            let direction: Ordering = key.cmp(&x.key);
+            current = match direction {
+                Ordering::Equal => {
+                    let replacement: Option<PersistentNodeLink> = match (&x.left, &x.right) {
+                        (None, _) => x.right.clone(),
+                        (_, None) => x.left.clone(),
+                        (Some(_), Some(right)) => {
+                            //replace the key with its successor and drop the successor from the right subtree,
+                            //the successor has no left child so that removal stops right there
+                            let successor: i32 = PersistentNode::minimum(right).key;
+                            Some(PersistentNode::new_link(
+                                successor,
+                                x.left.clone(),
+                                PersistentNode::remove(&x.right, successor),
+                            ))
+                        }
+                    };
+                    return PersistentNode::copy_path(path, replacement);
+                }
+                Ordering::Less => x.left.clone(),
+                Ordering::Greater => x.right.clone(),
+            };
+            path.push((x, direction));
+        }
+        node.clone()
+    }
+
+    //search the tree which node fit the value
+    pub fn search(node: &Option<PersistentNodeLink>, key: i32) -> Option<PersistentNodeLink> {
+        let mut current: Option<&PersistentNodeLink> = node.as_ref();
+        while let Some(x) = current {
+            current = match key.cmp(&x.key) {
+                Ordering::Equal => return Some(x.clone()),
+                Ordering::Less => x.left.as_ref(),
+                Ordering::Greater => x.right.as_ref(),
+            };
+        }
+        None
+    }
+
+    pub fn minimum(node: &PersistentNodeLink) -> &PersistentNodeLink {
+        let mut current: &PersistentNodeLink = node;
+        while let Some(ref left) = current.left {
+            current = left;
+        }
+        current
+    }
+
+    pub fn maximum(node: &PersistentNodeLink) -> &PersistentNodeLink {
+        let mut current: &PersistentNodeLink = node;
+        while let Some(ref right) = current.right {
+            current = right;
+        }
+        current
+    }
+
+    //collect the keys in ascending order
+    fn collect_keys(node: &Option<PersistentNodeLink>, keys: &mut Vec<i32>) {
+        let mut stack: Vec<&PersistentNodeLink> = Vec::new();
+        let mut current: Option<&PersistentNodeLink> = node.as_ref();
+        loop {
+            while let Some(x) = current {
+                stack.push(x);
+                current = x.left.as_ref();
+            }
+            match stack.pop() {
+                Some(x) => {
+                    keys.push(x.key);
+                    current = x.right.as_ref();
+                }
+                None => return,
+            }
+        }
+    }
+
+    //copy the shape into mutable BstNode with parent pointers set,
+    //the pending nodes are kept on a stack so long paths do not recurse once per level
+    fn to_bst_nodelink(node: &PersistentNodeLink) -> BstNodeLink {
+        let root: BstNodeLink = BstNode::new_bst_nodelink(node.key);
+        let mut stack: Vec<(&PersistentNodeLink, BstNodeLink)> = vec![(node, root.clone())];
+        while let Some((original, link)) = stack.pop() {
+            if let Some(ref left) = original.left {
+                let left_link: BstNodeLink = BstNode::new_bst_nodelink(left.key);
+                left_link.borrow_mut().parent = Some(Rc::downgrade(&link));
+                link.borrow_mut().left = Some(left_link.clone());
+                stack.push((left, left_link));
+            }
+            if let Some(ref right) = original.right {
+                let right_link: BstNodeLink = BstNode::new_bst_nodelink(right.key);
+                right_link.borrow_mut().parent = Some(Rc::downgrade(&link));
+                link.borrow_mut().right = Some(right_link.clone());
+                stack.push((right, right_link));
+            }
+        }
+        root
+    }
+}
+
+/// One immutable version of a persistent BST, cloning it is O(1).
+#[derive(Debug, Clone, Default)]
+pub struct PersistentBst {
+    root: Option<PersistentNodeLink>,
+    len: usize,
+}
+
+impl PersistentBst {
+    pub fn new() -> Self {
+        PersistentBst::default()
+    }
+
+    pub fn root(&self) -> Option<PersistentNodeLink> {
+        self.root.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Return a new version holding key, self stays valid and unchanged
+     */
+    pub fn insert(&self, key: i32) -> PersistentBst {
+        if self.contains(key) {
+            return self.clone();
+        }
+        PersistentBst {
+            root: PersistentNode::insert(&self.root, key),
+            len: self.len + 1,
+        }
+    }
+
+    /**
+     * Return a new version without key, self stays valid and unchanged
+     */
+    pub fn remove(&self, key: i32) -> PersistentBst {
+        if !self.contains(key) {
+            return self.clone();
+        }
+        PersistentBst {
+            root: PersistentNode::remove(&self.root, key),
+            len: self.len - 1,
+        }
+    }
+
+    pub fn contains(&self, key: i32) -> bool {
+        PersistentNode::search(&self.root, key).is_some()
+    }
+
+    pub fn minimum(&self) -> Option<i32> {
+        self.root.as_ref().map(|x| PersistentNode::minimum(x).key)
+    }
+
+    pub fn maximum(&self) -> Option<i32> {
+        self.root.as_ref().map(|x| PersistentNode::maximum(x).key)
+    }
+
+    pub fn keys(&self) -> Vec<i32> {
+        let mut keys: Vec<i32> = Vec::with_capacity(self.len);
+        PersistentNode::collect_keys(&self.root, &mut keys);
+        keys
+    }
+
+    /**
+     * Copy this version into a regular BstNode tree, e.g. to feed the dot file generators
+     */
+    pub fn to_bst_nodelink(&self) -> Option<BstNodeLink> {
+        self.root.as_ref().map(PersistentNode::to_bst_nodelink)
+    }
+}
+
+/// Linear history of persistent BST versions, version 0 is the empty tree.
+/// Every mutation appends a new version, so any past version can still be queried.
+#[derive(Debug, Clone)]
+pub struct VersionHistory {
+    versions: Vec<PersistentBst>,
+}
+
+impl Default for VersionHistory {
+    fn default() -> Self {
+        VersionHistory {
+            versions: vec![PersistentBst::new()],
+        }
+    }
+}
+
+impl VersionHistory {
+    pub fn new() -> Self {
+        VersionHistory::default()
+    }
+
+    /**
+     * Number of the latest version
+     */
+    pub fn current_version(&self) -> usize {
+        self.versions.len() - 1
+    }
+
+    pub fn current(&self) -> &PersistentBst {
+        &self.versions[self.current_version()]
+    }
+
+    pub fn version(&self, version: usize) -> Option<&PersistentBst> {
+        self.versions.get(version)
+    }
+
+    /**
+     * Insert key on top of the latest version and return the number of the new version
+     */
+    pub fn insert(&mut self, key: i32) -> usize {
+        let next: PersistentBst = self.current().insert(key);
+        self.versions.push(next);
+        self.current_version()
+    }
+
+    /**
+     * Remove key from the latest version and return the number of the new version
+     */
+    pub fn remove(&mut self, key: i32) -> usize {
+        let next: PersistentBst = self.current().remove(key);
+        self.versions.push(next);
+        self.current_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(keys: &[i32]) -> PersistentBst {
+        keys.iter()
+            .fold(PersistentBst::new(), |tree, key| tree.insert(*key))
+    }
+
+    #[test]
+    fn old_versions_are_unchanged() {
+        let first: PersistentBst = build(&[5, 3, 8]);
+        let second: PersistentBst = first.insert(4);
+        let third: PersistentBst = second.remove(5);
+        assert_eq!(first.keys(), vec![3, 5, 8]);
+        assert_eq!(second.keys(), vec![3, 4, 5, 8]);
+        assert_eq!(third.keys(), vec![3, 4, 8]);
+        assert_eq!((first.len(), second.len(), third.len()), (3, 4, 3));
+        assert!(!third.contains(5));
+        assert!(second.contains(5));
+    }
+
+    #[test]
+    fn only_the_search_path_is_copied() {
+        let first: PersistentBst = build(&[5, 3, 8, 7, 9]);
+        let second: PersistentBst = first.insert(2);
+        let (old_root, new_root) = (first.root().unwrap(), second.root().unwrap());
+        assert!(!Rc::ptr_eq(&old_root, &new_root));
+        assert!(Rc::ptr_eq(
+            old_root.right.as_ref().unwrap(),
+            new_root.right.as_ref().unwrap()
+        ));
+        assert!(!Rc::ptr_eq(
+            old_root.left.as_ref().unwrap(),
+            new_root.left.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn present_inserts_and_absent_removes_share_the_root() {
+        let tree: PersistentBst = build(&[2, 1, 3]);
+        let root: PersistentNodeLink = tree.root().unwrap();
+        assert!(Rc::ptr_eq(&tree.insert(3).root().unwrap(), &root));
+        assert!(Rc::ptr_eq(&tree.remove(7).root().unwrap(), &root));
+        assert_eq!(tree.insert(3).len(), 3);
+        assert_eq!(tree.remove(7).len(), 3);
+    }
+
+    #[test]
+    fn remove_every_shape() {
+        let tree: PersistentBst = build(&[50, 30, 70, 20, 40, 60, 80, 65]);
+        //a leaf, a node with one child and the root with two
+        for key in [20, 60, 50] {
+            let removed: PersistentBst = tree.remove(key);
+            let mut expected: Vec<i32> = tree.keys();
+            expected.retain(|x| *x != key);
+            assert_eq!(removed.keys(), expected);
+            let copy: BstNodeLink = removed.to_bst_nodelink().unwrap();
+            assert_eq!(BstNode::inorder_keys(&copy), expected);
+        }
+        assert!(build(&[1]).remove(1).is_empty());
+        assert!(build(&[1]).remove(1).root().is_none());
+    }
+
+    #[test]
+    fn minimum_maximum_and_the_bst_copy() {
+        let tree: PersistentBst = build(&[10, 4, 12, 1]);
+        assert_eq!(tree.minimum(), Some(1));
+        assert_eq!(tree.maximum(), Some(12));
+        assert_eq!(PersistentBst::new().minimum(), None);
+        let copy: BstNodeLink = tree.to_bst_nodelink().unwrap();
+        assert_eq!(BstNode::inorder_keys(&copy), vec![1, 4, 10, 12]);
+        let four: BstNodeLink = copy.borrow().left.clone().unwrap();
+        assert_eq!(four.borrow().key, Some(4));
+        let parent: BstNodeLink = four.borrow().parent.as_ref().unwrap().upgrade().unwrap();
+        assert!(Rc::ptr_eq(&parent, &copy));
+        assert!(PersistentBst::new().to_bst_nodelink().is_none());
+    }
+
+    #[test]
+    fn copying_a_long_path_fits_a_small_stack() {
+        let depth: i32 = 200_000;
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                //a right chain 0, 1, .., depth - 1, built bottom-up
+                let chain: Option<PersistentNodeLink> =
+                    (0..depth).rev().fold(None, |right, key| {
+                        Some(PersistentNode::new_link(key, None, right))
+                    });
+                let copy: BstNodeLink = PersistentNode::to_bst_nodelink(chain.as_ref().unwrap());
+                assert!(BstNode::validate(&copy).is_ok());
+                assert_eq!(BstNode::rightmost(&copy).borrow().key, Some(depth - 1));
+                //the copy is a plain BstNode tree, take it apart before its recursive drop runs
+                let mut stack: Vec<BstNodeLink> = vec![copy];
+                while let Some(node) = stack.pop() {
+                    stack.extend(node.borrow_mut().right.take());
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn version_history_keeps_every_version() {
+        let mut history: VersionHistory = VersionHistory::new();
+        assert_eq!(history.current_version(), 0);
+        assert_eq!(history.insert(3), 1);
+        assert_eq!(history.insert(1), 2);
+        assert_eq!(history.remove(3), 3);
+        assert!(history.version(0).unwrap().is_empty());
+        assert_eq!(history.version(2).unwrap().keys(), vec![1, 3]);
+        assert_eq!(history.current().keys(), vec![1]);
+        assert!(history.version(4).is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::error::BstResult;
+use crate::structure::parenthesized::{self, ParsedNode};
+use std::rc::Rc;
+
+//this package implement a dependency free text format for BstNode trees, built with the serialization feature.
+//A node is written as key(left,right) in preorder, a missing child is left empty and a leaf is just its key,
+//e.g. 15(6(3(2,4),7(,13(9,))),18(17,20)). NIL keys cannot be written, the empty string is the empty tree.
+
+/**
+ * Write the tree under root in the parenthesized preorder format, NIL keys are skipped with their subtree
+ */
+pub fn to_string(root: &BstNodeLink) -> String {
+    parenthesized::write(root)
+}
+
+/// Reads a tree written by `to_string`, whitespace is allowed between tokens.
+///
+/// # Returns
+///
+/// * `Ok(None)` for an empty or blank text.
+/// * `Ok(Some(root))` with every parent pointer set.
+///
+/// # Errors
+///
+/// * `BstError::InvalidInput` if the text is not in the format.
+/// * `BstError::InvariantViolation` if the keys are not in BST order.
+pub fn from_str(text: &str) -> BstResult<Option<BstNodeLink>> {
+    let parsed: Vec<ParsedNode> = parenthesized::parse(text)?;
+    let links: Vec<BstNodeLink> = parsed
+        .iter()
+        .map(|x| BstNode::new_bst_nodelink(x.key))
+        .collect();
+    for (link, x) in links.iter().zip(parsed.iter()) {
+        for child in [x.left, x.right].into_iter().flatten() {
+            links[child].borrow_mut().parent = Some(Rc::downgrade(link));
+        }
+        link.borrow_mut().left = x.left.map(|i| links[i].clone());
+        link.borrow_mut().right = x.right.map(|i| links[i].clone());
+    }
+    let root: Option<BstNodeLink> = links.first().cloned();
+    if let Some(ref x) = root {
+        BstNode::validate(x)?;
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::error::BstError;
+
+    #[test]
+    fn round_trip() {
+        let text: &str = "15(6(3(2,4),7(,13(9,))),18(17,20))";
+        let root: BstNodeLink = from_str(text).unwrap().unwrap();
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(
+            BstNode::inorder_keys(&root),
+            vec![2, 3, 4, 6, 7, 9, 13, 15, 17, 18, 20]
+        );
+        assert_eq!(to_string(&root), text);
+    }
+
+    #[test]
+    fn empty_single_and_negative() {
+        assert!(from_str("").unwrap().is_none());
+        assert!(from_str("  ").unwrap().is_none());
+        assert_eq!(to_string(&from_str("7").unwrap().unwrap()), "7");
+        let root: BstNodeLink = from_str(" 0 ( -5 , 5 ) ").unwrap().unwrap();
+        assert_eq!(to_string(&root), "0(-5,5)");
+    }
+
+    #[test]
+    fn malformed_text_is_rejected() {
+        for text in [
+            "1(",
+            "1(2",
+            "1(,)",
+            "(1,2)",
+            "1(0,2)3",
+            "x",
+            "99999999999",
+            "-",
+            "1 2",
+            "15(6 7,)",
+        ] {
+            assert!(
+                matches!(from_str(text), Err(BstError::InvalidInput(_))),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn unordered_keys_are_rejected() {
+        assert!(matches!(
+            from_str("5(7,3)"),
+            Err(BstError::InvariantViolation(_))
+        ));
+    }
+}
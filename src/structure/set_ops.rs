@@ -0,0 +1,244 @@
+use crate::structure::bst::{BstNode, BstNodeLink, InorderKeys};
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+//this package treats a BST as an ordered set of keys
+//every operation walks both trees in order once, so all of them run in linear time
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperation {
+    Union,
+    Intersection,
+    Difference,
+    SymmetricDifference,
+}
+
+/// Lazy iterator yielding the keys of a set operation between two trees in ascending order.
+pub struct SetOpKeys {
+    first: Peekable<InorderKeys>,
+    second: Peekable<InorderKeys>,
+    operation: SetOperation,
+}
+
+impl SetOpKeys {
+    pub fn new(first: &BstNodeLink, second: &BstNodeLink, operation: SetOperation) -> Self {
+        SetOpKeys {
+            first: BstNode::keys(first).peekable(),
+            second: BstNode::keys(second).peekable(),
+            operation,
+        }
+    }
+}
+
+impl Iterator for SetOpKeys {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        loop {
+            let ordering: Ordering = match (self.first.peek(), self.second.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(a), Some(b)) => a.cmp(b),
+            };
+            match ordering {
+                //key only lives in the first tree
+                Ordering::Less => {
+                    let key: Option<i32> = self.first.next();
+                    if self.operation != SetOperation::Intersection {
+                        return key;
+                    }
+                    //nothing left on the other side can match anymore
+                    self.second.peek()?;
+                }
+                //key only lives in the second tree
+                Ordering::Greater => {
+                    let key: Option<i32> = self.second.next();
+                    match self.operation {
+                        SetOperation::Union | SetOperation::SymmetricDifference => return key,
+                        SetOperation::Intersection | SetOperation::Difference => {
+                            self.first.peek()?;
+                        }
+                    }
+                }
+                //key lives in both trees
+                Ordering::Equal => {
+                    let key: Option<i32> = self.first.next();
+                    self.second.next();
+                    match self.operation {
+                        SetOperation::Union | SetOperation::Intersection => return key,
+                        SetOperation::Difference | SetOperation::SymmetricDifference => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Keys present in either tree, lazily
+ */
+pub fn union_keys(first: &BstNodeLink, second: &BstNodeLink) -> SetOpKeys {
+    SetOpKeys::new(first, second, SetOperation::Union)
+}
+
+/**
+ * Keys present in both trees, lazily
+ */
+pub fn intersection_keys(first: &BstNodeLink, second: &BstNodeLink) -> SetOpKeys {
+    SetOpKeys::new(first, second, SetOperation::Intersection)
+}
+
+/**
+ * Keys present in the first tree but not in the second, lazily
+ */
+pub fn difference_keys(first: &BstNodeLink, second: &BstNodeLink) -> SetOpKeys {
+    SetOpKeys::new(first, second, SetOperation::Difference)
+}
+
+/**
+ * Keys present in exactly one of the trees, lazily
+ */
+pub fn symmetric_difference_keys(first: &BstNodeLink, second: &BstNodeLink) -> SetOpKeys {
+    SetOpKeys::new(first, second, SetOperation::SymmetricDifference)
+}
+
+/// Builds a new tree holding the union of both trees, the inputs are left untouched.
+///
+/// # Returns
+///
+/// * `Some(BstNodeLink)` with the root of a new height balanced tree.
+/// * `None` if neither tree holds a key.
+pub fn union(first: &BstNodeLink, second: &BstNodeLink) -> Option<BstNodeLink> {
+    BstNode::union(first, second)
+}
+
+/// Builds a new tree holding the keys present in both trees.
+///
+/// # Returns
+///
+/// * `Some(BstNodeLink)` with the root of a new height balanced tree.
+/// * `None` if the trees share no key.
+pub fn intersection(first: &BstNodeLink, second: &BstNodeLink) -> Option<BstNodeLink> {
+    build_from_operation(first, second, SetOperation::Intersection)
+}
+
+/// Builds a new tree holding the keys of `first` that are not in `second`.
+///
+/// # Returns
+///
+/// * `Some(BstNodeLink)` with the root of a new height balanced tree.
+/// * `None` if every key of `first` is also in `second`.
+pub fn difference(first: &BstNodeLink, second: &BstNodeLink) -> Option<BstNodeLink> {
+    build_from_operation(first, second, SetOperation::Difference)
+}
+
+/// Builds a new tree holding the keys present in exactly one of the trees.
+///
+/// # Returns
+///
+/// * `Some(BstNodeLink)` with the root of a new height balanced tree.
+/// * `None` if both trees hold the same keys.
+pub fn symmetric_difference(first: &BstNodeLink, second: &BstNodeLink) -> Option<BstNodeLink> {
+    build_from_operation(first, second, SetOperation::SymmetricDifference)
+}
+
+/**
+ * Return true if every key of the first tree is also in the second tree
+ */
+pub fn is_subset(first: &BstNodeLink, second: &BstNodeLink) -> bool {
+    difference_keys(first, second).next().is_none()
+}
+
+/**
+ * Return true if the trees share no key
+ */
+pub fn is_disjoint(first: &BstNodeLink, second: &BstNodeLink) -> bool {
+    intersection_keys(first, second).next().is_none()
+}
+
+fn build_from_operation(
+    first: &BstNodeLink,
+    second: &BstNodeLink,
+    operation: SetOperation,
+) -> Option<BstNodeLink> {
+    let keys: Vec<i32> = SetOpKeys::new(first, second, operation).collect();
+    BstNode::build_from_sorted_keys(&keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(keys: &[i32]) -> BstNodeLink {
+        BstNode::build_from_sorted_keys(keys).unwrap()
+    }
+
+    #[test]
+    fn lazy_keys_per_operation() {
+        let first: BstNodeLink = tree(&[1, 3, 5, 7, 9]);
+        let second: BstNodeLink = tree(&[3, 4, 5, 10]);
+        assert_eq!(
+            union_keys(&first, &second).collect::<Vec<i32>>(),
+            vec![1, 3, 4, 5, 7, 9, 10]
+        );
+        assert_eq!(
+            intersection_keys(&first, &second).collect::<Vec<i32>>(),
+            vec![3, 5]
+        );
+        assert_eq!(
+            difference_keys(&first, &second).collect::<Vec<i32>>(),
+            vec![1, 7, 9]
+        );
+        assert_eq!(
+            difference_keys(&second, &first).collect::<Vec<i32>>(),
+            vec![4, 10]
+        );
+        assert_eq!(
+            symmetric_difference_keys(&first, &second).collect::<Vec<i32>>(),
+            vec![1, 4, 7, 9, 10]
+        );
+    }
+
+    #[test]
+    fn built_trees_are_new_roots_and_leave_the_inputs_alone() {
+        let first: BstNodeLink = tree(&[1, 2, 3, 4]);
+        let second: BstNodeLink = tree(&[3, 4, 5]);
+        let built: Vec<BstNodeLink> = vec![
+            union(&first, &second).unwrap(),
+            intersection(&first, &second).unwrap(),
+            difference(&first, &second).unwrap(),
+            symmetric_difference(&first, &second).unwrap(),
+        ];
+        for root in &built {
+            assert!(root.borrow().parent.is_none());
+        }
+        assert_eq!(BstNode::inorder_keys(&built[0]), vec![1, 2, 3, 4, 5]);
+        assert_eq!(BstNode::inorder_keys(&built[1]), vec![3, 4]);
+        assert_eq!(BstNode::inorder_keys(&built[2]), vec![1, 2]);
+        assert_eq!(BstNode::inorder_keys(&built[3]), vec![1, 2, 5]);
+        assert_eq!(BstNode::inorder_keys(&first), vec![1, 2, 3, 4]);
+        assert_eq!(BstNode::inorder_keys(&second), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn empty_results_are_none() {
+        let first: BstNodeLink = tree(&[1, 2]);
+        let second: BstNodeLink = tree(&[5, 6]);
+        assert!(intersection(&first, &second).is_none());
+        assert!(difference(&first, &first).is_none());
+        assert!(symmetric_difference(&second, &second).is_none());
+    }
+
+    #[test]
+    fn subset_and_disjoint() {
+        let small: BstNodeLink = tree(&[2, 4]);
+        let large: BstNodeLink = tree(&[1, 2, 3, 4]);
+        let other: BstNodeLink = tree(&[5, 8]);
+        assert!(is_subset(&small, &large));
+        assert!(is_subset(&small, &small));
+        assert!(!is_subset(&large, &small));
+        assert!(is_disjoint(&large, &other));
+        assert!(!is_disjoint(&small, &large));
+    }
+}
@@ -0,0 +1,364 @@
+use crate::structure::augment::{self, AugmentedBst, SubtreeSize};
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::error::{BstError, BstResult};
+use crate::structure::observer::RotateDirection;
+use std::rc::Rc;
+
+//this package implement a weight-balanced BB[alpha] tree on top of AugmentedBst<SubtreeSize>.
+//The weight of a node is its subtree size plus one, a node is balanced while each child holds
+//at least alpha of its weight. Insert and delete walk back up and restore the balance with a single
+//or double rotation per node, the subtree sizes double as order statistics for select and rank.
+
+/// Alpha used by `WeightBalancedBst::new`.
+pub const DEFAULT_ALPHA: f64 = 0.25;
+
+//one rotation per node restores the balance only for alpha in (2/11, 1 - 1/sqrt(2)]
+const MIN_ALPHA: f64 = 2.0 / 11.0;
+const MAX_ALPHA: f64 = 1.0 - std::f64::consts::FRAC_1_SQRT_2;
+
+/// `BB[alpha]` tree of unique keys made of plain `BstNode`s.
+pub struct WeightBalancedBst {
+    tree: AugmentedBst<SubtreeSize>,
+    alpha: f64,
+}
+
+impl Default for WeightBalancedBst {
+    fn default() -> Self {
+        WeightBalancedBst {
+            tree: AugmentedBst::new(),
+            alpha: DEFAULT_ALPHA,
+        }
+    }
+}
+
+impl WeightBalancedBst {
+    pub fn new() -> Self {
+        WeightBalancedBst::default()
+    }
+
+    /// Creates an empty tree with the given balance parameter.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvalidInput` unless `2/11 < alpha <= 1 - 1/sqrt(2)`, outside of
+    ///   that range single and double rotations cannot always restore the balance.
+    pub fn with_alpha(alpha: f64) -> BstResult<Self> {
+        if !(alpha > MIN_ALPHA && alpha <= MAX_ALPHA) {
+            return Err(BstError::InvalidInput(format!(
+                "alpha {} is outside of (2/11, 1 - 1/sqrt(2)]",
+                alpha
+            )));
+        }
+        Ok(WeightBalancedBst {
+            tree: AugmentedBst::new(),
+            alpha,
+        })
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /**
+     * Root of the underlying BstNode tree, it can be handed to the BstNode functions and dot writers
+     * Changing the tree through it bypasses the balancing and the subtree sizes
+     */
+    pub fn root(&self) -> Option<BstNodeLink> {
+        self.tree.root()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.root_value().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.root().is_none()
+    }
+
+    /**
+     * Amount of nodes in the subtree of node, 0 for a missing node
+     */
+    pub fn size_of(&self, node: &Option<BstNodeLink>) -> usize {
+        node.as_ref().and_then(|x| self.tree.value(x)).unwrap_or(0)
+    }
+
+    pub fn contains(&self, key: i32) -> bool {
+        self.root()
+            .and_then(|x| BstNode::tree_search_link(&x, &key))
+            .is_some()
+    }
+
+    /**
+     * Keys in ascending order
+     */
+    pub fn keys(&self) -> Vec<i32> {
+        match self.root() {
+            Some(ref root) => BstNode::inorder_keys(root),
+            None => Vec::new(),
+        }
+    }
+
+    /**
+     * Insert a key and rebalance its path, return false if the key already exists
+     */
+    pub fn insert(&mut self, key: i32) -> bool {
+        let inserted: Option<BstNodeLink> = self.tree.tree_insert(&key);
+        match inserted {
+            Some(x) => {
+                self.rebalance_upward(BstNode::get_parent(&x));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /**
+     * Delete a key and rebalance the path above the lowest changed node, return false if the key is missing
+     */
+    pub fn delete(&mut self, key: i32) -> bool {
+        let node: Option<BstNodeLink> = self
+            .root()
+            .and_then(|x| BstNode::tree_search_link(&x, &key));
+        let z: BstNodeLink = match node {
+            Some(x) => x,
+            None => return false,
+        };
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&z);
+        let lowest: Option<BstNodeLink> = augment::lowest_changed_by_delete(&z, &replacement);
+        if !self.tree.tree_delete(&z) {
+            return false;
+        }
+        self.rebalance_upward(lowest);
+        true
+    }
+
+    /// Finds the key at a position of the ascending order.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position counted from 0, `select(0)` is the minimum.
+    ///
+    /// # Returns
+    ///
+    /// The key, or `None` if `index` is not below `len()`.
+    pub fn select(&self, index: usize) -> Option<i32> {
+        let mut remaining: usize = index;
+        let mut current: Option<BstNodeLink> = self.root();
+        while let Some(x) = current {
+            let left: Option<BstNodeLink> = x.borrow().left.clone();
+            let left_size: usize = self.size_of(&left);
+            if remaining < left_size {
+                current = left;
+            } else if remaining == left_size {
+                return x.borrow().key;
+            } else {
+                remaining -= left_size + 1;
+                current = x.borrow().right.clone();
+            }
+        }
+        None
+    }
+
+    /**
+     * Amount of keys smaller than key, key itself does not have to be in the tree
+     */
+    pub fn rank(&self, key: i32) -> usize {
+        let mut rank: usize = 0;
+        let mut current: Option<BstNodeLink> = self.root();
+        while let Some(x) = current {
+            if x.borrow().key < Some(key) {
+                rank += self.size_of(&x.borrow().left) + 1;
+                current = x.borrow().right.clone();
+            } else {
+                current = x.borrow().left.clone();
+            }
+        }
+        rank
+    }
+
+    /// Checks the BST order, the parent pointers, the subtree sizes and the balance of every node.
+    ///
+    /// # Errors
+    ///
+    /// * `BstError::InvariantViolation` naming the first broken rule.
+    /// * `BstError::DanglingParent` if a parent pointer cannot be upgraded.
+    pub fn validate(&self) -> BstResult<()> {
+        let root: BstNodeLink = match self.root() {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        BstNode::validate(&root)?;
+        let mut stack: Vec<BstNodeLink> = vec![root];
+        while let Some(x) = stack.pop() {
+            let left: Option<BstNodeLink> = x.borrow().left.clone();
+            let right: Option<BstNodeLink> = x.borrow().right.clone();
+            let size: usize = self.size_of(&Some(x.clone()));
+            if size != self.size_of(&left) + 1 + self.size_of(&right) {
+                return Err(BstError::InvariantViolation(format!(
+                    "subtree size {} of {:?} does not add up",
+                    size,
+                    x.borrow().key
+                )));
+            }
+            if !self.is_balanced(&x) {
+                return Err(BstError::InvariantViolation(format!(
+                    "{:?} is out of BB[{}] balance",
+                    x.borrow().key,
+                    self.alpha
+                )));
+            }
+            stack.extend(left);
+            stack.extend(right);
+        }
+        Ok(())
+    }
+
+    //size plus one, so an empty subtree still weighs something
+    fn weight(&self, node: &Option<BstNodeLink>) -> f64 {
+        (self.size_of(node) + 1) as f64
+    }
+
+    //share of the weight of node held by its left subtree
+    fn left_share(&self, node: &BstNodeLink) -> f64 {
+        self.weight(&node.borrow().left) / self.weight(&Some(node.clone()))
+    }
+
+    fn is_balanced(&self, node: &BstNodeLink) -> bool {
+        let share: f64 = self.left_share(node);
+        share >= self.alpha && share <= 1.0 - self.alpha
+    }
+
+    //check every node from node up to the root, the sizes on that path must be up to date
+    fn rebalance_upward(&mut self, node: Option<BstNodeLink>) {
+        let mut current: Option<BstNodeLink> = node;
+        while let Some(x) = current {
+            let top: BstNodeLink = self.rebalance(&x);
+            current = BstNode::get_parent(&top);
+        }
+    }
+
+    //restore the balance of node, return the node standing in its place afterwards
+    fn rebalance(&mut self, node: &BstNodeLink) -> BstNodeLink {
+        //rotations of a lighter child keep the balance of the heavier one when share <= threshold
+        let threshold: f64 = 1.0 / (2.0 - self.alpha);
+        let share: f64 = self.left_share(node);
+        if share < self.alpha {
+            //right heavy, a right child whose left side is too heavy needs a double rotation
+            let right: BstNodeLink = node.borrow().right.clone().unwrap();
+            if self.left_share(&right) > threshold {
+                self.rotate(&right, RotateDirection::Right);
+            }
+            self.rotate(node, RotateDirection::Left)
+        } else if share > 1.0 - self.alpha {
+            let left: BstNodeLink = node.borrow().left.clone().unwrap();
+            if self.left_share(&left) < 1.0 - threshold {
+                self.rotate(&left, RotateDirection::Left);
+            }
+            self.rotate(node, RotateDirection::Right)
+        } else {
+            node.clone()
+        }
+    }
+
+    //rotate pivot down and refresh both sizes that changed, the root handle follows
+    fn rotate(&mut self, pivot: &BstNodeLink, direction: RotateDirection) -> BstNodeLink {
+        let was_root: bool = self.root().is_some_and(|x| Rc::ptr_eq(&x, pivot));
+        let rising: BstNodeLink = match direction {
+            RotateDirection::Left => BstNode::rotate_left(pivot),
+            RotateDirection::Right => BstNode::rotate_right(pivot),
+        }
+        .expect("an unbalanced node has a child on its heavy side");
+        self.tree.refresh(pivot);
+        self.tree.refresh(&rising);
+        if was_root {
+            self.tree.set_root(Some(rising.clone()));
+        }
+        rising
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::rng::XorShift64;
+
+    fn height(node: &Option<BstNodeLink>) -> usize {
+        match node {
+            Some(x) => 1 + height(&x.borrow().left).max(height(&x.borrow().right)),
+            None => 0,
+        }
+    }
+
+    #[test]
+    fn alpha_range() {
+        assert_eq!(WeightBalancedBst::new().alpha(), DEFAULT_ALPHA);
+        assert!(WeightBalancedBst::with_alpha(0.2).is_ok());
+        assert!(WeightBalancedBst::with_alpha(MAX_ALPHA).is_ok());
+        for alpha in [0.1, 2.0 / 11.0, 0.3, f64::NAN] {
+            assert!(matches!(
+                WeightBalancedBst::with_alpha(alpha),
+                Err(BstError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn sorted_inserts_stay_logarithmic() {
+        let mut tree: WeightBalancedBst = WeightBalancedBst::new();
+        for key in 0..1024 {
+            assert!(tree.insert(key));
+        }
+        assert!(!tree.insert(10));
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.len(), 1024);
+        //a BB[1/4] tree is at most log base 4/3 of its weight high
+        assert!(height(&tree.root()) <= 25, "{}", height(&tree.root()));
+        assert!(BstNode::get_parent(&tree.root().unwrap()).is_none());
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_invariants() {
+        let mut rng: XorShift64 = XorShift64::new(7);
+        for alpha in [0.2, DEFAULT_ALPHA, MAX_ALPHA] {
+            let mut tree: WeightBalancedBst = WeightBalancedBst::with_alpha(alpha).unwrap();
+            let mut model: std::collections::BTreeSet<i32> = std::collections::BTreeSet::new();
+            for _ in 0..1500 {
+                let key: i32 = rng.below(200) as i32;
+                if rng.below(3) == 0 {
+                    assert_eq!(tree.delete(key), model.remove(&key));
+                } else {
+                    assert_eq!(tree.insert(key), model.insert(key));
+                }
+                tree.validate().unwrap();
+            }
+            assert_eq!(tree.keys(), model.iter().copied().collect::<Vec<i32>>());
+            for key in model.clone() {
+                assert!(tree.delete(key));
+            }
+            assert!(tree.is_empty());
+            assert_eq!(tree.len(), 0);
+        }
+    }
+
+    #[test]
+    fn select_and_rank() {
+        let mut tree: WeightBalancedBst = WeightBalancedBst::new();
+        for key in [50, 20, 80, 10, 30, 70, 90, 60] {
+            tree.insert(key);
+        }
+        let sorted: Vec<i32> = tree.keys();
+        for (index, key) in sorted.iter().enumerate() {
+            assert_eq!(tree.select(index), Some(*key));
+            assert_eq!(tree.rank(*key), index);
+        }
+        assert_eq!(tree.select(sorted.len()), None);
+        assert_eq!(tree.rank(0), 0);
+        assert_eq!(tree.rank(55), 4);
+        assert_eq!(tree.rank(100), 8);
+        tree.delete(20);
+        assert_eq!(tree.select(1), Some(30));
+        assert_eq!(tree.rank(55), 3);
+        assert!(tree.contains(30) && !tree.contains(20));
+        assert_eq!(WeightBalancedBst::new().select(0), None);
+    }
+}
@@ -0,0 +1,589 @@
+use crate::structure::augment::{AugmentedBst, SubtreeSize};
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::metrics;
+use crate::structure::multiset::{BstMultiset, DuplicateMode};
+use crate::structure::observer;
+use crate::structure::persistent::PersistentBst;
+use crate::structure::weight_balanced::WeightBalancedBst;
+use crate::tool::rng::XorShift64;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hint::black_box;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+//this package implement an offline benchmark harness for the tree structures.
+//Every subject runs insert, search, delete and iterate over the same key stream,
+//std BTreeMap runs alongside as the reference. Wall clock time per phase is reported as a table or CSV.
+
+/// Order in which the keys of a workload are fed to the trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStream {
+    Random,
+    Sorted,
+    ReverseSorted,
+    /// Skewed draws with repeats, a few keys are hit far more often than the rest.
+    Zipfian,
+}
+
+impl KeyStream {
+    pub fn all() -> [KeyStream; 4] {
+        [
+            KeyStream::Random,
+            KeyStream::Sorted,
+            KeyStream::ReverseSorted,
+            KeyStream::Zipfian,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyStream::Random => "random",
+            KeyStream::Sorted => "sorted",
+            KeyStream::ReverseSorted => "reverse",
+            KeyStream::Zipfian => "zipfian",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<KeyStream> {
+        KeyStream::all().into_iter().find(|x| x.name() == name)
+    }
+
+    /**
+     * count keys in this stream's order, the same seed always yields the same keys
+     */
+    pub fn generate(&self, count: usize, seed: u64) -> Vec<i32> {
+        let mut rng: XorShift64 = XorShift64::new(seed);
+        let mut keys: Vec<i32> = (0..count as i32).collect();
+        match self {
+            KeyStream::Random => rng.shuffle(&mut keys),
+            KeyStream::Sorted => {}
+            KeyStream::ReverseSorted => keys.reverse(),
+            KeyStream::Zipfian => {
+                //rank r is drawn with a weight of 1/r, the ranks are spread over the key space
+                let mut ranked: Vec<i32> = keys.clone();
+                rng.shuffle(&mut ranked);
+                let mut cumulative: Vec<f64> = Vec::with_capacity(count);
+                let mut total: f64 = 0.0;
+                for rank in 1..=count {
+                    total += 1.0 / rank as f64;
+                    cumulative.push(total);
+                }
+                for key in keys.iter_mut() {
+                    let target: f64 = rng.next_f64() * total;
+                    let rank: usize = cumulative.partition_point(|x| *x < target);
+                    *key = ranked[rank.min(count - 1)];
+                }
+            }
+        }
+        keys
+    }
+}
+
+/// A tree structure driven by the harness, duplicate inserts and missing deletes must be no-ops.
+pub trait BenchSubject {
+    fn name(&self) -> &'static str;
+    fn insert(&mut self, key: i32);
+    fn search(&self, key: i32) -> bool;
+    fn delete(&mut self, key: i32);
+    /**
+     * Visit every key in order, return the amount of visited keys
+     */
+    fn iterate(&self) -> usize;
+}
+
+/// Plain `BstNode` tree, the root handle is swapped when the root itself is deleted.
+#[derive(Default)]
+pub struct BstNodeSubject {
+    root: Option<BstNodeLink>,
+}
+
+impl BenchSubject for BstNodeSubject {
+    fn name(&self) -> &'static str {
+        "BstNode"
+    }
+
+    fn insert(&mut self, key: i32) {
+        match self.root {
+            Some(ref root) => {
+                BstNode::tree_insert(root, &key);
+            }
+            None => self.root = Some(BstNode::new_bst_nodelink(key)),
+        }
+    }
+
+    fn search(&self, key: i32) -> bool {
+        self.root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, &key))
+            .is_some()
+    }
+
+    fn delete(&mut self, key: i32) {
+        let node: BstNodeLink = match self
+            .root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, &key))
+        {
+            Some(x) => x,
+            None => return,
+        };
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&node);
+        let is_root: bool = self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, &node));
+        BstNode::tree_delete_node(&node);
+        if is_root {
+            self.root = replacement;
+        }
+    }
+
+    fn iterate(&self) -> usize {
+        match self.root {
+            Some(ref root) => BstNode::keys(root).map(black_box).count(),
+            None => 0,
+        }
+    }
+}
+
+/// `PersistentBst`, every mutation builds the next version and drops the previous one.
+#[derive(Default)]
+pub struct PersistentSubject {
+    tree: PersistentBst,
+}
+
+impl BenchSubject for PersistentSubject {
+    fn name(&self) -> &'static str {
+        "PersistentBst"
+    }
+
+    fn insert(&mut self, key: i32) {
+        self.tree = self.tree.insert(key);
+    }
+
+    fn search(&self, key: i32) -> bool {
+        self.tree.contains(key)
+    }
+
+    fn delete(&mut self, key: i32) {
+        self.tree = self.tree.remove(key);
+    }
+
+    fn iterate(&self) -> usize {
+        self.tree.keys().into_iter().map(black_box).count()
+    }
+}
+
+/// `AugmentedBst` keeping subtree sizes.
+#[derive(Default)]
+pub struct AugmentedSubject {
+    tree: AugmentedBst<SubtreeSize>,
+}
+
+impl BenchSubject for AugmentedSubject {
+    fn name(&self) -> &'static str {
+        "AugmentedBst"
+    }
+
+    fn insert(&mut self, key: i32) {
+        self.tree.tree_insert(&key);
+    }
+
+    fn search(&self, key: i32) -> bool {
+        self.tree
+            .root()
+            .and_then(|x| BstNode::tree_search_link(&x, &key))
+            .is_some()
+    }
+
+    fn delete(&mut self, key: i32) {
+        self.tree.tree_delete_with_key(key);
+    }
+
+    fn iterate(&self) -> usize {
+        match self.tree.root() {
+            Some(ref root) => BstNode::keys(root).map(black_box).count(),
+            None => 0,
+        }
+    }
+}
+
+/// `WeightBalancedBst` with the default alpha, rotating on every imbalance.
+#[derive(Default)]
+pub struct WeightBalancedSubject {
+    tree: WeightBalancedBst,
+}
+
+impl BenchSubject for WeightBalancedSubject {
+    fn name(&self) -> &'static str {
+        "WeightBalancedBst"
+    }
+
+    fn insert(&mut self, key: i32) {
+        self.tree.insert(key);
+    }
+
+    fn search(&self, key: i32) -> bool {
+        self.tree.contains(key)
+    }
+
+    fn delete(&mut self, key: i32) {
+        self.tree.delete(key);
+    }
+
+    fn iterate(&self) -> usize {
+        match self.tree.root() {
+            Some(ref root) => BstNode::keys(root).map(black_box).count(),
+            None => 0,
+        }
+    }
+}
+
+/// `BstMultiset` in counted mode, a repeated key only bumps its count.
+pub struct MultisetSubject {
+    tree: BstMultiset,
+}
+
+impl Default for MultisetSubject {
+    fn default() -> Self {
+        MultisetSubject {
+            tree: BstMultiset::new(DuplicateMode::Counted),
+        }
+    }
+}
+
+impl BenchSubject for MultisetSubject {
+    fn name(&self) -> &'static str {
+        "BstMultiset"
+    }
+
+    fn insert(&mut self, key: i32) {
+        //keep set semantics so every subject ends up holding the same keys
+        if !self.tree.contains(key) {
+            self.tree.insert(key);
+        }
+    }
+
+    fn search(&self, key: i32) -> bool {
+        self.tree.contains(key)
+    }
+
+    fn delete(&mut self, key: i32) {
+        self.tree.remove_all(key);
+    }
+
+    fn iterate(&self) -> usize {
+        self.tree.iter().map(black_box).count()
+    }
+}
+
+/// The std reference every tree is compared against.
+#[derive(Default)]
+pub struct BTreeMapSubject {
+    map: BTreeMap<i32, ()>,
+}
+
+impl BenchSubject for BTreeMapSubject {
+    fn name(&self) -> &'static str {
+        "BTreeMap"
+    }
+
+    fn insert(&mut self, key: i32) {
+        self.map.insert(key, ());
+    }
+
+    fn search(&self, key: i32) -> bool {
+        self.map.contains_key(&key)
+    }
+
+    fn delete(&mut self, key: i32) {
+        self.map.remove(&key);
+    }
+
+    fn iterate(&self) -> usize {
+        self.map.keys().copied().map(black_box).count()
+    }
+}
+
+/**
+ * A fresh, empty instance of every benchmarked structure
+ */
+pub fn default_subjects() -> Vec<Box<dyn BenchSubject>> {
+    vec![
+        Box::new(BstNodeSubject::default()),
+        Box::new(PersistentSubject::default()),
+        Box::new(AugmentedSubject::default()),
+        Box::new(WeightBalancedSubject::default()),
+        Box::new(MultisetSubject::default()),
+        Box::new(BTreeMapSubject::default()),
+    ]
+}
+
+/// Timing of one phase of one structure on one key stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub structure: String,
+    pub stream: KeyStream,
+    pub operation: &'static str,
+    pub operations: usize,
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    pub fn nanos_per_op(&self) -> f64 {
+        if self.operations == 0 {
+            return 0.0;
+        }
+        self.elapsed.as_nanos() as f64 / self.operations as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BenchReport {
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    pub fn to_csv(&self) -> String {
+        let mut csv: String =
+            String::from("structure,stream,operation,operations,total_ns,ns_per_op\n");
+        for result in self.results.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.1}\n",
+                result.structure,
+                result.stream.name(),
+                result.operation,
+                result.operations,
+                result.elapsed.as_nanos(),
+                result.nanos_per_op()
+            ));
+        }
+        csv
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<17} {:<8} {:<8} {:>8} {:>14} {:>12}",
+            "structure", "stream", "op", "ops", "total us", "ns/op"
+        )?;
+        for result in self.results.iter() {
+            writeln!(
+                f,
+                "{:<17} {:<8} {:<8} {:>8} {:>14.1} {:>12.1}",
+                result.structure,
+                result.stream.name(),
+                result.operation,
+                result.operations,
+                result.elapsed.as_nanos() as f64 / 1000.0,
+                result.nanos_per_op()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//phase returns how many operations it ran
+fn time_phase(phase: impl FnOnce() -> usize) -> (usize, Duration) {
+    let start: Instant = Instant::now();
+    let operations: usize = phase();
+    (operations, start.elapsed())
+}
+
+/**
+ * Run insert, search, delete and iterate for every subject made by make_subjects
+ * on every stream, each stream holds count keys generated from seed
+ */
+pub fn run(
+    count: usize,
+    streams: &[KeyStream],
+    seed: u64,
+    make_subjects: impl Fn() -> Vec<Box<dyn BenchSubject>>,
+) -> BenchReport {
+    //BTreeMap is not instrumented, so neither are the trees while they are timed
+    let metrics_enabled: bool = metrics::is_enabled();
+    metrics::set_enabled(false);
+    let report: BenchReport =
+        observer::suspended(|| run_uninstrumented(count, streams, seed, make_subjects));
+    metrics::set_enabled(metrics_enabled);
+    report
+}
+
+fn run_uninstrumented(
+    count: usize,
+    streams: &[KeyStream],
+    seed: u64,
+    make_subjects: impl Fn() -> Vec<Box<dyn BenchSubject>>,
+) -> BenchReport {
+    let mut report: BenchReport = BenchReport::default();
+    for stream in streams.iter() {
+        let keys: Vec<i32> = stream.generate(count, seed);
+        for mut subject in make_subjects() {
+            let mut phases: Vec<(&'static str, (usize, Duration))> = Vec::new();
+            phases.push((
+                "insert",
+                time_phase(|| {
+                    for key in keys.iter() {
+                        subject.insert(black_box(*key));
+                    }
+                    keys.len()
+                }),
+            ));
+            phases.push((
+                "search",
+                time_phase(|| {
+                    for key in keys.iter() {
+                        black_box(subject.search(black_box(*key)));
+                    }
+                    keys.len()
+                }),
+            ));
+            phases.push(("iterate", time_phase(|| subject.iterate())));
+            phases.push((
+                "delete",
+                time_phase(|| {
+                    for key in keys.iter() {
+                        subject.delete(black_box(*key));
+                    }
+                    keys.len()
+                }),
+            ));
+            for (operation, (operations, elapsed)) in phases {
+                report.results.push(BenchResult {
+                    structure: subject.name().to_string(),
+                    stream: *stream,
+                    operation,
+                    operations,
+                    elapsed,
+                });
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::observer::{CollectingObserver, EventFilter};
+
+    fn tree_subjects() -> Vec<Box<dyn BenchSubject>> {
+        vec![
+            Box::new(BstNodeSubject::default()),
+            Box::new(PersistentSubject::default()),
+        ]
+    }
+
+    #[test]
+    fn sorted_streams_fit_a_small_stack() {
+        //a degenerate tree as deep as the stream, nothing may recurse per level
+        let report: BenchReport = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(|| run(1500, &[KeyStream::Sorted], 7, tree_subjects))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(report.results.len(), 8);
+    }
+
+    #[test]
+    fn runs_without_instrumentation() {
+        let collector: Rc<CollectingObserver> = Rc::new(CollectingObserver::new());
+        let id = observer::register(collector.clone(), EventFilter::all());
+        metrics::reset();
+        run(50, &[KeyStream::Random], 7, tree_subjects);
+        observer::unregister(id);
+        assert!(collector.events().is_empty());
+        assert_eq!(metrics::snapshot(), metrics::OpMetrics::default());
+    }
+
+    #[test]
+    fn streams_hold_the_expected_keys() {
+        let count: usize = 200;
+        let every_key: Vec<i32> = (0..count as i32).collect();
+        assert_eq!(KeyStream::Sorted.generate(count, 1), every_key);
+        let reverse: Vec<i32> = every_key.iter().rev().copied().collect();
+        assert_eq!(KeyStream::ReverseSorted.generate(count, 1), reverse);
+        let mut random: Vec<i32> = KeyStream::Random.generate(count, 1);
+        assert_ne!(random, every_key);
+        assert_eq!(random, KeyStream::Random.generate(count, 1));
+        random.sort();
+        assert_eq!(random, every_key);
+        //the zipfian stream repeats its favourite keys
+        let zipfian: Vec<i32> = KeyStream::Zipfian.generate(count, 1);
+        let mut distinct: Vec<i32> = zipfian.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(zipfian.len(), count);
+        assert!(distinct.len() < count / 2, "{}", distinct.len());
+        assert!(zipfian.iter().all(|x| every_key.contains(x)));
+        assert!(KeyStream::Zipfian.generate(0, 1).is_empty());
+    }
+
+    #[test]
+    fn stream_names_round_trip() {
+        for stream in KeyStream::all() {
+            assert_eq!(KeyStream::parse(stream.name()), Some(stream));
+        }
+        assert_eq!(KeyStream::parse("shuffled"), None);
+    }
+
+    #[test]
+    fn every_subject_agrees_with_the_reference() {
+        let keys: Vec<i32> = KeyStream::Zipfian.generate(300, 5);
+        for mut subject in default_subjects() {
+            for key in keys.iter() {
+                subject.insert(*key);
+            }
+            let mut distinct: Vec<i32> = keys.clone();
+            distinct.sort();
+            distinct.dedup();
+            assert_eq!(subject.iterate(), distinct.len(), "{}", subject.name());
+            assert!(subject.search(keys[0]));
+            assert!(!subject.search(-1));
+            for key in keys.iter() {
+                subject.delete(*key);
+            }
+            assert_eq!(subject.iterate(), 0, "{}", subject.name());
+        }
+    }
+
+    #[test]
+    fn csv_and_table_have_a_row_per_result() {
+        let report: BenchReport = run(
+            20,
+            &[KeyStream::Sorted, KeyStream::Random],
+            3,
+            tree_subjects,
+        );
+        //2 streams, 2 subjects, 4 phases
+        assert_eq!(report.results.len(), 16);
+        let csv: String = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 17);
+        assert_eq!(
+            lines[0],
+            "structure,stream,operation,operations,total_ns,ns_per_op"
+        );
+        assert!(lines[1].starts_with("BstNode,sorted,insert,20,"));
+        assert!(lines[3].starts_with("BstNode,sorted,iterate,20,"));
+        assert!(lines.iter().skip(1).all(|x| x.split(',').count() == 6));
+        let table: String = report.to_string();
+        assert_eq!(table.lines().count(), 17);
+        assert!(table.lines().next().unwrap().starts_with("structure"));
+    }
+
+    #[test]
+    fn nanos_per_op_of_an_empty_phase() {
+        let mut result: BenchResult = BenchResult {
+            structure: "BstNode".to_string(),
+            stream: KeyStream::Random,
+            operation: "iterate",
+            operations: 0,
+            elapsed: Duration::from_nanos(50),
+        };
+        assert_eq!(result.nanos_per_op(), 0.0);
+        result.operations = 10;
+        assert_eq!(result.nanos_per_op(), 5.0);
+    }
+}
@@ -0,0 +1,336 @@
+use crate::structure::binary_tree::{BinaryTreeNode, TreeLink};
+use crate::structure::bst::BstNodeLink;
+use crate::structure::compare::{NodeChange, TreeDiff};
+use crate::structure::error::BstResult;
+use crate::structure::weight_balanced::WeightBalancedBst;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+//this package hold the graphviz dot writers, it is only built with the visualization feature.
+//Every writer takes any BinaryTreeNode, so NodeLink and BstNodeLink trees go through the same code.
+
+fn node_id<T>(node: &TreeLink<T>) -> usize {
+    node.as_ptr() as usize
+}
+
+fn write_graphviz_dot_notation<T: BinaryTreeNode, W: Write>(
+    root: &TreeLink<T>,
+    writer: W,
+) -> std::io::Result<()> {
+    write_labeled_dot_notation(Some(root), |x: &TreeLink<T>| x.borrow().label(), writer)
+}
+
+//same graph with the label of every node picked by the caller, an empty tree gives an empty graph
+fn write_labeled_dot_notation<T: BinaryTreeNode, W: Write>(
+    root: Option<&TreeLink<T>>,
+    label: impl Fn(&TreeLink<T>) -> String,
+    mut writer: W,
+) -> std::io::Result<()> {
+    writeln!(writer, "digraph BinaryTree {{")?;
+    let mut queue: VecDeque<TreeLink<T>> = root.into_iter().cloned().collect();
+    while let Some(node_link) = queue.pop_front() {
+        let node: std::cell::Ref<'_, T> = node_link.borrow();
+        let this_id: usize = node_id(&node_link);
+        writeln!(writer, "    {} [label=\"{}\"];", this_id, label(&node_link))?;
+        if let Some(left) = node.left() {
+            let left_id: usize = node_id(&left);
+            writeln!(
+                writer,
+                "    {} -> {} [label=\"left\", style=solid, color=red];",
+                this_id, left_id
+            )?;
+            queue.push_back(left);
+        }
+        if let Some(right) = node.right() {
+            let right_id: usize = node_id(&right);
+            writeln!(
+                writer,
+                "    {} -> {} [label=\"right\", style=solid, color=green];",
+                this_id, right_id
+            )?;
+            queue.push_back(right);
+        }
+        if let Some(parent) = node.parent() {
+            let parent_id: usize = node_id(&parent);
+            writeln!(
+                writer,
+                "    {} -> {} [label=\"parent\", style=solid, color=blue];",
+                this_id, parent_id
+            )?;
+        }
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+pub fn generate_dotfile_bst_better<T: BinaryTreeNode>(root: &TreeLink<T>, output_path: &str) {
+    try_generate_dotfile_bst_better(root, output_path).expect("Unable to write to .dot file");
+}
+
+/**
+ * Same as generate_dotfile_bst_better, but failures are returned instead of panicking
+ */
+pub fn try_generate_dotfile_bst_better<T: BinaryTreeNode>(
+    root: &TreeLink<T>,
+    output_path: &str,
+) -> BstResult<()> {
+    let file: File = File::create(output_path)?;
+    let mut writer: BufWriter<File> = BufWriter::new(file);
+    write_graphviz_dot_notation(root, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn print_graph<T: BinaryTreeNode>(root: &TreeLink<T>) {
+    try_print_graph(root).expect("Unable to print dot graph");
+}
+
+/**
+ * Same as print_graph, but failures are returned instead of panicking
+ */
+pub fn try_print_graph<T: BinaryTreeNode>(root: &TreeLink<T>) -> BstResult<()> {
+    write_graphviz_dot_notation(root, std::io::stdout())?;
+    Ok(())
+}
+
+/**
+ * @root: root node of the tree, a NodeLink or a BstNodeLink
+ * @output_path: write the graphviz structure to output_path
+ * Generate graphviz dot file given a NodeLink, you will traverse from root to all leaves incrementally,
+ * as you proceed wrote the progress to dot file
+ */
+pub fn generate_dotfile<T: BinaryTreeNode>(root: &TreeLink<T>, output_path: &str) {
+    try_generate_dotfile(root, output_path).expect("Failed to create");
+}
+
+/**
+ * Same as generate_dotfile, but failures are returned instead of panicking or being ignored
+ */
+pub fn try_generate_dotfile<T: BinaryTreeNode>(
+    root: &TreeLink<T>,
+    output_path: &str,
+) -> BstResult<()> {
+    let graph_name = " tree";
+    let preamble: String = "graph".to_owned() + graph_name + "{\n";
+    let epilogue = "}";
+    //pass either left child or right child but not the root
+    let graph_arrangement = node_traversal(root);
+    //traverse the node as usual
+    let final_text: String = preamble + &graph_arrangement + epilogue;
+    let mut output: File = File::create(output_path)?;
+    output.write_all(final_text.as_bytes())?;
+    Ok(())
+}
+
+/**
+ * We will print string as we traverse, node by node
+ * at most a line per node printing, e.g: a--b;
+ * traversal mode in BFS
+ */
+#[allow(clippy::needless_return)]
+fn node_traversal<T: BinaryTreeNode>(node: &TreeLink<T>) -> String {
+    let mut new_info: String = "".to_string();
+    //we print the child nodes first
+    let left_child: Option<TreeLink<T>> = node.borrow().left();
+    //won't print anything if left child is None
+    new_info += &print_child(node, left_child.as_ref());
+    let right_child: Option<TreeLink<T>> = node.borrow().right();
+    new_info += &print_child(node, right_child.as_ref());
+    //now we need to traverse deeper
+    if let Some(ref left) = left_child {
+        new_info += &node_traversal(left);
+    }
+    if let Some(ref right) = right_child {
+        new_info += &node_traversal(right);
+    }
+    return new_info;
+}
+
+#[allow(clippy::needless_return)]
+fn print_child<T: BinaryTreeNode>(
+    parent_node: &TreeLink<T>,
+    child_node: Option<&TreeLink<T>>,
+) -> String {
+    let mut new_info: String = "".to_string();
+    if let Some(child) = child_node {
+        //concat parent
+        new_info += "\t";
+        new_info += &parent_node.borrow().label();
+        new_info += "--";
+        new_info += &child.borrow().label();
+        new_info += ";\n";
+    }
+    return new_info;
+}
+
+pub fn generate_dotfile_bst(root: &BstNodeLink, output_path: &str) {
+    try_generate_dotfile_bst(root, output_path).expect("Failed to create");
+}
+
+/**
+ * Same as generate_dotfile_bst, but failures are returned instead of panicking or being ignored
+ * Kept for the callers from before the writers became generic, it is try_generate_dotfile now
+ */
+pub fn try_generate_dotfile_bst(root: &BstNodeLink, output_path: &str) -> BstResult<()> {
+    try_generate_dotfile(root, output_path)
+}
+
+//the after tree with its changes filled in, removed keys are drawn on their own
+fn write_diff_dot_notation<W: Write>(
+    diff: &TreeDiff,
+    after: &BstNodeLink,
+    mut writer: W,
+) -> std::io::Result<()> {
+    writeln!(writer, "digraph TreeDiff {{")?;
+    writeln!(writer, "    node [style=filled, fillcolor=white];")?;
+    let mut queue: VecDeque<BstNodeLink> = VecDeque::from([after.clone()]);
+    while let Some(node_link) = queue.pop_front() {
+        let node = node_link.borrow();
+        let this_id: usize = node_id(&node_link);
+        let color: &str = match node.key.and_then(|x| diff.change_of(x)) {
+            Some(NodeChange::Added { .. }) => "palegreen",
+            Some(NodeChange::Reparented { .. }) => "orange",
+            Some(NodeChange::Moved { .. }) => "lightblue",
+            _ => "white",
+        };
+        writeln!(
+            writer,
+            "    {} [label=\"{}\", fillcolor={}];",
+            this_id,
+            node.label(),
+            color
+        )?;
+        for (child, side) in [(node.left(), "left"), (node.right(), "right")] {
+            if let Some(child) = child {
+                writeln!(
+                    writer,
+                    "    {} -> {} [label=\"{}\"];",
+                    this_id,
+                    node_id(&child),
+                    side
+                )?;
+                queue.push_back(child);
+            }
+        }
+    }
+    for change in diff.changes.iter() {
+        if let NodeChange::Removed { key } = change {
+            writeln!(
+                writer,
+                "    \"removed {}\" [label=\"{}\", style=\"filled,dashed\", fillcolor=lightpink];",
+                key, key
+            )?;
+        }
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/**
+ * Write the tree after a change with the diff against the tree before highlighted,
+ * green nodes were added, orange ones re-parented, blue ones moved to the other side,
+ * removed keys are dashed pink nodes outside the tree
+ */
+pub fn generate_diff_dotfile(diff: &TreeDiff, after: &BstNodeLink, output_path: &str) {
+    try_generate_diff_dotfile(diff, after, output_path).expect("Unable to write to .dot file");
+}
+
+/**
+ * Same as generate_diff_dotfile, but failures are returned instead of panicking
+ */
+pub fn try_generate_diff_dotfile(
+    diff: &TreeDiff,
+    after: &BstNodeLink,
+    output_path: &str,
+) -> BstResult<()> {
+    let file: File = File::create(output_path)?;
+    let mut writer: BufWriter<File> = BufWriter::new(file);
+    write_diff_dot_notation(diff, after, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/**
+ * Write a weight-balanced tree with the subtree size of every node under its key
+ */
+pub fn generate_dotfile_weight_balanced(tree: &WeightBalancedBst, output_path: &str) {
+    try_generate_dotfile_weight_balanced(tree, output_path).expect("Unable to write to .dot file");
+}
+
+/**
+ * Same as generate_dotfile_weight_balanced, but failures are returned instead of panicking
+ */
+pub fn try_generate_dotfile_weight_balanced(
+    tree: &WeightBalancedBst,
+    output_path: &str,
+) -> BstResult<()> {
+    let file: File = File::create(output_path)?;
+    let mut writer: BufWriter<File> = BufWriter::new(file);
+    write_weight_balanced_dot_notation(tree, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_weight_balanced_dot_notation<W: Write>(
+    tree: &WeightBalancedBst,
+    writer: W,
+) -> std::io::Result<()> {
+    let label = |x: &BstNodeLink| {
+        format!(
+            "{}\\nsize={}",
+            x.borrow().label(),
+            tree.size_of(&Some(x.clone()))
+        )
+    };
+    write_labeled_dot_notation(tree.root().as_ref(), label, writer)
+}
+
+/*
+pub fn graph_dotfile_string(root: &NodeLink) -> String{
+    ""
+}
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNode;
+    use crate::structure::compare;
+
+    #[test]
+    fn diff_dot_highlights_the_changes() {
+        let before: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 2, 3]).unwrap();
+        let after: BstNodeLink = BstNode::deep_clone(&before);
+        let one: BstNodeLink = BstNode::tree_search_link(&after, &1).unwrap();
+        assert!(BstNode::tree_delete_node(&one));
+        BstNode::tree_insert(&after, &4);
+        let diff: TreeDiff = compare::diff(&before, &after);
+        let mut text: Vec<u8> = Vec::new();
+        write_diff_dot_notation(&diff, &after, &mut text).unwrap();
+        let text: String = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("digraph TreeDiff {"));
+        assert!(text.contains("[label=\"4\", fillcolor=palegreen];"));
+        assert!(text.contains("[label=\"2\", fillcolor=white];"));
+        assert!(text.contains("\"removed 1\" [label=\"1\""));
+    }
+
+    #[test]
+    fn weight_balanced_dot_shows_sizes() {
+        let mut tree: WeightBalancedBst = WeightBalancedBst::new();
+        let mut text: Vec<u8> = Vec::new();
+        write_weight_balanced_dot_notation(&tree, &mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "digraph BinaryTree {\n}\n"
+        );
+        for key in [2, 1, 3] {
+            tree.insert(key);
+        }
+        let mut text: Vec<u8> = Vec::new();
+        write_weight_balanced_dot_notation(&tree, &mut text).unwrap();
+        let text: String = String::from_utf8(text).unwrap();
+        assert!(text.contains("[label=\"2\\nsize=3\"];"));
+        assert!(text.contains("[label=\"3\\nsize=1\"];"));
+    }
+}
@@ -0,0 +1,455 @@
+use crate::structure::binary_tree;
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::rng::XorShift64;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+//this package implement fuzz entry points for the mutating BstNode operations.
+//Arbitrary bytes are decoded into operation sequences, or into a tree shape built through
+//add_left_child and add_right_child followed by operations. Every input must run without a panic,
+//without a RefCell double borrow and with validate passing after every step.
+//The checked-in seed corpus lives under fuzz/corpus/<target>.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzTarget {
+    /// The whole input is an operation sequence on an initially empty tree.
+    Operations,
+    /// The input starts with a tree shape, the rest is an operation sequence on that tree.
+    Tree,
+}
+
+impl FuzzTarget {
+    pub fn all() -> [FuzzTarget; 2] {
+        [FuzzTarget::Operations, FuzzTarget::Tree]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FuzzTarget::Operations => "operations",
+            FuzzTarget::Tree => "tree",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<FuzzTarget> {
+        FuzzTarget::all().into_iter().find(|x| x.name() == name)
+    }
+
+    /**
+     * Run one input, see FuzzFailure for what counts as a failure
+     */
+    pub fn run(&self, bytes: &[u8]) -> Result<(), FuzzFailure> {
+        match self {
+            FuzzTarget::Operations => fuzz_operations(bytes),
+            FuzzTarget::Tree => fuzz_tree(bytes),
+        }
+    }
+}
+
+/// One mutation decoded from two input bytes, the keys are signed bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzOp {
+    Insert(i32),
+    /// `tree_delete_node` on the node found by a live search.
+    Delete(i32),
+    /// `tree_delete_with_key` called through the node at preorder position `receiver`,
+    /// taken modulo the node count.
+    DeleteWithKey {
+        receiver: u8,
+        key: i32,
+    },
+    /// Transplant the node with its left subtree, the right subtree is dropped.
+    TransplantLeft(i32),
+    /// Transplant the node with its right subtree, the left subtree is dropped.
+    TransplantRight(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FuzzFailure {
+    /// A RefCell was borrowed while already mutably borrowed, or the other way round.
+    DoubleBorrow {
+        step: usize,
+        message: String,
+    },
+    Panic {
+        step: usize,
+        message: String,
+    },
+    /// The tree broke an ordering or linking rule after the step.
+    Invariant {
+        step: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FuzzFailure::DoubleBorrow { step, message } => {
+                write!(f, "double borrow at step {}: {}", step, message)
+            }
+            FuzzFailure::Panic { step, message } => {
+                write!(f, "panic at step {}: {}", step, message)
+            }
+            FuzzFailure::Invariant { step, message } => {
+                write!(f, "invariant violation at step {}: {}", step, message)
+            }
+        }
+    }
+}
+
+/**
+ * Decode every byte pair into an operation, a trailing odd byte is ignored.
+ * The low three bits of the first byte pick the operation, the rest picks the receiver of DeleteWithKey
+ */
+pub fn decode_ops(bytes: &[u8]) -> Vec<FuzzOp> {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            let key: i32 = pair[1] as i8 as i32;
+            match pair[0] % 8 {
+                0..=2 => FuzzOp::Insert(key),
+                3 | 4 => FuzzOp::Delete(key),
+                5 => FuzzOp::DeleteWithKey {
+                    receiver: pair[0] >> 3,
+                    key,
+                },
+                6 => FuzzOp::TransplantLeft(key),
+                _ => FuzzOp::TransplantRight(key),
+            }
+        })
+        .collect()
+}
+
+/// Decodes a tree through `add_left_child` and `add_right_child`.
+///
+/// The first byte is the node count, capped at 64. Every further node takes one byte:
+/// its bits pick left or right from the root down, the node is attached at the first free spot on that path.
+/// Once the shape is built, keys are handed out in order, so the result is always a valid BST.
+///
+/// # Returns
+///
+/// * The root, `None` for an empty input or a zero node count.
+/// * The amount of bytes consumed.
+pub fn decode_tree(bytes: &[u8]) -> (Option<BstNodeLink>, usize) {
+    let count: usize = match bytes.first() {
+        Some(x) => (*x as usize).min(64),
+        None => return (None, 0),
+    };
+    if count == 0 {
+        return (None, 1);
+    }
+    let root: BstNodeLink = BstNode::new_bst_nodelink(0);
+    let mut consumed: usize = 1;
+    for path in bytes[1..].iter().take(count - 1) {
+        consumed += 1;
+        let mut current: BstNodeLink = root.clone();
+        let mut bits: u8 = *path;
+        loop {
+            let go_left: bool = bits & 1 == 0;
+            bits = bits.rotate_right(1);
+            let next: Option<BstNodeLink> = if go_left {
+                current.borrow().left.clone()
+            } else {
+                current.borrow().right.clone()
+            };
+            match next {
+                Some(x) => current = x,
+                None => {
+                    if go_left {
+                        current.borrow_mut().add_left_child(&current, 0);
+                    } else {
+                        current.borrow_mut().add_right_child(&current, 0);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    //keys are spread out, so inserted keys can land between them
+    let mut next_key: i32 = -((count as i32) * 2);
+    assign_inorder_keys(&Some(root.clone()), &mut next_key);
+    (Some(root), consumed)
+}
+
+fn assign_inorder_keys(node: &Option<BstNodeLink>, next_key: &mut i32) {
+    if let Some(x) = node {
+        let left: Option<BstNodeLink> = x.borrow().left.clone();
+        let right: Option<BstNodeLink> = x.borrow().right.clone();
+        assign_inorder_keys(&left, next_key);
+        x.borrow_mut().key = Some(*next_key);
+        *next_key += 4;
+        assign_inorder_keys(&right, next_key);
+    }
+}
+
+/**
+ * Fuzz target: operations on an initially empty tree
+ */
+pub fn fuzz_operations(bytes: &[u8]) -> Result<(), FuzzFailure> {
+    run_fuzz_ops(None, &decode_ops(bytes))
+}
+
+/**
+ * Fuzz target: a decoded tree shape followed by operations on it
+ */
+pub fn fuzz_tree(bytes: &[u8]) -> Result<(), FuzzFailure> {
+    let (root, consumed) = decode_tree(bytes);
+    if let Some(Err(err)) = root.as_ref().map(BstNode::validate) {
+        return Err(FuzzFailure::Invariant {
+            step: 0,
+            message: format!("decoded tree: {}", err),
+        });
+    }
+    run_fuzz_ops(root, &decode_ops(&bytes[consumed..]))
+}
+
+fn run_fuzz_ops(root: Option<BstNodeLink>, ops: &[FuzzOp]) -> Result<(), FuzzFailure> {
+    let mut root: Option<BstNodeLink> = root;
+    for (step, op) in ops.iter().enumerate() {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| apply(&mut root, *op)));
+        if let Err(payload) = outcome {
+            let message: String = payload
+                .downcast_ref::<&str>()
+                .map(|x| x.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            //RefCell reports double borrows as "already borrowed" or "already mutably borrowed"
+            if message.contains("already borrowed") || message.contains("already mutably borrowed")
+            {
+                return Err(FuzzFailure::DoubleBorrow { step, message });
+            }
+            return Err(FuzzFailure::Panic { step, message });
+        }
+        if let Some(Err(err)) = root.as_ref().map(BstNode::validate) {
+            return Err(FuzzFailure::Invariant {
+                step,
+                message: format!("after {:?}: {}", op, err),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn apply(root: &mut Option<BstNodeLink>, op: FuzzOp) {
+    let current_root: BstNodeLink = match root {
+        Some(ref x) => x.clone(),
+        None => {
+            if let FuzzOp::Insert(key) = op {
+                *root = Some(BstNode::new_bst_nodelink(key));
+            }
+            return;
+        }
+    };
+    match op {
+        FuzzOp::Insert(key) => {
+            BstNode::tree_insert(&current_root, &key);
+        }
+        FuzzOp::Delete(key) => {
+            if let Some(node) = BstNode::tree_search_link(&current_root, &key) {
+                let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&node);
+                if BstNode::tree_delete_node(&node) && Rc::ptr_eq(&node, &current_root) {
+                    *root = replacement;
+                }
+            }
+        }
+        FuzzOp::DeleteWithKey { receiver, key } => {
+            let count: usize = binary_tree::count_nodes(&current_root);
+            let node: BstNodeLink = binary_tree::preorder(&current_root)
+                .nth(receiver as usize % count)
+                .unwrap();
+            //the receiver refuses its own key and only unlinks descendants, so the root stays
+            node.borrow_mut().tree_delete_with_key(key);
+        }
+        FuzzOp::TransplantLeft(key) | FuzzOp::TransplantRight(key) => {
+            if let Some(node) = BstNode::tree_search_link(&current_root, &key) {
+                let subtree: Option<BstNodeLink> = match op {
+                    FuzzOp::TransplantLeft(_) => node.borrow().left.clone(),
+                    _ => node.borrow().right.clone(),
+                };
+                if BstNode::transplant_node(&node, &subtree) && Rc::ptr_eq(&node, &current_root) {
+                    *root = subtree;
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Flip, insert and drop random bytes of input, the way a mutational fuzzer derives new inputs from a corpus
+ */
+pub fn mutate(input: &[u8], rng: &mut XorShift64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = input.to_vec();
+    let rounds: u64 = 1 + rng.below(8);
+    for _ in 0..rounds {
+        match rng.below(3) {
+            0 if !bytes.is_empty() => {
+                let i: usize = rng.below(bytes.len() as u64) as usize;
+                bytes[i] ^= 1 << rng.below(8);
+            }
+            1 => {
+                let i: usize = rng.below(bytes.len() as u64 + 1) as usize;
+                bytes.insert(i, rng.next_u64() as u8);
+            }
+            _ if !bytes.is_empty() => {
+                let i: usize = rng.below(bytes.len() as u64) as usize;
+                bytes.remove(i);
+            }
+            _ => bytes.push(rng.next_u64() as u8),
+        }
+    }
+    bytes
+}
+
+/**
+ * Run target on every corpus input and on iterations mutations of them,
+ * return the first failing input along with its failure.
+ * The panic hook is left alone, a caller wanting a quiet run installs its own before calling
+ */
+pub fn fuzz_corpus(
+    target: FuzzTarget,
+    corpus: &[Vec<u8>],
+    iterations: usize,
+    seed: u64,
+) -> Option<(Vec<u8>, FuzzFailure)> {
+    let mut rng: XorShift64 = XorShift64::new(seed);
+    let mut failure: Option<(Vec<u8>, FuzzFailure)> = None;
+    for input in corpus.iter() {
+        if let Err(err) = target.run(input) {
+            failure = Some((input.clone(), err));
+            break;
+        }
+    }
+    if failure.is_none() && !corpus.is_empty() {
+        for _ in 0..iterations {
+            let parent: &Vec<u8> = &corpus[rng.below(corpus.len() as u64) as usize];
+            let input: Vec<u8> = mutate(parent, &mut rng);
+            if let Err(err) = target.run(&input) {
+                failure = Some((input, err));
+                break;
+            }
+        }
+    }
+    failure
+}
+
+/**
+ * Read every file of `fuzz/corpus/<target>` under dir, sorted by name
+ */
+pub fn load_corpus(dir: &str, target: FuzzTarget) -> std::io::Result<Vec<Vec<u8>>> {
+    let path: std::path::PathBuf = std::path::Path::new(dir)
+        .join("fuzz")
+        .join("corpus")
+        .join(target.name());
+    let mut files: Vec<std::path::PathBuf> = std::fs::read_dir(path)?
+        .filter_map(|x| x.ok().map(|entry| entry.path()))
+        .filter(|x| x.is_file())
+        .collect();
+    files.sort();
+    files.iter().map(std::fs::read).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_corpus_runs_clean() {
+        for target in FuzzTarget::all() {
+            let corpus: Vec<Vec<u8>> = load_corpus(env!("CARGO_MANIFEST_DIR"), target).unwrap();
+            assert!(!corpus.is_empty(), "empty corpus for {}", target.name());
+            let failure = fuzz_corpus(target, &corpus, 2000, 11);
+            assert!(failure.is_none(), "{:?}", failure);
+        }
+    }
+
+    #[test]
+    fn random_inputs_run_clean() {
+        let mut rng: XorShift64 = XorShift64::new(3);
+        for _ in 0..500 {
+            let length: usize = rng.below(200) as usize;
+            let bytes: Vec<u8> = (0..length).map(|_| rng.next_u64() as u8).collect();
+            for target in FuzzTarget::all() {
+                assert_eq!(target.run(&bytes), Ok(()), "input {:?}", bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_ops_reads_byte_pairs() {
+        assert_eq!(
+            decode_ops(&[0, 5, 3, 255, 5, 1, 6, 2, 7, 3, 9]),
+            vec![
+                FuzzOp::Insert(5),
+                FuzzOp::Delete(-1),
+                FuzzOp::DeleteWithKey {
+                    receiver: 0,
+                    key: 1
+                },
+                FuzzOp::TransplantLeft(2),
+                FuzzOp::TransplantRight(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_with_key_goes_through_the_chosen_receiver() {
+        assert_eq!(
+            decode_ops(&[21, 7]),
+            vec![FuzzOp::DeleteWithKey {
+                receiver: 2,
+                key: 7
+            }]
+        );
+        //tree 0(-4(-8,),4), preorder 0, -4, -8, 4
+        let (root, _) = decode_tree(&[4, 0, 1, 0]);
+        let mut root: Option<BstNodeLink> = root;
+        //-4 deletes its child -8, then refuses its own key
+        apply(
+            &mut root,
+            FuzzOp::DeleteWithKey {
+                receiver: 1,
+                key: -8,
+            },
+        );
+        apply(
+            &mut root,
+            FuzzOp::DeleteWithKey {
+                receiver: 1,
+                key: -4,
+            },
+        );
+        //the receiver position wraps around the node count
+        apply(
+            &mut root,
+            FuzzOp::DeleteWithKey {
+                receiver: 3,
+                key: 4,
+            },
+        );
+        let root: BstNodeLink = root.unwrap();
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(BstNode::inorder_keys(&root), vec![-4, 0]);
+    }
+
+    #[test]
+    fn decode_tree_builds_a_valid_bst_of_the_given_shape() {
+        //root, then left, then right, then left of left
+        let (root, consumed) = decode_tree(&[4, 0, 1, 0, 8, 8]);
+        assert_eq!(consumed, 4);
+        let root: BstNodeLink = root.unwrap();
+        assert!(BstNode::validate(&root).is_ok());
+        assert_eq!(BstNode::inorder_keys(&root), vec![-8, -4, 0, 4]);
+        assert_eq!(root.borrow().key, Some(0));
+        assert!(decode_tree(&[]).0.is_none());
+        assert!(decode_tree(&[0, 1]).0.is_none());
+    }
+
+    #[test]
+    fn root_operations_keep_the_tree_valid() {
+        //insert 0, 5, -5, then transplant the root with its right subtree,
+        //then ask the new root to delete its own key, which it refuses
+        let bytes: [u8; 10] = [0, 0, 0, 5, 0, 251, 7, 0, 5, 5];
+        assert_eq!(fuzz_operations(&bytes), Ok(()));
+    }
+}
@@ -0,0 +1,435 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::rng::XorShift64;
+use crate::tool::try_generate_dotfile_bst_better;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+//this package implement a randomized differential tester for BstNode.
+//Random operation sequences run against a BstNode tree and a BTreeSet side by side,
+//after every step the answers must agree, the in-order keys must match the set and validate must pass.
+//A failing sequence is shrunk to a minimal reproducer whose final tree is written as a dot file.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelOp {
+    Insert(i32),
+    Delete(i32),
+    /// `tree_delete_with_key` through the root handle, the root's own key goes through `tree_delete_node`.
+    DeleteWithKey(i32),
+    Search(i32),
+    /// `tree_search_correct` on the root, which hands back a copy of the node.
+    SearchCorrect(i32),
+    /// Successor of a present key, an absent key observes `false`.
+    Successor(i32),
+    /// `tree_successor` of a present key, which hands back the node itself for the maximum.
+    TreeSuccessor(i32),
+    Minimum,
+    Maximum,
+}
+
+impl fmt::Display for ModelOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelOp::Insert(key) => write!(f, "insert {}", key),
+            ModelOp::Delete(key) => write!(f, "delete {}", key),
+            ModelOp::DeleteWithKey(key) => write!(f, "delete_with_key {}", key),
+            ModelOp::Search(key) => write!(f, "search {}", key),
+            ModelOp::SearchCorrect(key) => write!(f, "search_correct {}", key),
+            ModelOp::Successor(key) => write!(f, "successor {}", key),
+            ModelOp::TreeSuccessor(key) => write!(f, "tree_successor {}", key),
+            ModelOp::Minimum => write!(f, "minimum"),
+            ModelOp::Maximum => write!(f, "maximum"),
+        }
+    }
+}
+
+/// What a single operation answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observation {
+    Flag(bool),
+    Key(Option<i32>),
+}
+
+/// First disagreement found while replaying a sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Index of the operation after which the check failed.
+    pub step: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {}: {}", self.step, self.reason)
+    }
+}
+
+/// A shrunk failing sequence along with the tree it left behind.
+pub struct Counterexample {
+    pub ops: Vec<ModelOp>,
+    pub mismatch: Mismatch,
+    pub tree: Option<BstNodeLink>,
+}
+
+/// `BstNode` tree owning its root handle, the root is swapped when the root itself is deleted.
+#[derive(Default)]
+struct TreeUnderTest {
+    root: Option<BstNodeLink>,
+}
+
+impl TreeUnderTest {
+    fn apply(&mut self, op: ModelOp) -> Observation {
+        match op {
+            ModelOp::Insert(key) => Observation::Flag(match self.root {
+                Some(ref root) => BstNode::tree_insert(root, &key).is_some(),
+                None => {
+                    self.root = Some(BstNode::new_bst_nodelink(key));
+                    true
+                }
+            }),
+            ModelOp::Delete(key) => {
+                let node: BstNodeLink = match self.search(key) {
+                    Some(x) => x,
+                    None => return Observation::Flag(false),
+                };
+                let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&node);
+                let is_root: bool = self.root.as_ref().is_some_and(|x| Rc::ptr_eq(x, &node));
+                let deleted: bool = BstNode::tree_delete_node(&node);
+                if deleted && is_root {
+                    self.root = replacement;
+                }
+                Observation::Flag(deleted)
+            }
+            ModelOp::DeleteWithKey(key) => {
+                let root: BstNodeLink = match self.root {
+                    Some(ref x) => x.clone(),
+                    None => return Observation::Flag(false),
+                };
+                let deleted: bool = root.borrow_mut().tree_delete_with_key(key);
+                //the receiver refuses its own key, the root is deleted as a node instead
+                if !deleted && root.borrow().key == Some(key) {
+                    return self.apply(ModelOp::Delete(key));
+                }
+                Observation::Flag(deleted)
+            }
+            ModelOp::Search(key) => Observation::Flag(self.search(key).is_some()),
+            ModelOp::SearchCorrect(key) => Observation::Flag(
+                self.root
+                    .as_ref()
+                    .and_then(|x| x.borrow().tree_search_correct(&key))
+                    .is_some(),
+            ),
+            ModelOp::Successor(key) => match self.search(key) {
+                Some(x) => {
+                    Observation::Key(BstNode::tree_successor_link(&x).and_then(|s| s.borrow().key))
+                }
+                None => Observation::Flag(false),
+            },
+            ModelOp::TreeSuccessor(key) => match self.search(key) {
+                Some(x) => {
+                    let successor: Option<i32> = BstNode::tree_successor(&x).borrow().key;
+                    //the maximum is its own successor
+                    Observation::Key(successor.filter(|s| *s != key))
+                }
+                None => Observation::Flag(false),
+            },
+            ModelOp::Minimum => Observation::Key(
+                self.root
+                    .as_ref()
+                    .and_then(|x| x.borrow().minimum().borrow().key),
+            ),
+            ModelOp::Maximum => Observation::Key(
+                self.root
+                    .as_ref()
+                    .and_then(|x| x.borrow().maximum().borrow().key),
+            ),
+        }
+    }
+
+    fn search(&self, key: i32) -> Option<BstNodeLink> {
+        self.root
+            .as_ref()
+            .and_then(|x| BstNode::tree_search_link(x, &key))
+    }
+
+    fn keys(&self) -> Vec<i32> {
+        self.root
+            .as_ref()
+            .map(BstNode::inorder_keys)
+            .unwrap_or_default()
+    }
+}
+
+fn apply_to_model(model: &mut BTreeSet<i32>, op: ModelOp) -> Observation {
+    match op {
+        ModelOp::Insert(key) => Observation::Flag(model.insert(key)),
+        ModelOp::Delete(key) | ModelOp::DeleteWithKey(key) => Observation::Flag(model.remove(&key)),
+        ModelOp::Search(key) | ModelOp::SearchCorrect(key) => {
+            Observation::Flag(model.contains(&key))
+        }
+        ModelOp::Successor(key) | ModelOp::TreeSuccessor(key) => {
+            if !model.contains(&key) {
+                return Observation::Flag(false);
+            }
+            Observation::Key(model.range(key.saturating_add(1)..).next().copied())
+        }
+        ModelOp::Minimum => Observation::Key(model.first().copied()),
+        ModelOp::Maximum => Observation::Key(model.last().copied()),
+    }
+}
+
+/**
+ * Draw length operations with keys within 0..key_range, inserts are weighted up
+ * so the tree grows instead of hovering around empty
+ */
+pub fn generate_ops(rng: &mut XorShift64, length: usize, key_range: i32) -> Vec<ModelOp> {
+    let range: u64 = key_range.max(1) as u64;
+    (0..length)
+        .map(|_| {
+            let key: i32 = rng.below(range) as i32;
+            match rng.below(14) {
+                0..=4 => ModelOp::Insert(key),
+                5 | 6 => ModelOp::Delete(key),
+                7 => ModelOp::DeleteWithKey(key),
+                8 => ModelOp::Search(key),
+                9 => ModelOp::SearchCorrect(key),
+                10 => ModelOp::Successor(key),
+                11 => ModelOp::TreeSuccessor(key),
+                12 => ModelOp::Minimum,
+                _ => ModelOp::Maximum,
+            }
+        })
+        .collect()
+}
+
+/**
+ * Replay ops against BstNode and BTreeSet, return the first mismatch and the tree at that point.
+ * A panic inside the tree code counts as a mismatch too
+ */
+pub fn run_ops(ops: &[ModelOp]) -> (Option<Mismatch>, Option<BstNodeLink>) {
+    let mut tree: TreeUnderTest = TreeUnderTest::default();
+    let mut model: BTreeSet<i32> = BTreeSet::new();
+    for (step, op) in ops.iter().enumerate() {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| tree.apply(*op)));
+        let observed: Observation = match outcome {
+            Ok(x) => x,
+            Err(payload) => {
+                let message: String = payload
+                    .downcast_ref::<&str>()
+                    .map(|x| x.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                let reason: String = format!("{} panicked: {}", op, message);
+                return (Some(Mismatch { step, reason }), tree.root);
+            }
+        };
+        let expected: Observation = apply_to_model(&mut model, *op);
+        if observed != expected {
+            let reason: String = format!("{} returned {:?}, expected {:?}", op, observed, expected);
+            return (Some(Mismatch { step, reason }), tree.root);
+        }
+        let keys: Vec<i32> = tree.keys();
+        if !keys.iter().eq(model.iter()) {
+            let reason: String = format!(
+                "after {} the tree holds {:?}, expected {:?}",
+                op, keys, model
+            );
+            return (Some(Mismatch { step, reason }), tree.root);
+        }
+        if let Some(Err(err)) = tree.root.as_ref().map(BstNode::validate) {
+            let reason: String = format!("after {}: {}", op, err);
+            return (Some(Mismatch { step, reason }), tree.root);
+        }
+    }
+    (None, tree.root)
+}
+
+/**
+ * Shrink a failing sequence, first by dropping chunks of operations, then by moving keys towards zero.
+ * fails must hold for ops, the result is a sequence fails still holds for
+ */
+pub fn shrink(ops: &[ModelOp], fails: impl Fn(&[ModelOp]) -> bool) -> Vec<ModelOp> {
+    let mut current: Vec<ModelOp> = ops.to_vec();
+    let mut chunk: usize = (current.len() / 2).max(1);
+    loop {
+        let mut removed: bool = false;
+        let mut start: usize = 0;
+        while start < current.len() {
+            let end: usize = (start + chunk).min(current.len());
+            let candidate: Vec<ModelOp> = [&current[..start], &current[end..]].concat();
+            if fails(&candidate) {
+                current = candidate;
+                removed = true;
+            } else {
+                start += chunk;
+            }
+        }
+        if !removed {
+            if chunk == 1 {
+                break;
+            }
+            chunk /= 2;
+        }
+    }
+    //smaller keys make the reproducer easier to read
+    let mut changed: bool = true;
+    while changed {
+        changed = false;
+        for i in 0..current.len() {
+            for smaller in smaller_ops(current[i]) {
+                let mut candidate: Vec<ModelOp> = current.clone();
+                candidate[i] = smaller;
+                if fails(&candidate) {
+                    current = candidate;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+    }
+    current
+}
+
+fn smaller_ops(op: ModelOp) -> Vec<ModelOp> {
+    let with_key = |key: i32| -> Vec<i32> {
+        let mut keys: Vec<i32> = vec![0, key / 2, key - key.signum()];
+        keys.retain(|x| x.abs() < key.abs());
+        keys.dedup();
+        keys
+    };
+    match op {
+        ModelOp::Insert(key) => with_key(key).into_iter().map(ModelOp::Insert).collect(),
+        ModelOp::Delete(key) => with_key(key).into_iter().map(ModelOp::Delete).collect(),
+        ModelOp::DeleteWithKey(key) => with_key(key)
+            .into_iter()
+            .map(ModelOp::DeleteWithKey)
+            .collect(),
+        ModelOp::Search(key) => with_key(key).into_iter().map(ModelOp::Search).collect(),
+        ModelOp::SearchCorrect(key) => with_key(key)
+            .into_iter()
+            .map(ModelOp::SearchCorrect)
+            .collect(),
+        ModelOp::Successor(key) => with_key(key).into_iter().map(ModelOp::Successor).collect(),
+        ModelOp::TreeSuccessor(key) => with_key(key)
+            .into_iter()
+            .map(ModelOp::TreeSuccessor)
+            .collect(),
+        ModelOp::Minimum | ModelOp::Maximum => Vec::new(),
+    }
+}
+
+/**
+ * Run cases random sequences of length operations each, return the first failure shrunk to a minimal reproducer.
+ * The panic hook is left alone, a caller wanting a quiet run installs its own before calling
+ */
+pub fn check(seed: u64, cases: usize, length: usize) -> Option<Counterexample> {
+    let mut rng: XorShift64 = XorShift64::new(seed);
+    //a small key space makes repeated inserts and deletes of the same key likely
+    let key_range: i32 = (length as i32 / 2).max(8);
+    for _ in 0..cases {
+        let ops: Vec<ModelOp> = generate_ops(&mut rng, length, key_range);
+        if run_ops(&ops).0.is_none() {
+            continue;
+        }
+        let shrunk: Vec<ModelOp> = shrink(&ops, |candidate| run_ops(candidate).0.is_some());
+        let (mismatch, tree) = run_ops(&shrunk);
+        return Some(Counterexample {
+            ops: shrunk,
+            mismatch: mismatch.unwrap(),
+            tree,
+        });
+    }
+    None
+}
+
+impl Counterexample {
+    /**
+     * Write the tree left behind by the reproducer as a dot file, return false for an empty tree
+     */
+    pub fn write_dot(&self, output_path: &str) -> bool {
+        match self.tree {
+            Some(ref root) => try_generate_dotfile_bst_better(root, output_path).is_ok(),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_sequences_agree_with_btreeset() {
+        for seed in 1..=5 {
+            let failure: Option<Counterexample> = check(seed, 20, 200);
+            assert!(failure.is_none(), "{}", failure.unwrap().mismatch);
+        }
+    }
+
+    #[test]
+    fn root_deletions_keep_the_handle_valid() {
+        let ops: Vec<ModelOp> = vec![
+            ModelOp::Insert(5),
+            ModelOp::Insert(3),
+            ModelOp::Insert(8),
+            ModelOp::Delete(5),
+            ModelOp::Minimum,
+            ModelOp::Delete(8),
+            ModelOp::Successor(3),
+            ModelOp::Delete(3),
+            ModelOp::Maximum,
+            ModelOp::Insert(1),
+        ];
+        let (mismatch, tree) = run_ops(&ops);
+        assert!(mismatch.is_none(), "{}", mismatch.unwrap());
+        assert_eq!(tree.map(|x| BstNode::inorder_keys(&x)), Some(vec![1]));
+    }
+
+    #[test]
+    fn delete_with_key_and_the_copying_searches() {
+        let ops: Vec<ModelOp> = vec![
+            ModelOp::Insert(5),
+            ModelOp::Insert(3),
+            ModelOp::Insert(8),
+            ModelOp::Insert(9),
+            ModelOp::TreeSuccessor(8),
+            ModelOp::TreeSuccessor(9),
+            ModelOp::DeleteWithKey(8),
+            ModelOp::SearchCorrect(8),
+            ModelOp::SearchCorrect(9),
+            ModelOp::DeleteWithKey(5),
+            ModelOp::DeleteWithKey(5),
+            ModelOp::Minimum,
+        ];
+        let (mismatch, tree) = run_ops(&ops);
+        assert!(mismatch.is_none(), "{}", mismatch.unwrap());
+        assert_eq!(tree.map(|x| BstNode::inorder_keys(&x)), Some(vec![3, 9]));
+    }
+
+    #[test]
+    fn shrink_finds_a_minimal_sequence() {
+        let mut rng: XorShift64 = XorShift64::new(7);
+        let mut ops: Vec<ModelOp> = generate_ops(&mut rng, 300, 100);
+        ops.push(ModelOp::Insert(42));
+        ops.push(ModelOp::Delete(42));
+        //pretend the bug needs 42 to be inserted and deleted again
+        let fails = |candidate: &[ModelOp]| {
+            let inserted: Option<usize> = candidate.iter().position(|x| *x == ModelOp::Insert(42));
+            inserted.is_some_and(|i| candidate[i..].contains(&ModelOp::Delete(42)))
+        };
+        let shrunk: Vec<ModelOp> = shrink(&ops, fails);
+        assert_eq!(shrunk, vec![ModelOp::Insert(42), ModelOp::Delete(42)]);
+    }
+
+    #[test]
+    fn shrink_moves_keys_towards_zero() {
+        let ops: Vec<ModelOp> = vec![ModelOp::Search(9), ModelOp::Insert(37)];
+        let fails = |candidate: &[ModelOp]| {
+            candidate
+                .iter()
+                .any(|x| matches!(x, ModelOp::Insert(key) if *key >= 10))
+        };
+        assert_eq!(shrink(&ops, fails), vec![ModelOp::Insert(10)]);
+    }
+}
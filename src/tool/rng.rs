@@ -0,0 +1,53 @@
+//small deterministic xorshift64 generator for the workloads of the command line subcommands,
+//a fixed seed keeps every run reproducible without pulling in a dependency
+pub struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    /**
+     * A zero seed would lock the generator at zero, it is replaced by a fixed constant
+     */
+    pub fn new(seed: u64) -> Self {
+        XorShift64 {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x: u64 = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /**
+     * Uniform value within 0..bound, bound must not be zero
+     */
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /**
+     * Uniform value within 0.0..1.0
+     */
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /**
+     * Fisher-Yates shuffle in place
+     */
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j: usize = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
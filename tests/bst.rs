@@ -0,0 +1,166 @@
+use binarysearchtree::structure::bst::{BstNode, BstNodeLink};
+use binarysearchtree::structure::error::BstError;
+use std::rc::Rc;
+
+//the tree of the demo in main.rs
+//            15
+//        6        18
+//      3   7    17  20
+//     2 4   13
+//          9
+fn sample_tree() -> BstNodeLink {
+    let root: BstNodeLink = BstNode::new_bst_nodelink(15);
+    for key in [6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+        assert!(BstNode::tree_insert(&root, &key).is_some());
+    }
+    root
+}
+
+fn find(root: &BstNodeLink, key: i32) -> BstNodeLink {
+    BstNode::tree_search_link(root, &key).unwrap()
+}
+
+fn key_of(node: &Option<BstNodeLink>) -> Option<i32> {
+    node.as_ref().and_then(|x| x.borrow().key)
+}
+
+#[test]
+fn successors_walk_the_whole_tree() {
+    let root: BstNodeLink = sample_tree();
+    let mut keys: Vec<i32> = Vec::new();
+    let mut current: Option<BstNodeLink> = Some(BstNode::leftmost(&root));
+    while let Some(x) = current {
+        keys.push(x.borrow().key.unwrap());
+        current = BstNode::tree_successor_link(&x);
+    }
+    assert_eq!(keys, BstNode::inorder_keys(&root));
+    //the maximum has no successor, tree_successor hands the node itself back
+    let maximum: BstNodeLink = find(&root, 20);
+    assert!(BstNode::tree_successor_link(&maximum).is_none());
+    assert!(Rc::ptr_eq(&BstNode::tree_successor(&maximum), &maximum));
+    assert_eq!(
+        BstNode::tree_successor(&find(&root, 13)).borrow().key,
+        Some(15)
+    );
+    assert_eq!(
+        key_of(&BstNode::tree_predecessor_link(&find(&root, 17))),
+        Some(15)
+    );
+    assert!(BstNode::tree_predecessor_link(&find(&root, 2)).is_none());
+}
+
+#[test]
+fn searching_through_copies_and_live_links() {
+    let root: BstNodeLink = sample_tree();
+    assert_eq!(key_of(&root.borrow().tree_search(&13)), Some(13));
+    assert_eq!(key_of(&root.borrow().tree_search_correct(&9)), Some(9));
+    assert!(root.borrow().tree_search(&10).is_none());
+    assert_eq!(root.borrow().minimum().borrow().key, Some(2));
+    assert_eq!(root.borrow().maximum_correct().borrow().key, Some(20));
+    let nine: BstNodeLink = find(&root, 9);
+    assert!(Rc::ptr_eq(&BstNode::get_root(&nine), &root));
+    assert_eq!(key_of(&BstNode::get_parent(&nine)), Some(13));
+}
+
+#[test]
+fn deleting_the_root_until_the_tree_is_empty() {
+    let mut root: Option<BstNodeLink> = Some(sample_tree());
+    let mut remaining: Vec<i32> = BstNode::inorder_keys(root.as_ref().unwrap());
+    while let Some(current) = root {
+        let key: i32 = current.borrow().key.unwrap();
+        let replacement: Option<BstNodeLink> = BstNode::tree_delete_replacement(&current);
+        assert!(BstNode::tree_delete_node(&current));
+        remaining.retain(|x| *x != key);
+        root = replacement;
+        if let Some(ref new_root) = root {
+            assert!(BstNode::get_parent(new_root).is_none());
+            assert!(BstNode::validate(new_root).is_ok());
+            assert_eq!(BstNode::inorder_keys(new_root), remaining);
+        }
+    }
+    assert!(remaining.is_empty());
+}
+
+#[test]
+fn delete_with_key_through_the_root() {
+    let root: BstNodeLink = sample_tree();
+    assert!(root.borrow_mut().tree_delete_with_key(6));
+    assert!(root.borrow_mut().tree_delete_with_key(9));
+    assert!(!root.borrow_mut().tree_delete_with_key(6));
+    assert!(matches!(
+        root.borrow_mut().try_tree_delete_with_key(15),
+        Err(BstError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        root.borrow_mut().try_tree_delete_with_key(100),
+        Err(BstError::NotFound(100))
+    ));
+    assert_eq!(key_of(&root.borrow().left), Some(7));
+    assert!(BstNode::validate(&root).is_ok());
+    assert_eq!(
+        BstNode::inorder_keys(&root),
+        vec![2, 3, 4, 7, 13, 15, 17, 18, 20]
+    );
+}
+
+#[test]
+fn transplant_at_the_root_and_below() {
+    let root: BstNodeLink = sample_tree();
+    let eighteen: BstNodeLink = find(&root, 18);
+    let twenty: Option<BstNodeLink> = eighteen.borrow().right.clone();
+    assert!(BstNode::transplant_node(&eighteen, &twenty));
+    assert_eq!(key_of(&root.borrow().right), Some(20));
+    assert!(Rc::ptr_eq(
+        &BstNode::get_parent(twenty.as_ref().unwrap()).unwrap(),
+        &root
+    ));
+
+    let six: Option<BstNodeLink> = root.borrow().left.clone();
+    assert!(BstNode::transplant_node(&root, &six));
+    let new_root: BstNodeLink = six.unwrap();
+    assert!(BstNode::get_parent(&new_root).is_none());
+    assert_eq!(BstNode::inorder_keys(&new_root), vec![2, 3, 4, 6, 7, 9, 13]);
+}
+
+#[test]
+fn insert_refuses_duplicates() {
+    let root: BstNodeLink = sample_tree();
+    assert!(BstNode::tree_insert(&root, &7).is_none());
+    assert!(matches!(
+        BstNode::try_tree_insert(&root, &7),
+        Err(BstError::DuplicateKey(7))
+    ));
+    let inserted: BstNodeLink = BstNode::try_tree_insert(&root, &5).unwrap();
+    assert_eq!(key_of(&BstNode::get_parent(&inserted)), Some(4));
+    assert!(BstNode::validate(&root).is_ok());
+}
+
+#[test]
+fn rotations_and_move_to_root() {
+    let root: BstNodeLink = sample_tree();
+    let new_root: BstNodeLink = BstNode::rotate_right(&root).unwrap();
+    assert_eq!(new_root.borrow().key, Some(6));
+    assert!(BstNode::get_parent(&new_root).is_none());
+    assert!(BstNode::validate(&new_root).is_ok());
+    let moved: BstNodeLink = BstNode::move_to_root(&new_root, &9).unwrap();
+    assert_eq!(moved.borrow().key, Some(9));
+    assert!(BstNode::validate(&moved).is_ok());
+    assert_eq!(BstNode::inorder_keys(&moved).len(), 11);
+    assert!(matches!(
+        BstNode::try_move_to_root(&moved, &10),
+        Err(BstError::NotFound(10))
+    ));
+}
+
+#[test]
+fn split_and_join_round_trip() {
+    let root: BstNodeLink = sample_tree();
+    let (left, right) = BstNode::split(&root, &13);
+    assert_eq!(
+        BstNode::inorder_keys(left.as_ref().unwrap()),
+        vec![2, 3, 4, 6, 7, 9]
+    );
+    let joined: BstNodeLink = BstNode::try_join(left, right).unwrap().unwrap();
+    assert!(BstNode::validate(&joined).is_ok());
+    assert_eq!(BstNode::inorder_keys(&joined).len(), 11);
+}
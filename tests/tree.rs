@@ -0,0 +1,145 @@
+use binarysearchtree::structure::bst::{BstNode, BstNodeLink};
+use binarysearchtree::structure::tree::{Node, NodeLink};
+use std::rc::Rc;
+
+//          1
+//      2       3
+//    4   5   6   7
+//                  8
+fn sample_tree() -> NodeLink {
+    let root: NodeLink = Node::new_nodelink(1);
+    root.borrow_mut().add_left_child(&root, 2);
+    root.borrow_mut().add_right_child(&root, 3);
+    let two: NodeLink = root.borrow().left.clone().unwrap();
+    two.borrow_mut().add_left_child(&two, 4);
+    two.borrow_mut().add_right_child(&two, 5);
+    let three: NodeLink = root.borrow().right.clone().unwrap();
+    three.borrow_mut().add_left_child(&three, 6);
+    three.borrow_mut().add_right_child(&three, 7);
+    let seven: NodeLink = three.borrow().right.clone().unwrap();
+    seven.borrow_mut().add_right_child(&seven, 8);
+    root
+}
+
+fn value_of(node: &Option<NodeLink>) -> Option<i32> {
+    node.as_ref().map(|x| x.borrow().value)
+}
+
+#[test]
+fn get_node_by_value_reaches_every_subtree() {
+    let root: NodeLink = sample_tree();
+    for value in 1..=8 {
+        let found: Option<NodeLink> = root.borrow().get_node_by_value(value);
+        assert_eq!(value_of(&found), Some(value));
+    }
+    assert!(root.borrow().get_node_by_value(9).is_none());
+    //a match below the receiver is the live node, so its parent is reachable
+    let eight: NodeLink = root.borrow().get_node_by_value(8).unwrap();
+    assert_eq!(value_of(&Node::get_parent(&eight)), Some(7));
+    let six: NodeLink = root.borrow().get_node_by_value(6).unwrap();
+    assert_eq!(value_of(&Node::get_sibling(&six)), Some(7));
+}
+
+#[test]
+fn get_node_by_full_property_matches_parent_and_children() {
+    let root: NodeLink = sample_tree();
+    let seven: NodeLink = root.borrow().get_node_by_value(7).unwrap();
+    let found: NodeLink = root.borrow().get_node_by_full_property(&seven).unwrap();
+    assert!(Rc::ptr_eq(&found, &seven));
+    //same value under another parent is not the same node
+    let impostor: NodeLink = Node::new_nodelink(7);
+    assert!(root.borrow().get_node_by_full_property(&impostor).is_none());
+}
+
+#[test]
+fn count_nodes_and_tree_depth() {
+    let root: NodeLink = sample_tree();
+    assert_eq!(root.borrow().count_nodes(), 8);
+    assert_eq!(root.borrow().tree_depth(), 3);
+    let three: NodeLink = root.borrow().get_node_by_value(3).unwrap();
+    assert_eq!(three.borrow().count_nodes(), 4);
+    assert_eq!(three.borrow().tree_depth(), 2);
+    let leaf: NodeLink = Node::new_nodelink(4);
+    assert_eq!(leaf.borrow().count_nodes(), 1);
+    assert_eq!(leaf.borrow().tree_depth(), 0);
+    assert_eq!(Node::count_nodes_by_nodelink(&root, 2), 10);
+}
+
+#[test]
+fn get_sibling_from_either_side() {
+    let root: NodeLink = sample_tree();
+    let four: NodeLink = root.borrow().get_node_by_value(4).unwrap();
+    let five: NodeLink = root.borrow().get_node_by_value(5).unwrap();
+    assert!(Rc::ptr_eq(&Node::get_sibling(&four).unwrap(), &five));
+    assert!(Rc::ptr_eq(&Node::get_sibling(&five).unwrap(), &four));
+    let eight: NodeLink = root.borrow().get_node_by_value(8).unwrap();
+    assert!(Node::get_sibling(&eight).is_none());
+    assert!(Node::get_sibling(&root).is_none());
+}
+
+#[test]
+fn discard_node_by_value_cuts_the_whole_subtree() {
+    let root: NodeLink = sample_tree();
+    let seven: NodeLink = root.borrow().get_node_by_value(7).unwrap();
+    assert!(root.borrow_mut().discard_node_by_value(7));
+    assert!(seven.borrow().parent.is_none());
+    assert_eq!(root.borrow().count_nodes(), 6);
+    assert!(root.borrow().get_node_by_value(8).is_none());
+    let three: NodeLink = root.borrow().get_node_by_value(3).unwrap();
+    assert!(three.borrow().right.is_none());
+    assert_eq!(value_of(&three.borrow().left), Some(6));
+    //the discarded subtree stays intact on its own
+    assert_eq!(seven.borrow().count_nodes(), 2);
+    assert!(!root.borrow_mut().discard_node_by_value(7));
+    assert!(!root.borrow_mut().discard_node_by_value(42));
+}
+
+#[test]
+fn traversals_and_shape_queries() {
+    let root: NodeLink = sample_tree();
+    let values = |iter: &mut dyn Iterator<Item = NodeLink>| -> Vec<i32> {
+        iter.map(|x| x.borrow().value).collect()
+    };
+    assert_eq!(
+        values(&mut Node::preorder(&root)),
+        vec![1, 2, 4, 5, 3, 6, 7, 8]
+    );
+    assert_eq!(
+        values(&mut Node::inorder(&root)),
+        vec![4, 2, 5, 1, 6, 3, 7, 8]
+    );
+    assert_eq!(
+        values(&mut Node::postorder(&root)),
+        vec![4, 5, 2, 6, 8, 7, 3, 1]
+    );
+    assert_eq!(
+        values(&mut Node::level_order(&root)),
+        vec![1, 2, 3, 4, 5, 6, 7, 8]
+    );
+    assert_eq!(Node::leaf_count(&root), 4);
+    assert!(!Node::is_perfect(&root));
+    assert!(Node::is_balanced(&root));
+    assert_eq!(Node::width_per_level(&root), vec![1, 2, 4, 1]);
+    let four: NodeLink = root.borrow().get_node_by_value(4).unwrap();
+    let eight: NodeLink = root.borrow().get_node_by_value(8).unwrap();
+    let ancestor: NodeLink = Node::lowest_common_ancestor(&four, &eight).unwrap();
+    assert!(Rc::ptr_eq(&ancestor, &root));
+    assert_eq!(Node::node_depth(&eight), 3);
+}
+
+#[test]
+fn parenthesized_text_and_the_bst_bridge() {
+    let root: NodeLink = sample_tree();
+    let text: String = Node::to_parenthesized(&root);
+    assert_eq!(text, "1(2(4,5),3(6,7(,8)))");
+    let parsed: NodeLink = Node::from_parenthesized(&text).unwrap().unwrap();
+    assert_eq!(*parsed.borrow(), *root.borrow());
+    assert!(!Node::is_bst(&root));
+
+    let bst: BstNodeLink = BstNode::build_from_sorted_keys(&[1, 2, 3, 4, 5]).unwrap();
+    let copy: NodeLink = Node::from_bst_nodelink(&bst).unwrap();
+    assert!(Node::is_bst(&copy));
+    assert_eq!(copy.borrow().count_nodes(), 5);
+    let back: BstNodeLink = BstNode::try_from_nodelink(&copy).unwrap();
+    assert_eq!(BstNode::inorder_keys(&back), vec![1, 2, 3, 4, 5]);
+}